@@ -8,6 +8,7 @@ extern crate nb;
 extern crate panic_halt;
 
 use crate::hal::clock::GenericClockController;
+use crate::hal::gpio::v2::{Pin, PA16};
 use crate::hal::pac::gclk::genctrl::SRC_A::DPLL0;
 use crate::hal::pac::gclk::pchctrl::GEN_A::GCLK2;
 use crate::hal::pac::Peripherals;
@@ -23,11 +24,12 @@ fn main() -> ! {
         &mut peripherals.OSCCTRL,
         &mut peripherals.NVMCTRL,
     );
-    let mut pins = hal::Pins::new(peripherals.PORT);
+    let pins = hal::Pins::new(peripherals.PORT);
 
-    let _gclk2 = clocks
+    let gclk2 = clocks
         .configure_gclk_divider_and_source(GCLK2, 12, DPLL0, false)
         .unwrap();
-    pins.d13.into_function_m(&mut pins.port);
+    let d13: Pin<PA16, _> = pins.d13.into();
+    let _gclk2_out = clocks.enable_gclk_out(&gclk2, d13, false, false);
     loop {}
 }
@@ -8,6 +8,7 @@ use panic_halt as _;
 use pygamer::{self as hal, entry, pac, Pins};
 
 use hal::clock::GenericClockController;
+use hal::gpio::v2::{Pin, PA16};
 use pac::gclk::genctrl::SRC_A::DPLL0;
 use pac::gclk::pchctrl::GEN_A::GCLK2;
 use pac::Peripherals;
@@ -22,12 +23,13 @@ fn main() -> ! {
         &mut peripherals.OSCCTRL,
         &mut peripherals.NVMCTRL,
     );
-    let mut pins = Pins::new(peripherals.PORT);
+    let pins = Pins::new(peripherals.PORT);
 
     //3mhz
-    let _gclk2 = clocks
+    let gclk2 = clocks
         .configure_gclk_divider_and_source(GCLK2, 40, DPLL0, false)
         .unwrap();
-    pins.d5.into_function_m(&mut pins.port);
+    let d5: Pin<PA16, _> = pins.d5.into();
+    let _gclk2_out = clocks.enable_gclk_out(&gclk2, d5, false, false);
     loop {}
 }
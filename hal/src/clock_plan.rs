@@ -0,0 +1,602 @@
+//! # Clock planning
+//!
+//! `const fn` calculations for GCLK dividers, SERCOM BAUD values and TC
+//! prescaler/period pairs. Rather than silently truncating, each plan holds
+//! the register values, the frequency they actually achieve and the relative
+//! error to the request, in parts per million. Register values are rounded to
+//! the nearest achievable setting.
+//!
+//! Every planner comes in two flavours. The `try_` functions return a
+//! [`PlanError`] for requests that can't be met at all. The others panic
+//! instead, which makes an impossible request a compile-time error when the
+//! plan is evaluated in a `const` context:
+//!
+//! ```
+//! use atsamd_hal::clock_plan::*;
+//! use atsamd_hal::time::Hertz;
+//!
+//! const SERCOM_FREQ: Hertz = Hertz(48_000_000);
+//!
+//! const UART: BaudPlan = uart_baud(SERCOM_FREQ, Hertz(115_200), Oversampling::X16);
+//! const SPI: SpiBaudPlan = spi_baud(SERCOM_FREQ, Hertz(8_000_000));
+//! const TICK: TcPlan = tc_frequency(Hertz(32_768), Hertz(10), CounterSize::Count16);
+//!
+//! // Fails to compile if the UART is more than 0.1% off
+//! const _: () = assert!(UART.is_within(1000));
+//! ```
+//!
+//! None of this touches the hardware, so it is also available without a chip
+//! feature and can be unit-tested on the host.
+
+use crate::time::{Hertz, Nanoseconds};
+
+/// Reasons a plan can't be made
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlanError {
+    /// A frequency or period was zero
+    Zero,
+    /// The requested frequency is too high, or the period too short
+    TooFast,
+    /// The requested frequency is too low, or the period too long
+    TooSlow,
+}
+
+impl PlanError {
+    /// Panic with a message describing the error
+    ///
+    /// In a `const` context, this is a compile-time error.
+    pub const fn panic(self) -> ! {
+        match self {
+            PlanError::Zero => panic!("clock plan: frequency or period is zero"),
+            PlanError::TooFast => panic!("clock plan: requested frequency is too high"),
+            PlanError::TooSlow => panic!("clock plan: requested frequency is too low"),
+        }
+    }
+}
+
+/// Divide and round to the nearest integer
+const fn div_round(n: u128, d: u128) -> u128 {
+    (n + d / 2) / d
+}
+
+/// Clamp `x` to `min..=max`
+const fn clamp(x: u128, min: u128, max: u128) -> u128 {
+    if x < min {
+        min
+    } else if x > max {
+        max
+    } else {
+        x
+    }
+}
+
+/// Relative error of `achieved_num / achieved_den` to `target_num /
+/// target_den`, in parts per million
+const fn error_ppm(
+    achieved_num: u128,
+    achieved_den: u128,
+    target_num: u128,
+    target_den: u128,
+) -> i32 {
+    let achieved = (achieved_num * target_den) as i128;
+    let target = (target_num * achieved_den) as i128;
+    ((achieved - target) * 1_000_000 / target) as i32
+}
+
+/// Returns `true` if the magnitude of `error_ppm` is at most `ppm`
+const fn within(error_ppm: i32, ppm: u32) -> bool {
+    error_ppm.unsigned_abs() <= ppm
+}
+
+//==============================================================================
+// GCLK
+//==============================================================================
+
+/// Largest divider of GCLK generators other than GCLK1
+///
+/// On the SAMD21, GCLK2 is further limited to 31.
+pub const GCLK_MAX_DIV: u32 = 255;
+
+/// Largest divider of GCLK1
+pub const GCLK1_MAX_DIV: u32 = 65535;
+
+/// GCLK generator divider
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GclkPlan {
+    /// Value of the `DIV` field, with `DIVSEL` cleared
+    pub div: u16,
+    /// Achieved generator frequency
+    pub freq: Hertz,
+    /// Relative error of `freq`, in parts per million
+    pub error_ppm: i32,
+}
+
+impl GclkPlan {
+    /// Returns `true` if the relative error is at most `ppm`
+    pub const fn is_within(&self, ppm: u32) -> bool {
+        within(self.error_ppm, ppm)
+    }
+}
+
+/// Plan a GCLK divider for `target` from a `source` clock
+///
+/// `max_div` is [`GCLK_MAX_DIV`] or [`GCLK1_MAX_DIV`], depending on the
+/// generator.
+pub const fn try_gclk_divider(
+    source: Hertz,
+    target: Hertz,
+    max_div: u32,
+) -> Result<GclkPlan, PlanError> {
+    if source.0 == 0 || target.0 == 0 {
+        return Err(PlanError::Zero);
+    }
+    if target.0 > source.0 {
+        return Err(PlanError::TooFast);
+    }
+    if (target.0 as u128) * (max_div as u128) < source.0 as u128 {
+        return Err(PlanError::TooSlow);
+    }
+    let div = div_round(source.0 as u128, target.0 as u128);
+    let div = clamp(div, 1, max_div as u128);
+    Ok(GclkPlan {
+        div: div as u16,
+        freq: Hertz((source.0 as u128 / div) as u32),
+        error_ppm: error_ppm(source.0 as u128, div, target.0 as u128, 1),
+    })
+}
+
+/// Plan a GCLK divider, panicking if it is impossible
+///
+/// See [`try_gclk_divider`].
+pub const fn gclk_divider(source: Hertz, target: Hertz, max_div: u32) -> GclkPlan {
+    match try_gclk_divider(source, target, max_div) {
+        Ok(plan) => plan,
+        Err(e) => e.panic(),
+    }
+}
+
+//==============================================================================
+// SERCOM
+//==============================================================================
+
+/// Number of samples per bit of an asynchronous USART
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Oversampling {
+    X16 = 16,
+    X8 = 8,
+    X3 = 3,
+}
+
+/// Asynchronous USART BAUD value in arithmetic mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BaudPlan {
+    /// Value of the `BAUD` register
+    pub baud: u16,
+    /// Achieved baud rate
+    pub freq: Hertz,
+    /// Relative error of `freq`, in parts per million
+    pub error_ppm: i32,
+}
+
+impl BaudPlan {
+    /// Returns `true` if the relative error is at most `ppm`
+    pub const fn is_within(&self, ppm: u32) -> bool {
+        within(self.error_ppm, ppm)
+    }
+}
+
+/// Plan an asynchronous USART BAUD value in arithmetic mode
+///
+/// The baud rate is `f_ref * (65536 - BAUD) / (S * 65536)`, where `S` is the
+/// oversampling.
+pub const fn try_uart_baud(
+    f_ref: Hertz,
+    baud: Hertz,
+    oversampling: Oversampling,
+) -> Result<BaudPlan, PlanError> {
+    if f_ref.0 == 0 || baud.0 == 0 {
+        return Err(PlanError::Zero);
+    }
+    let s = oversampling as u128;
+    if s * baud.0 as u128 > f_ref.0 as u128 {
+        return Err(PlanError::TooFast);
+    }
+    if 65536 * s * (baud.0 as u128) < f_ref.0 as u128 {
+        return Err(PlanError::TooSlow);
+    }
+    // 65536 - BAUD
+    let scale = div_round(65536 * s * baud.0 as u128, f_ref.0 as u128);
+    let scale = clamp(scale, 1, 65536);
+    let num = f_ref.0 as u128 * scale;
+    let den = s * 65536;
+    Ok(BaudPlan {
+        baud: (65536 - scale) as u16,
+        freq: Hertz((num / den) as u32),
+        error_ppm: error_ppm(num, den, baud.0 as u128, 1),
+    })
+}
+
+/// Plan an asynchronous USART BAUD value, panicking if it is impossible
+///
+/// See [`try_uart_baud`].
+pub const fn uart_baud(f_ref: Hertz, baud: Hertz, oversampling: Oversampling) -> BaudPlan {
+    match try_uart_baud(f_ref, baud, oversampling) {
+        Ok(plan) => plan,
+        Err(e) => e.panic(),
+    }
+}
+
+/// Asynchronous USART BAUD value in fractional mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FracBaudPlan {
+    /// Value of the 13-bit `BAUD` field
+    pub baud: u16,
+    /// Value of the 3-bit `FP` field
+    pub fp: u8,
+    /// Achieved baud rate
+    pub freq: Hertz,
+    /// Relative error of `freq`, in parts per million
+    pub error_ppm: i32,
+}
+
+impl FracBaudPlan {
+    /// Returns `true` if the relative error is at most `ppm`
+    pub const fn is_within(&self, ppm: u32) -> bool {
+        within(self.error_ppm, ppm)
+    }
+}
+
+/// Plan an asynchronous USART BAUD value in fractional mode
+///
+/// The baud rate is `f_ref / (S * (BAUD + FP / 8))`, where `S` is the
+/// oversampling. Only [`Oversampling::X16`] and [`Oversampling::X8`] support
+/// fractional mode.
+pub const fn try_uart_frac_baud(
+    f_ref: Hertz,
+    baud: Hertz,
+    oversampling: Oversampling,
+) -> Result<FracBaudPlan, PlanError> {
+    if f_ref.0 == 0 || baud.0 == 0 {
+        return Err(PlanError::Zero);
+    }
+    let s = oversampling as u128;
+    const MAX_X: u128 = 0x1FFF * 8 + 7;
+    if s * (baud.0 as u128) > f_ref.0 as u128 {
+        return Err(PlanError::TooFast);
+    }
+    if s * (baud.0 as u128) * MAX_X < 8 * f_ref.0 as u128 {
+        return Err(PlanError::TooSlow);
+    }
+    // 8 * BAUD + FP
+    let x = div_round(8 * f_ref.0 as u128, s * baud.0 as u128);
+    let x = clamp(x, 8, MAX_X);
+    let num = 8 * f_ref.0 as u128;
+    let den = s * x;
+    Ok(FracBaudPlan {
+        baud: (x / 8) as u16,
+        fp: (x % 8) as u8,
+        freq: Hertz((num / den) as u32),
+        error_ppm: error_ppm(num, den, baud.0 as u128, 1),
+    })
+}
+
+/// Plan a fractional USART BAUD value, panicking if it is impossible
+///
+/// See [`try_uart_frac_baud`].
+pub const fn uart_frac_baud(f_ref: Hertz, baud: Hertz, oversampling: Oversampling) -> FracBaudPlan {
+    match try_uart_frac_baud(f_ref, baud, oversampling) {
+        Ok(plan) => plan,
+        Err(e) => e.panic(),
+    }
+}
+
+/// SPI BAUD value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SpiBaudPlan {
+    /// Value of the `BAUD` register
+    pub baud: u8,
+    /// Achieved SCK frequency
+    pub freq: Hertz,
+    /// Relative error of `freq`, in parts per million
+    pub error_ppm: i32,
+}
+
+impl SpiBaudPlan {
+    /// Returns `true` if the relative error is at most `ppm`
+    pub const fn is_within(&self, ppm: u32) -> bool {
+        within(self.error_ppm, ppm)
+    }
+}
+
+/// Plan an SPI BAUD value
+///
+/// The SCK frequency is `f_ref / (2 * (BAUD + 1))`. The synchronous USART
+/// uses the same formula.
+pub const fn try_spi_baud(f_ref: Hertz, sck: Hertz) -> Result<SpiBaudPlan, PlanError> {
+    if f_ref.0 == 0 || sck.0 == 0 {
+        return Err(PlanError::Zero);
+    }
+    if 2 * (sck.0 as u128) > f_ref.0 as u128 {
+        return Err(PlanError::TooFast);
+    }
+    if 2 * 256 * (sck.0 as u128) < f_ref.0 as u128 {
+        return Err(PlanError::TooSlow);
+    }
+    // BAUD + 1
+    let n = div_round(f_ref.0 as u128, 2 * sck.0 as u128);
+    let n = clamp(n, 1, 256);
+    Ok(SpiBaudPlan {
+        baud: (n - 1) as u8,
+        freq: Hertz((f_ref.0 as u128 / (2 * n)) as u32),
+        error_ppm: error_ppm(f_ref.0 as u128, 2 * n, sck.0 as u128, 1),
+    })
+}
+
+/// Plan an SPI BAUD value, panicking if it is impossible
+///
+/// See [`try_spi_baud`].
+pub const fn spi_baud(f_ref: Hertz, sck: Hertz) -> SpiBaudPlan {
+    match try_spi_baud(f_ref, sck) {
+        Ok(plan) => plan,
+        Err(e) => e.panic(),
+    }
+}
+
+//==============================================================================
+// TC
+//==============================================================================
+
+/// TC prescaler values, in the order of the `PRESCALER` field
+pub const TC_PRESCALERS: [u16; 8] = [1, 2, 4, 8, 16, 64, 256, 1024];
+
+/// TC counter size
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CounterSize {
+    Count8,
+    Count16,
+    Count32,
+}
+
+impl CounterSize {
+    /// Largest TOP value of the counter
+    pub const fn max_top(self) -> u32 {
+        match self {
+            CounterSize::Count8 => u8::MAX as u32,
+            CounterSize::Count16 => u16::MAX as u32,
+            CounterSize::Count32 => u32::MAX,
+        }
+    }
+}
+
+/// TC prescaler and period
+///
+/// The counter counts from zero to `top` inclusive, so it wraps at
+/// `f_tc / (prescaler * (top + 1))`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TcPlan {
+    /// Index of the prescaler in [`TC_PRESCALERS`], the value of the
+    /// `PRESCALER` field
+    pub prescaler_index: u8,
+    /// Prescaler division
+    pub prescaler: u16,
+    /// TOP value, for the `PER` or `CC0` register
+    pub top: u32,
+    /// Achieved wrap frequency
+    pub freq: Hertz,
+    /// Relative error of the frequency or period that was requested, in parts
+    /// per million
+    pub error_ppm: i32,
+}
+
+impl TcPlan {
+    /// Returns `true` if the relative error is at most `ppm`
+    pub const fn is_within(&self, ppm: u32) -> bool {
+        within(self.error_ppm, ppm)
+    }
+}
+
+/// Find the smallest prescaler for `ticks_num / ticks_den` counts
+///
+/// Returns the prescaler index and number of counts.
+const fn tc_counts(
+    ticks_num: u128,
+    ticks_den: u128,
+    size: CounterSize,
+) -> Result<(usize, u128), PlanError> {
+    if ticks_num < ticks_den {
+        return Err(PlanError::TooFast);
+    }
+    let max_counts = size.max_top() as u128 + 1;
+    let mut i = 0;
+    while i < TC_PRESCALERS.len() {
+        let prescaler = TC_PRESCALERS[i] as u128;
+        if ticks_num <= max_counts * prescaler * ticks_den {
+            let counts = div_round(ticks_num, ticks_den * prescaler);
+            return Ok((i, clamp(counts, 1, max_counts)));
+        }
+        i += 1;
+    }
+    Err(PlanError::TooSlow)
+}
+
+/// Plan a TC prescaler and period for a wrap frequency
+///
+/// The smallest prescaler that fits is used, for the best resolution.
+pub const fn try_tc_frequency(
+    f_tc: Hertz,
+    freq: Hertz,
+    size: CounterSize,
+) -> Result<TcPlan, PlanError> {
+    if f_tc.0 == 0 || freq.0 == 0 {
+        return Err(PlanError::Zero);
+    }
+    let (i, counts) = match tc_counts(f_tc.0 as u128, freq.0 as u128, size) {
+        Ok(found) => found,
+        Err(e) => return Err(e),
+    };
+    let den = TC_PRESCALERS[i] as u128 * counts;
+    Ok(TcPlan {
+        prescaler_index: i as u8,
+        prescaler: TC_PRESCALERS[i],
+        top: (counts - 1) as u32,
+        freq: Hertz((f_tc.0 as u128 / den) as u32),
+        error_ppm: error_ppm(f_tc.0 as u128, den, freq.0 as u128, 1),
+    })
+}
+
+/// Plan a TC wrap frequency, panicking if it is impossible
+///
+/// See [`try_tc_frequency`].
+pub const fn tc_frequency(f_tc: Hertz, freq: Hertz, size: CounterSize) -> TcPlan {
+    match try_tc_frequency(f_tc, freq, size) {
+        Ok(plan) => plan,
+        Err(e) => e.panic(),
+    }
+}
+
+/// Plan a TC prescaler and period for a timeout
+///
+/// The smallest prescaler that fits is used, for the best resolution. The
+/// error is relative to the requested period.
+pub const fn try_tc_timeout(
+    f_tc: Hertz,
+    timeout: Nanoseconds,
+    size: CounterSize,
+) -> Result<TcPlan, PlanError> {
+    if f_tc.0 == 0 || timeout.0 == 0 {
+        return Err(PlanError::Zero);
+    }
+    let ticks_num = f_tc.0 as u128 * timeout.0 as u128;
+    let (i, counts) = match tc_counts(ticks_num, 1_000_000_000, size) {
+        Ok(found) => found,
+        Err(e) => return Err(e),
+    };
+    let den = TC_PRESCALERS[i] as u128 * counts;
+    Ok(TcPlan {
+        prescaler_index: i as u8,
+        prescaler: TC_PRESCALERS[i],
+        top: (counts - 1) as u32,
+        freq: Hertz((f_tc.0 as u128 / den) as u32),
+        // Achieved period is den / f_tc seconds
+        error_ppm: error_ppm(den * 1_000_000_000, f_tc.0 as u128, timeout.0 as u128, 1),
+    })
+}
+
+/// Plan a TC timeout, panicking if it is impossible
+///
+/// See [`try_tc_timeout`].
+pub const fn tc_timeout(f_tc: Hertz, timeout: Nanoseconds, size: CounterSize) -> TcPlan {
+    match try_tc_timeout(f_tc, timeout, size) {
+        Ok(plan) => plan,
+        Err(e) => e.panic(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gclk_divider_rounds_to_nearest() {
+        let plan = gclk_divider(Hertz(120_000_000), Hertz(7_000_000), GCLK_MAX_DIV);
+        assert_eq!(plan.div, 17);
+        assert_eq!(plan.freq, Hertz(7_058_823));
+        assert_eq!(plan.error_ppm, 8403);
+        assert!(!plan.is_within(1000));
+    }
+
+    #[test]
+    fn gclk_divider_limits() {
+        let source = Hertz(48_000_000);
+        assert_eq!(
+            try_gclk_divider(source, Hertz(96_000_000), GCLK_MAX_DIV),
+            Err(PlanError::TooFast)
+        );
+        assert_eq!(
+            try_gclk_divider(source, Hertz(32_768), GCLK_MAX_DIV),
+            Err(PlanError::TooSlow)
+        );
+        assert_eq!(
+            try_gclk_divider(source, Hertz(32_768), GCLK1_MAX_DIV).map(|p| p.div),
+            Ok(1465)
+        );
+        assert_eq!(
+            try_gclk_divider(source, Hertz(0), GCLK_MAX_DIV),
+            Err(PlanError::Zero)
+        );
+    }
+
+    #[test]
+    fn uart_baud_matches_datasheet_formula() {
+        let plan = uart_baud(Hertz(48_000_000), Hertz(115_200), Oversampling::X16);
+        assert_eq!(plan.baud, 63019);
+        assert_eq!(plan.freq, Hertz(115_219));
+        assert!(plan.is_within(200));
+
+        assert_eq!(
+            try_uart_baud(Hertz(48_000_000), Hertz(4_000_000), Oversampling::X16),
+            Err(PlanError::TooFast)
+        );
+    }
+
+    #[test]
+    fn uart_frac_baud_rounds_to_eighths() {
+        let plan = uart_frac_baud(Hertz(48_000_000), Hertz(115_200), Oversampling::X16);
+        assert_eq!((plan.baud, plan.fp), (26, 0));
+        assert_eq!(plan.freq, Hertz(115_384));
+        assert!(plan.is_within(2000));
+    }
+
+    #[test]
+    fn spi_baud_limits() {
+        let plan = spi_baud(Hertz(48_000_000), Hertz(8_000_000));
+        assert_eq!(plan.baud, 2);
+        assert_eq!(plan.error_ppm, 0);
+
+        assert_eq!(
+            try_spi_baud(Hertz(48_000_000), Hertz(50_000)),
+            Err(PlanError::TooSlow)
+        );
+        assert_eq!(
+            try_spi_baud(Hertz(48_000_000), Hertz(48_000_000)),
+            Err(PlanError::TooFast)
+        );
+    }
+
+    #[test]
+    fn tc_picks_smallest_prescaler() {
+        let plan = tc_frequency(Hertz(48_000_000), Hertz(1_000), CounterSize::Count16);
+        assert_eq!(plan.prescaler, 1);
+        assert_eq!(plan.top, 47_999);
+        assert_eq!(plan.error_ppm, 0);
+
+        let plan = tc_frequency(Hertz(48_000_000), Hertz(1), CounterSize::Count16);
+        assert_eq!(plan.prescaler, 1024);
+        assert_eq!(plan.top, 46_874);
+        assert_eq!(plan.prescaler_index, 7);
+
+        assert_eq!(
+            try_tc_frequency(Hertz(48_000_000), Hertz(1), CounterSize::Count8),
+            Err(PlanError::TooSlow)
+        );
+    }
+
+    #[test]
+    fn tc_timeout_matches_frequency() {
+        let from_freq = tc_frequency(Hertz(32_768), Hertz(10), CounterSize::Count16);
+        let from_timeout = tc_timeout(
+            Hertz(32_768),
+            Nanoseconds(100_000_000),
+            CounterSize::Count16,
+        );
+        assert_eq!(from_freq.top, from_timeout.top);
+        assert_eq!(from_freq.prescaler, from_timeout.prescaler);
+        assert_eq!(from_freq.error_ppm, -from_timeout.error_ppm);
+    }
+
+    #[test]
+    fn plans_are_const() {
+        const PLAN: TcPlan = tc_frequency(Hertz(32_768), Hertz(1), CounterSize::Count16);
+        assert_eq!(PLAN.top, 32_767);
+    }
+}
@@ -3,6 +3,8 @@
 use cortex_m::peripheral::syst::SystClkSource;
 use cortex_m::peripheral::SYST;
 
+#[cfg(feature = "min-samd51g")]
+use crate::clock::scaling::{ClockDomain, Retime};
 use crate::clock::GenericClockController;
 use crate::time::Hertz;
 use hal::blocking::delay::{DelayMs, DelayUs};
@@ -18,10 +20,12 @@ impl Delay {
     pub fn new(mut syst: SYST, clocks: &mut GenericClockController) -> Self {
         syst.set_clock_source(SystClkSource::Core);
 
-        Delay {
-            syst,
-            sysclock: clocks.gclk0().into(),
-        }
+        #[cfg(any(feature = "samd11", feature = "samd21"))]
+        let sysclock = clocks.gclk0().into();
+        #[cfg(feature = "min-samd51g")]
+        let sysclock = clocks.cpu_freq();
+
+        Delay { syst, sysclock }
     }
 
     /// Releases the system timer (SysTick) resource
@@ -30,6 +34,19 @@ impl Delay {
     }
 }
 
+#[cfg(feature = "min-samd51g")]
+impl Retime for Delay {
+    #[inline]
+    fn clock_domain(&self) -> ClockDomain {
+        ClockDomain::Cpu
+    }
+
+    #[inline]
+    fn retime(&mut self, _old: Hertz, new: Hertz) {
+        self.sysclock = new;
+    }
+}
+
 impl DelayMs<u32> for Delay {
     fn delay_ms(&mut self, ms: u32) {
         self.delay_us(ms * 1_000);
@@ -255,6 +255,18 @@ impl<Id: ChId> Channel<Id, Ready> {
         })
     }
 
+    /// Run in standby mode. When set, the channel keeps transferring data
+    /// while the chip is in standby, as long as its trigger source also runs
+    /// in standby. This is disabled by default.
+    #[cfg(feature = "min-samd51g")]
+    #[inline]
+    pub fn run_in_standby(&mut self, dmac: &mut DmaController, set: bool) {
+        let dmac = dmac.dmac();
+        self.with_chid(dmac, |d| {
+            d.chctrla.modify(|_, w| w.runstdby().bit(set));
+        })
+    }
+
     /// Start transfer on channel using the specified trigger source.
     ///
     /// # Return
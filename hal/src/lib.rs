@@ -72,6 +72,7 @@ macro_rules! dbgprint {
     ($($arg:tt)*) => {{}};
 }
 
+pub mod clock_plan;
 #[cfg(feature = "device")]
 pub mod delay;
 #[cfg(feature = "device")]
@@ -22,6 +22,8 @@ use crate::target_device::{
     rtc::mode2::CTRL as MODE2_CTRLA, PM,
 };
 
+pub mod monotonic;
+
 /// Datetime represents an RTC clock/calendar value.
 #[derive(Debug, Clone, Copy)]
 pub struct Datetime {
@@ -309,6 +311,69 @@ impl Rtc<ClockMode> {
     }
 }
 
+// --- Backup and General Purpose Registers (SAMx5x)
+
+/// Number of RTC backup registers
+#[cfg(feature = "min-samd51g")]
+pub const NUM_BACKUP_REGISTERS: usize = 8;
+
+/// Number of RTC general purpose registers
+#[cfg(feature = "min-samd51g")]
+pub const NUM_GP_REGISTERS: usize = 4;
+
+#[cfg(feature = "min-samd51g")]
+impl<Mode: RtcMode> Rtc<Mode> {
+    /// Returns the value of a backup register.
+    ///
+    /// The backup registers keep their values in Backup and while running
+    /// from VBAT, as long as the backup domain is powered. Panics if `index` is
+    /// not less than [`NUM_BACKUP_REGISTERS`].
+    #[inline]
+    pub fn backup_register(&self, index: usize) -> u32 {
+        self.rtc.mode0().bkup[index].read().bits()
+    }
+
+    /// Sets the value of a backup register.
+    ///
+    /// Panics if `index` is not less than [`NUM_BACKUP_REGISTERS`].
+    #[inline]
+    pub fn set_backup_register(&mut self, index: usize, value: u32) {
+        self.mode0().bkup[index].write(|w| unsafe { w.bits(value) });
+    }
+
+    /// Enables or disables the general purpose registers, in pairs.
+    ///
+    /// GP0 and GP1 take over the first compare/alarm register, and GP2 and
+    /// GP3 take over the second one. The `CountDown` implementation uses the
+    /// first compare register, so it can't be used while GP0 and GP1 are
+    /// enabled.
+    pub fn enable_gp_registers(&mut self, gp0_gp1: bool, gp2_gp3: bool) {
+        self.mode0().ctrlb.modify(|_, w| {
+            w.gp0en().bit(gp0_gp1);
+            w.gp2en().bit(gp2_gp3)
+        });
+        self.sync();
+    }
+
+    /// Returns the value of a general purpose register.
+    ///
+    /// Panics if `index` is not less than [`NUM_GP_REGISTERS`].
+    #[inline]
+    pub fn gp_register(&mut self, index: usize) -> u32 {
+        self.sync();
+        self.mode0().gp[index].read().bits()
+    }
+
+    /// Sets the value of a general purpose register.
+    ///
+    /// Panics if `index` is not less than [`NUM_GP_REGISTERS`].
+    #[inline]
+    pub fn set_gp_register(&mut self, index: usize, value: u32) {
+        self.mode0().gp[index].write(|w| unsafe { w.bits(value) });
+        self.sync();
+    }
+}
+
 // --- Timer / Counter Functionality
 
 impl Periodic for Rtc<Count32Mode> {}
@@ -0,0 +1,201 @@
+//! # Tickless monotonic timer
+//!
+//! [`RtcMonotonic`] turns the 32-bit RTC counter into a 64-bit monotonic
+//! timer. The upper 32 bits are counted in software on the RTC overflow
+//! interrupt, and compare register 0 raises an interrupt at a scheduled
+//! instant, so no periodic tick is needed.
+//!
+//! Unlike SysTick, the RTC keeps counting in Standby, as long as its clock
+//! source runs in Standby. The CPU can therefore sleep in Standby until the
+//! next scheduled instant, e.g. by calling `sleep::sleep` with
+//! `SleepMode::Standby` in a loop in the RTIC `idle` task.
+//!
+//! With the `rtic` feature, [`RtcMonotonic`] implements the RTIC
+//! `Monotonic` trait. Bind the RTC interrupt to the monotonic, e.g.
+//! `#[monotonic(binds = RTC, default = true)]`.
+//!
+//! Without RTIC, call [`RtcMonotonic::on_interrupt`] from the RTC interrupt
+//! handler, and schedule wake-ups with [`RtcMonotonic::set_alarm`].
+//!
+//! ```no_run
+//! # use atsamd_hal::rtc::{Count32Mode, Rtc};
+//! # use atsamd_hal::rtc::monotonic::RtcMonotonic;
+//! # fn f(rtc: Rtc<Count32Mode>) {
+//! let mut mono = RtcMonotonic::<32_768>::new(rtc);
+//! let now = mono.now();
+//! mono.set_alarm(now + 32_768);
+//! mono.enable_alarm();
+//! # }
+//! ```
+
+use super::{Count32Mode, Rtc};
+use crate::time::Hertz;
+
+/// A 64-bit monotonic timer running at `FREQ` Hz, built on the RTC
+///
+/// See the [module-level](self) documentation for more details.
+pub struct RtcMonotonic<const FREQ: u32> {
+    rtc: Rtc<Count32Mode>,
+    overflows: u32,
+}
+
+impl<const FREQ: u32> RtcMonotonic<FREQ> {
+    /// Start the monotonic timer from zero
+    ///
+    /// The prescaler is reset, so the counter runs at the RTC clock frequency,
+    /// and the overflow interrupt is enabled. The RTC interrupt must also be
+    /// unmasked in the NVIC.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the RTC clock frequency is not `FREQ`.
+    pub fn new(mut rtc: Rtc<Count32Mode>) -> Self {
+        assert_eq!(rtc.rtc_clock_freq.0, FREQ, "RTC clock frequency mismatch");
+
+        rtc.sync();
+        rtc.enable(false);
+        rtc.mode0_ctrla().modify(|_, w| {
+            // The counter must not be cleared on a compare match
+            w.matchclr().clear_bit();
+            #[cfg(feature = "min-samd51g")]
+            w.countsync().set_bit();
+            w.prescaler().div1()
+        });
+        rtc.sync();
+
+        let mut mono = Self { rtc, overflows: 0 };
+        // SAFETY: The RTC isn't shared with anything else
+        unsafe { mono.restart() };
+        mono
+    }
+
+    /// Reset the counter to zero and enable the overflow interrupt
+    ///
+    /// # Safety
+    ///
+    /// Instants taken before the reset are no longer comparable with new ones.
+    unsafe fn restart(&mut self) {
+        self.rtc.set_count32(0);
+        self.overflows = 0;
+        let mode0 = self.rtc.mode0();
+        mode0.intflag.write(|w| w.ovf().set_bit().cmp0().set_bit());
+        mode0.intenset.write(|w| w.ovf().set_bit());
+    }
+
+    /// Returns the counting frequency
+    #[inline]
+    pub fn freq(&self) -> Hertz {
+        Hertz(FREQ)
+    }
+
+    /// Returns the current number of ticks
+    pub fn now(&mut self) -> u64 {
+        cortex_m::interrupt::free(|_| {
+            let mut count = self.rtc.count32();
+            let mut overflows = self.overflows;
+            // The counter overflowed, but the interrupt hasn't been handled yet
+            if self.rtc.mode0().intflag.read().ovf().bit_is_set() {
+                overflows = overflows.wrapping_add(1);
+                // The overflow may have happened after reading the counter
+                count = self.rtc.count32();
+            }
+            ((overflows as u64) << 32) | count as u64
+        })
+    }
+
+    /// Schedule the compare interrupt at `instant`, in ticks
+    ///
+    /// Only the lower 32 bits are compared, so an alarm more than 2^32 ticks
+    /// ahead fires early, and an alarm in the past only fires once the counter
+    /// wraps around. Check [`now`](Self::now) after setting the alarm and when
+    /// it fires.
+    pub fn set_alarm(&mut self, instant: u64) {
+        self.rtc.sync();
+        self.rtc.mode0().comp[0].write(|w| unsafe { w.comp().bits(instant as u32) });
+        self.rtc.sync();
+    }
+
+    /// Clear the compare interrupt flag
+    #[inline]
+    pub fn clear_alarm(&mut self) {
+        self.rtc.mode0().intflag.write(|w| w.cmp0().set_bit());
+    }
+
+    /// Enable the compare interrupt
+    #[inline]
+    pub fn enable_alarm(&mut self) {
+        self.rtc.mode0().intenset.write(|w| w.cmp0().set_bit());
+    }
+
+    /// Disable the compare interrupt
+    #[inline]
+    pub fn disable_alarm(&mut self) {
+        self.rtc.mode0().intenclr.write(|w| w.cmp0().set_bit());
+    }
+
+    /// Handle the overflow interrupt
+    ///
+    /// Call this from the RTC interrupt handler. The compare interrupt flag is
+    /// left for the caller to clear.
+    pub fn on_interrupt(&mut self) {
+        let intflag = &self.rtc.mode0().intflag;
+        if intflag.read().ovf().bit_is_set() {
+            intflag.write(|w| w.ovf().set_bit());
+            self.overflows = self.overflows.wrapping_add(1);
+        }
+    }
+
+    /// Disable the interrupts and return the RTC
+    pub fn free(mut self) -> Rtc<Count32Mode> {
+        self.rtc
+            .mode0()
+            .intenclr
+            .write(|w| w.ovf().set_bit().cmp0().set_bit());
+        self.rtc
+    }
+}
+
+#[cfg(feature = "rtic")]
+impl<const FREQ: u32> rtic_monotonic::Monotonic for RtcMonotonic<FREQ> {
+    type Instant = fugit::TimerInstantU64<FREQ>;
+    type Duration = fugit::TimerDurationU64<FREQ>;
+
+    #[inline]
+    fn now(&mut self) -> Self::Instant {
+        Self::Instant::from_ticks(RtcMonotonic::now(self))
+    }
+
+    #[inline]
+    fn zero() -> Self::Instant {
+        Self::Instant::from_ticks(0)
+    }
+
+    unsafe fn reset(&mut self) {
+        self.restart();
+    }
+
+    #[inline]
+    fn set_compare(&mut self, instant: Self::Instant) {
+        self.set_alarm(instant.ticks());
+    }
+
+    #[inline]
+    fn clear_compare_flag(&mut self) {
+        self.clear_alarm();
+    }
+
+    #[inline]
+    fn on_interrupt(&mut self) {
+        RtcMonotonic::on_interrupt(self);
+    }
+
+    #[inline]
+    fn enable_timer(&mut self) {
+        self.enable_alarm();
+    }
+
+    #[inline]
+    fn disable_timer(&mut self) {
+        self.disable_alarm();
+    }
+}
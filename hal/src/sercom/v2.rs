@@ -2,7 +2,10 @@
 //!
 //! This module provides a new API for the SERCOM peripherals. So far, only the
 //! [`pads`] and [`spi`] modules have been updated, but it is expected that the
-//! `uart`, and `i2c` modules will eventually receive updates as well.
+//! `uart`, and `i2c` modules will eventually receive updates as well. The
+//! [`spi_bus`] module allows a single [`spi::Spi`] to be shared between
+//! multiple devices, and the [`dyn_sercom`] module allows a SERCOM to switch
+//! between the SPI, I2C and UART roles at run-time.
 
 use core::ops::Deref;
 
@@ -30,9 +33,12 @@ pub use crate::common::thumbv6m::sercom::v2::*;
 #[cfg(feature = "min-samd51g")]
 pub use crate::common::thumbv7em::sercom::v2::*;
 
+use crate::clock::bus::{self, ApbId};
 use crate::typelevel::Sealed;
 
+pub mod dyn_sercom;
 pub mod pads;
+pub mod spi_bus;
 pub mod spi_future;
 
 //==============================================================================
@@ -43,6 +49,8 @@ pub mod spi_future;
 pub trait Sercom: Sealed + Deref<Target = sercom0::RegisterBlock> {
     /// SERCOM number
     const NUM: usize;
+    /// ID of the corresponding APB clock
+    type ApbClkId: ApbId;
     /// Enable the corresponding APB clock
     fn enable_apb_clock(&mut self, ctrl: &APB_CLK_CTRL);
 }
@@ -56,6 +64,7 @@ macro_rules! sercom {
                 impl Sealed for Sercom#N {}
                 impl Sercom for Sercom#N {
                     const NUM: usize = N;
+                    type ApbClkId = bus::Sercom#N;
                     #[inline]
                     fn enable_apb_clock(&mut self, ctrl: &APB_CLK_CTRL) {
                         ctrl.$apbmask.modify(|_, w| w.[<sercom#N _>]().set_bit());
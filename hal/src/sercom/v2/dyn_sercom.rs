@@ -0,0 +1,740 @@
+//! Runtime-reconfigurable SERCOM
+//!
+//! Each SERCOM peripheral can act as an SPI, I2C or UART peripheral, but the
+//! role-specific drivers in this crate fix the role at compile-time. Switching
+//! roles requires calling `free` on the driver and building a new one from
+//! the PAC SERCOM.
+//!
+//! [`DynSercom`] instead owns the PAC SERCOM, along with a set of [`DynPads`],
+//! and switches between roles at run-time. The pins are validated and placed
+//! in the correct alternate mode when each [`DynPad`] is created. Each role
+//! then selects a subset of the [`DynPads`] by [`DynPadNum`]. Invalid
+//! combinations, or pads that are missing from the [`DynPads`], return an
+//! [`Error`] rather than being rejected at compile-time.
+//!
+//! ```
+//! use atsamd_hal::sercom::v2::dyn_sercom::{DynSercom, SpiPads, UartPads};
+//! use atsamd_hal::sercom::v2::pads::{DynIoSet, DynPad, DynPadNum, DynPads};
+//! use atsamd_hal::time::U32Ext;
+//! use embedded_hal::spi::MODE_0;
+//!
+//! let pad0 = DynPad::new(pins.pa08.into(), DynPadNum::Pad0, DynIoSet::IoSet1).ok()?;
+//! let pad1 = DynPad::new(pins.pa09.into(), DynPadNum::Pad1, DynIoSet::IoSet1).ok()?;
+//! let pad3 = DynPad::new(pins.pa11.into(), DynPadNum::Pad3, DynIoSet::IoSet1).ok()?;
+//! let mut dyn_pads = DynPads::new();
+//! dyn_pads.insert(pad0).ok()?;
+//! dyn_pads.insert(pad1).ok()?;
+//! dyn_pads.insert(pad3).ok()?;
+//! let mut sercom = DynSercom::new(&mclk, peripherals.SERCOM0, dyn_pads, clock.freq());
+//!
+//! let pads = SpiPads {
+//!     data_in: Some(DynPadNum::Pad3),
+//!     data_out: Some(DynPadNum::Pad0),
+//!     sclk: DynPadNum::Pad1,
+//! };
+//! sercom.configure_spi(pads, MODE_0, 4.mhz())?;
+//! // ... use the SERCOM as an SPI master ...
+//!
+//! let pads = UartPads {
+//!     rx: Some(DynPadNum::Pad1),
+//!     tx: Some(DynPadNum::Pad0),
+//! };
+//! sercom.configure_uart(pads, 115_200.hz())?;
+//! // ... use the SERCOM as a UART ...
+//! ```
+//!
+//! [`DynSercom`] implements the embedded HAL SPI, I2C and serial traits. Each
+//! implementation returns [`Error::WrongRole`] if the SERCOM is not currently
+//! configured in the corresponding [`Role`].
+//!
+//! [`DynSercom::free`] returns the [`DynPads`], so that the pins can be
+//! released with [`DynPad::free`].
+//!
+//! [`DynPad`]: super::pads::DynPad
+//! [`DynPad::free`]: super::pads::DynPad::free
+
+use embedded_hal::spi::{Phase, Polarity};
+use embedded_hal::{blocking, serial, spi};
+
+use crate::clock::bus::ApbClk;
+use crate::clock_plan::{try_spi_baud, try_uart_baud, Oversampling};
+use crate::target_device as pac;
+use crate::time::Hertz;
+
+#[cfg(any(feature = "samd11", feature = "samd21"))]
+use pac::sercom0::{I2CM, SPI, USART};
+#[cfg(feature = "min-samd51g")]
+use pac::sercom0::{I2CM, SPIM as SPI, USART_INT as USART};
+
+use super::pads::{DynPadNum, DynPads};
+use super::{Sercom, APB_CLK_CTRL};
+
+const BUS_STATE_IDLE: u8 = 1;
+const BUS_STATE_OWNED: u8 = 2;
+
+const MASTER_ACT_READ: u8 = 2;
+const MASTER_ACT_STOP: u8 = 3;
+
+//=============================================================================
+// Role
+//=============================================================================
+
+/// Role currently configured for a [`DynSercom`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    /// The SERCOM is reset and disabled
+    Disabled,
+    /// The SERCOM is an SPI master
+    Spi,
+    /// The SERCOM is an I2C master
+    I2c,
+    /// The SERCOM is an asynchronous UART
+    Uart,
+}
+
+//=============================================================================
+// Error
+//=============================================================================
+
+/// Error `enum` for [`DynSercom`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The requested pads can't be used together in the requested role, or
+    /// are missing from the [`DynPads`]
+    InvalidPads,
+    /// The requested baud rate can't be generated from the SERCOM clock
+    InvalidBaud,
+    /// The SERCOM is not configured in the role required by the operation
+    WrongRole,
+    /// The receive buffer overflowed
+    Overflow,
+    /// A UART frame error occurred
+    Frame,
+    /// A UART parity error occurred
+    Parity,
+    /// I2C bus arbitration was lost
+    ArbitrationLost,
+    /// An I2C bus error occurred
+    BusError,
+    /// The I2C slave did not acknowledge
+    Nack,
+    /// An I2C timeout occurred
+    Timeout,
+}
+
+//=============================================================================
+// Pad descriptions
+//=============================================================================
+
+/// Pad assignment for the SPI role
+///
+/// At least one of `data_in` and `data_out` must be provided. The receiver is
+/// only enabled if `data_in` is provided. Every selected pad must be present
+/// in the [`DynSercom`]'s [`DynPads`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SpiPads {
+    /// Data input pad (MISO in master mode)
+    pub data_in: Option<DynPadNum>,
+    /// Data output pad (MOSI in master mode)
+    pub data_out: Option<DynPadNum>,
+    /// Serial clock pad
+    pub sclk: DynPadNum,
+}
+
+impl SpiPads {
+    /// Return the `(DIPO, DOPO)` register values, if the pads are valid
+    fn dipo_dopo<S: Sercom>(&self, pads: &DynPads<S>) -> Option<(u8, u8)> {
+        if !has_pads(pads, &[self.data_in, self.data_out, Some(self.sclk)]) {
+            return None;
+        }
+        let dopo = dopo(self.data_out, self.sclk)?;
+        let dipo = match self.data_in {
+            Some(pad) if pad == self.sclk || Some(pad) == self.data_out => return None,
+            Some(pad) => pad as u8,
+            None if self.data_out.is_none() => return None,
+            None => 0,
+        };
+        Some((dipo, dopo))
+    }
+}
+
+/// Return the DOPO value for a given data out and SCK pad
+#[cfg(feature = "min-samd51g")]
+fn dopo(data_out: Option<DynPadNum>, sclk: DynPadNum) -> Option<u8> {
+    use DynPadNum::*;
+    match (data_out, sclk) {
+        (Some(Pad0), Pad1) | (None, Pad1) => Some(0),
+        (Some(Pad3), Pad1) => Some(2),
+        _ => None,
+    }
+}
+
+/// Return the DOPO value for a given data out and SCK pad
+#[cfg(any(feature = "samd11", feature = "samd21"))]
+fn dopo(data_out: Option<DynPadNum>, sclk: DynPadNum) -> Option<u8> {
+    use DynPadNum::*;
+    match (data_out, sclk) {
+        (Some(Pad0), Pad1) | (None, Pad1) => Some(0),
+        (Some(Pad2), Pad3) | (None, Pad3) => Some(1),
+        (Some(Pad3), Pad1) => Some(2),
+        (Some(Pad0), Pad3) => Some(3),
+        _ => None,
+    }
+}
+
+/// Pad assignment for the UART role
+///
+/// At least one of `rx` and `tx` must be provided. The receiver and
+/// transmitter are only enabled if the corresponding pad is provided. Every
+/// selected pad must be present in the [`DynSercom`]'s [`DynPads`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UartPads {
+    /// Receive pad
+    pub rx: Option<DynPadNum>,
+    /// Transmit pad
+    pub tx: Option<DynPadNum>,
+}
+
+impl UartPads {
+    /// Return the `(RXPO, TXPO)` register values, if the pads are valid
+    fn rxpo_txpo<S: Sercom>(&self, pads: &DynPads<S>) -> Option<(u8, u8)> {
+        if !has_pads(pads, &[self.rx, self.tx]) {
+            return None;
+        }
+        let txpo = match self.tx {
+            Some(pad) => txpo(pad)?,
+            None => 0,
+        };
+        let rxpo = match self.rx {
+            Some(pad) if Some(pad) == self.tx => return None,
+            Some(pad) => pad as u8,
+            None if self.tx.is_none() => return None,
+            None => 0,
+        };
+        Some((rxpo, txpo))
+    }
+}
+
+/// Return the TXPO value for a given TX pad
+#[cfg(feature = "min-samd51g")]
+fn txpo(tx: DynPadNum) -> Option<u8> {
+    match tx {
+        DynPadNum::Pad0 => Some(0),
+        _ => None,
+    }
+}
+
+/// Return the TXPO value for a given TX pad
+#[cfg(any(feature = "samd11", feature = "samd21"))]
+fn txpo(tx: DynPadNum) -> Option<u8> {
+    match tx {
+        DynPadNum::Pad0 => Some(0),
+        DynPadNum::Pad2 => Some(1),
+        _ => None,
+    }
+}
+
+/// Return `true` if every selected pad is present in the [`DynPads`]
+fn has_pads<S: Sercom>(pads: &DynPads<S>, nums: &[Option<DynPadNum>]) -> bool {
+    nums.iter().flatten().all(|&num| pads.contains(num))
+}
+
+//=============================================================================
+// DynSercom
+//=============================================================================
+
+/// A SERCOM whose role can be changed at run-time
+///
+/// See the [module-level](self) documentation for more details.
+pub struct DynSercom<S: Sercom> {
+    sercom: S,
+    pads: DynPads<S>,
+    apb_clk: ApbClk<S::ApbClkId>,
+    freq: Hertz,
+    role: Role,
+}
+
+impl<S: Sercom> DynSercom<S> {
+    /// Create a new [`DynSercom`]
+    ///
+    /// Enable the APB clock and reset the SERCOM. The [`DynSercom`] takes
+    /// ownership of the [`DynPads`], which each role selects from. `freq` is
+    /// the frequency of the SERCOM core clock, which must already be
+    /// configured. The SERCOM starts in the [`Role::Disabled`] role. The APB
+    /// clock is gated off again when the [`DynSercom`] is freed or dropped.
+    #[inline]
+    pub fn new(
+        apb_clk_ctrl: &APB_CLK_CTRL,
+        sercom: S,
+        pads: DynPads<S>,
+        freq: impl Into<Hertz>,
+    ) -> Self {
+        // SAFETY: We own the SERCOM, so no other token exists for its clock
+        let apb_clk = unsafe { ApbClk::enable(apb_clk_ctrl) };
+        let mut dyn_sercom = DynSercom {
+            sercom,
+            pads,
+            apb_clk,
+            freq: freq.into(),
+            role: Role::Disabled,
+        };
+        dyn_sercom.disable();
+        dyn_sercom
+    }
+
+    /// Return the currently configured [`Role`]
+    #[inline]
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    /// Return a reference to the owned [`DynPads`]
+    #[inline]
+    pub fn pads(&self) -> &DynPads<S> {
+        &self.pads
+    }
+
+    /// Replace the owned [`DynPads`] and return the previous set
+    ///
+    /// The SERCOM is reset to the [`Role::Disabled`] role first, so that no
+    /// role is left using a pad that has been removed.
+    #[inline]
+    pub fn swap_pads(&mut self, pads: DynPads<S>) -> DynPads<S> {
+        self.disable();
+        core::mem::replace(&mut self.pads, pads)
+    }
+
+    /// Reset the SERCOM and return to the [`Role::Disabled`] role
+    #[inline]
+    pub fn disable(&mut self) {
+        let spi = self.spi();
+        spi.ctrla.modify(|_, w| w.swrst().set_bit());
+        while spi.syncbusy.read().swrst().bit_is_set() || spi.ctrla.read().swrst().bit_is_set() {}
+        self.role = Role::Disabled;
+    }
+
+    /// Configure the SERCOM as an SPI master
+    ///
+    /// The pads and baud rate are validated before the SERCOM is touched. On
+    /// error, the SERCOM keeps its previous role. Characters are 8 bits long
+    /// and transmitted MSB first.
+    pub fn configure_spi(
+        &mut self,
+        pads: SpiPads,
+        mode: spi::Mode,
+        baud: impl Into<Hertz>,
+    ) -> Result<(), Error> {
+        let (dipo, dopo) = pads.dipo_dopo(&self.pads).ok_or(Error::InvalidPads)?;
+        let baud = self.sync_baud(baud.into())?;
+        self.disable();
+        let spi = self.spi();
+        spi.ctrla.modify(|_, w| w.mode().spi_master());
+        spi.ctrlb.modify(|_, w| {
+            unsafe { w.chsize().bits(0) };
+            w.rxen().bit(pads.data_in.is_some())
+        });
+        while spi.syncbusy.read().ctrlb().bit_is_set() {}
+        spi.baud.write(|w| unsafe { w.baud().bits(baud) });
+        spi.ctrla.modify(|_, w| {
+            match mode.polarity {
+                Polarity::IdleLow => w.cpol().clear_bit(),
+                Polarity::IdleHigh => w.cpol().set_bit(),
+            };
+            match mode.phase {
+                Phase::CaptureOnFirstTransition => w.cpha().clear_bit(),
+                Phase::CaptureOnSecondTransition => w.cpha().set_bit(),
+            };
+            unsafe {
+                w.dipo().bits(dipo);
+                w.dopo().bits(dopo);
+            }
+            w.dord().clear_bit()
+        });
+        spi.ctrla.modify(|_, w| w.enable().set_bit());
+        while spi.syncbusy.read().enable().bit_is_set() {}
+        self.role = Role::Spi;
+        Ok(())
+    }
+
+    /// Configure the SERCOM as an I2C master
+    ///
+    /// In I2C mode, SDA is always on [`DynPadNum::Pad0`] and SCL is always on
+    /// [`DynPadNum::Pad1`], so both must be present in the [`DynPads`]. The
+    /// pads and baud rate are validated before the SERCOM is touched. On
+    /// error, the SERCOM keeps its previous role.
+    pub fn configure_i2c(&mut self, baud: impl Into<Hertz>) -> Result<(), Error> {
+        let sda_scl = [Some(DynPadNum::Pad0), Some(DynPadNum::Pad1)];
+        if !has_pads(&self.pads, &sda_scl) {
+            return Err(Error::InvalidPads);
+        }
+        let baud = self.sync_baud(baud.into())?;
+        self.disable();
+        let i2cm = self.i2cm();
+        i2cm.ctrla.modify(|_, w| w.mode().i2c_master());
+        while i2cm.syncbusy.read().enable().bit_is_set() {}
+        i2cm.baud.modify(|_, w| unsafe { w.baud().bits(baud) });
+        i2cm.ctrla.modify(|_, w| w.enable().set_bit());
+        while i2cm.syncbusy.read().enable().bit_is_set() {}
+        i2cm.status
+            .modify(|_, w| unsafe { w.busstate().bits(BUS_STATE_IDLE) });
+        while i2cm.syncbusy.read().sysop().bit_is_set() {}
+        self.role = Role::I2c;
+        Ok(())
+    }
+
+    /// Configure the SERCOM as an asynchronous UART
+    ///
+    /// The pads and baud rate are validated before the SERCOM is touched. On
+    /// error, the SERCOM keeps its previous role. Frames use 8 data bits, no
+    /// parity and one stop bit, transmitted LSB first.
+    pub fn configure_uart(&mut self, pads: UartPads, baud: impl Into<Hertz>) -> Result<(), Error> {
+        let (rxpo, txpo) = pads.rxpo_txpo(&self.pads).ok_or(Error::InvalidPads)?;
+        let baud = try_uart_baud(self.freq, baud.into(), Oversampling::X16)
+            .map_err(|_| Error::InvalidBaud)?
+            .baud;
+        self.disable();
+        let usart = self.usart();
+        usart.ctrla.modify(|_, w| {
+            w.dord().set_bit();
+            unsafe {
+                w.rxpo().bits(rxpo);
+                w.txpo().bits(txpo);
+                w.sampr().bits(0);
+                w.form().bits(0);
+            }
+            w.mode().usart_int_clk();
+            w.cmode().clear_bit()
+        });
+        usart.baud().modify(|_, w| unsafe { w.baud().bits(baud) });
+        usart.ctrlb.modify(|_, w| {
+            w.sbmode().clear_bit();
+            unsafe { w.chsize().bits(0) };
+            w.txen().bit(pads.tx.is_some());
+            w.rxen().bit(pads.rx.is_some())
+        });
+        while usart.syncbusy.read().ctrlb().bit_is_set() {}
+        usart.ctrla.modify(|_, w| w.enable().set_bit());
+        while usart.syncbusy.read().enable().bit_is_set() {}
+        self.role = Role::Uart;
+        Ok(())
+    }
+
+    /// Reset the SERCOM, gate off its APB clock and return the PAC SERCOM
+    /// and the [`DynPads`]
+    #[inline]
+    pub fn free(mut self) -> (S, DynPads<S>) {
+        self.disable();
+        self.apb_clk.disable();
+        (self.sercom, self.pads)
+    }
+
+    /// Calculate the BAUD register value for SPI and I2C
+    ///
+    /// Both roles use `f_baud = f_ref / (2 * (BAUD + 1))`.
+    #[inline]
+    fn sync_baud(&self, baud: Hertz) -> Result<u8, Error> {
+        try_spi_baud(self.freq, baud)
+            .map(|plan| plan.baud)
+            .map_err(|_| Error::InvalidBaud)
+    }
+
+    #[inline]
+    fn check_role(&self, role: Role) -> Result<(), Error> {
+        if self.role == role {
+            Ok(())
+        } else {
+            Err(Error::WrongRole)
+        }
+    }
+
+    #[inline]
+    fn spi(&self) -> &SPI {
+        #[cfg(any(feature = "samd11", feature = "samd21"))]
+        let spi = self.sercom.spi();
+        #[cfg(feature = "min-samd51g")]
+        let spi = self.sercom.spim();
+        spi
+    }
+
+    #[inline]
+    fn i2cm(&self) -> &I2CM {
+        self.sercom.i2cm()
+    }
+
+    #[inline]
+    fn usart(&self) -> &USART {
+        #[cfg(any(feature = "samd11", feature = "samd21"))]
+        let usart = self.sercom.usart();
+        #[cfg(feature = "min-samd51g")]
+        let usart = self.sercom.usart_int();
+        usart
+    }
+}
+
+//=============================================================================
+// I2C helpers
+//=============================================================================
+
+impl<S: Sercom> DynSercom<S> {
+    fn i2c_wait_bus(&self) {
+        loop {
+            match self.i2cm().status.read().busstate().bits() {
+                BUS_STATE_IDLE | BUS_STATE_OWNED => break,
+                _ => continue,
+            }
+        }
+    }
+
+    fn i2c_status(&self) -> Result<(), Error> {
+        let status = self.i2cm().status.read();
+        if status.arblost().bit_is_set() {
+            Err(Error::ArbitrationLost)
+        } else if status.buserr().bit_is_set() {
+            Err(Error::BusError)
+        } else if status.rxnack().bit_is_set() {
+            Err(Error::Nack)
+        } else if status.lowtout().bit_is_set()
+            || status.sexttout().bit_is_set()
+            || status.mexttout().bit_is_set()
+        {
+            Err(Error::Timeout)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn i2c_start_write(&self, addr: u8) -> Result<(), Error> {
+        self.i2c_wait_bus();
+        self.i2cm()
+            .addr
+            .write(|w| unsafe { w.addr().bits((addr as u16) << 1) });
+        while self.i2cm().intflag.read().mb().bit_is_clear() {}
+        self.i2c_status()
+    }
+
+    fn i2c_start_read(&self, addr: u8) -> Result<(), Error> {
+        self.i2c_wait_bus();
+        self.i2cm().intflag.modify(|_, w| w.error().clear_bit());
+        self.i2cm()
+            .addr
+            .write(|w| unsafe { w.addr().bits(((addr as u16) << 1) | 1) });
+        loop {
+            let intflag = self.i2cm().intflag.read();
+            // If arbitration was lost, it will be signalled via the mb bit
+            if intflag.mb().bit_is_set() {
+                return Err(Error::ArbitrationLost);
+            }
+            if intflag.sb().bit_is_set() || intflag.error().bit_is_set() {
+                break;
+            }
+        }
+        self.i2c_status()
+    }
+
+    fn i2c_cmd(&self, cmd: u8) {
+        self.i2cm().ctrlb.modify(|_, w| {
+            // Clear bit means send ACK
+            w.ackact().clear_bit();
+            unsafe { w.cmd().bits(cmd) }
+        });
+        while self.i2cm().syncbusy.read().sysop().bit_is_set() {}
+    }
+
+    fn i2c_send(&self, bytes: &[u8]) -> Result<(), Error> {
+        for b in bytes {
+            self.i2cm().data.write(|w| unsafe { w.bits(*b) });
+            loop {
+                let intflag = self.i2cm().intflag.read();
+                if intflag.mb().bit_is_set() || intflag.error().bit_is_set() {
+                    break;
+                }
+            }
+            self.i2c_status()?;
+        }
+        Ok(())
+    }
+
+    fn i2c_fill(&self, buffer: &mut [u8]) {
+        for (i, dest) in buffer.iter_mut().enumerate() {
+            if i != 0 {
+                // ACK the last byte so that we can receive another one
+                self.i2c_cmd(MASTER_ACT_READ);
+            }
+            while self.i2cm().intflag.read().sb().bit_is_clear() {}
+            *dest = self.i2cm().data.read().bits();
+        }
+        // Send NACK on the next command to stop the slave transmitting
+        self.i2cm().ctrlb.modify(|_, w| w.ackact().set_bit());
+    }
+
+    /// Run an I2C transaction and always finish with a STOP condition
+    fn i2c_transaction(&self, f: impl FnOnce() -> Result<(), Error>) -> Result<(), Error> {
+        self.check_role(Role::I2c)?;
+        let result = f();
+        self.i2cm()
+            .ctrlb
+            .modify(|_, w| unsafe { w.cmd().bits(MASTER_ACT_STOP) });
+        while self.i2cm().syncbusy.read().sysop().bit_is_set() {}
+        result
+    }
+}
+
+//=============================================================================
+// embedded-hal implementations
+//=============================================================================
+
+impl<S: Sercom> spi::FullDuplex<u8> for DynSercom<S> {
+    type Error = Error;
+
+    fn read(&mut self) -> nb::Result<u8, Error> {
+        self.check_role(Role::Spi)?;
+        let spi = self.spi();
+        if spi.status.read().bufovf().bit_is_set() {
+            spi.status.write(|w| w.bufovf().set_bit());
+            return Err(nb::Error::Other(Error::Overflow));
+        }
+        if spi.intflag.read().rxc().bit_is_set() {
+            Ok(spi.data.read().data().bits() as u8)
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
+    fn send(&mut self, byte: u8) -> nb::Result<(), Error> {
+        self.check_role(Role::Spi)?;
+        let spi = self.spi();
+        if spi.intflag.read().dre().bit_is_set() {
+            spi.data.write(|w| unsafe { w.data().bits(byte.into()) });
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
+impl<S: Sercom> blocking::spi::Transfer<u8> for DynSercom<S> {
+    type Error = Error;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Error> {
+        self.check_role(Role::Spi)?;
+        let rxen = self.spi().ctrlb.read().rxen().bit_is_set();
+        for word in words.iter_mut() {
+            nb::block!(spi::FullDuplex::send(self, *word))?;
+            if rxen {
+                *word = nb::block!(spi::FullDuplex::read(self))?;
+            }
+        }
+        Ok(words)
+    }
+}
+
+impl<S: Sercom> blocking::spi::Write<u8> for DynSercom<S> {
+    type Error = Error;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Error> {
+        self.check_role(Role::Spi)?;
+        for word in words {
+            nb::block!(spi::FullDuplex::send(self, *word))?;
+        }
+        let spi = self.spi();
+        while spi.intflag.read().txc().bit_is_clear() {}
+        // Discard any received data
+        while spi.intflag.read().rxc().bit_is_set() {
+            spi.data.read();
+        }
+        spi.status.write(|w| w.bufovf().set_bit());
+        Ok(())
+    }
+}
+
+impl<S: Sercom> blocking::i2c::Write for DynSercom<S> {
+    type Error = Error;
+
+    fn write(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Error> {
+        self.i2c_transaction(|| {
+            self.i2c_start_write(addr)?;
+            self.i2c_send(bytes)
+        })
+    }
+}
+
+impl<S: Sercom> blocking::i2c::Read for DynSercom<S> {
+    type Error = Error;
+
+    fn read(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Error> {
+        self.i2c_transaction(|| {
+            self.i2c_start_read(addr)?;
+            self.i2c_fill(buffer);
+            Ok(())
+        })
+    }
+}
+
+impl<S: Sercom> blocking::i2c::WriteRead for DynSercom<S> {
+    type Error = Error;
+
+    fn write_read(&mut self, addr: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), Error> {
+        self.i2c_transaction(|| {
+            self.i2c_start_write(addr)?;
+            self.i2c_send(bytes)?;
+            self.i2c_start_read(addr)?;
+            self.i2c_fill(buffer);
+            Ok(())
+        })
+    }
+}
+
+impl<S: Sercom> serial::Read<u8> for DynSercom<S> {
+    type Error = Error;
+
+    fn read(&mut self) -> nb::Result<u8, Error> {
+        self.check_role(Role::Uart)?;
+        let usart = self.usart();
+        let status = usart.status.read();
+        if status.bufovf().bit_is_set() {
+            usart.status.write(|w| w.bufovf().set_bit());
+            return Err(nb::Error::Other(Error::Overflow));
+        }
+        if status.ferr().bit_is_set() {
+            usart.data.read();
+            usart.status.write(|w| w.ferr().set_bit());
+            return Err(nb::Error::Other(Error::Frame));
+        }
+        if status.perr().bit_is_set() {
+            usart.data.read();
+            usart.status.write(|w| w.perr().set_bit());
+            return Err(nb::Error::Other(Error::Parity));
+        }
+        if usart.intflag.read().rxc().bit_is_set() {
+            Ok(usart.data.read().bits() as u8)
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
+impl<S: Sercom> serial::Write<u8> for DynSercom<S> {
+    type Error = Error;
+
+    fn write(&mut self, word: u8) -> nb::Result<(), Error> {
+        self.check_role(Role::Uart)?;
+        let usart = self.usart();
+        if usart.intflag.read().dre().bit_is_set() {
+            usart.data.write(|w| unsafe { w.bits(word.into()) });
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Error> {
+        self.check_role(Role::Uart)?;
+        if self.usart().intflag.read().txc().bit_is_set() {
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
+impl<S: Sercom> blocking::serial::write::Default<u8> for DynSercom<S> {}
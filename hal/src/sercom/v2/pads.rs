@@ -67,10 +67,31 @@
 //! The actual implementations of [`Map`] are chip specific, so they are not
 //! found in this module. They are included in the [`pad_map`] module.
 //!
+//! # Dynamic pads
+//!
+//! When the pinout is only known at run-time, e.g. when it is read from a
+//! configuration store, use [`DynPad`] and [`DynPads`] instead. A [`DynPad`]
+//! wraps a [`DynPin`] and is validated at run-time against the same
+//! [`pad_map`] data used by the [`Map`] implementations. Invalid combinations
+//! return a [`PadError`] rather than failing to compile. The rejected
+//! [`DynPin`] or [`DynPad`] is returned alongside the error, so it is not lost.
+//!
+//! ```
+//! let pin: DynPin = pins.pa08.into();
+//! let pad = DynPad::<Sercom0>::new(pin, DynPadNum::Pad0, DynIoSet::IoSet1)
+//!     .map_err(|(err, _pin)| err)?;
+//! let mut pads = DynPads::new();
+//! pads.insert(pad).map_err(|(err, _pad)| err)?;
+//! ```
+//!
+//! On SAMD11 and SAMD21 chips, [`DynPad::new`] does not take a `DynIoSet`.
+//!
 //! [`pad_map`]: crate::sercom::v2::pad_map
 
+use core::marker::PhantomData;
 use core::mem::transmute;
 
+use super::pad_map::dyn_map;
 use super::Sercom;
 use crate::gpio::v2::*;
 use crate::typelevel::{Is, NoneT, Sealed};
@@ -79,8 +100,23 @@ use crate::typelevel::{Is, NoneT, Sealed};
 //  PadNum
 //==============================================================================
 
+/// Value-level `enum` representing a SERCOM pad number
+///
+/// This is the value-level equivalent of the [`PadNum`] types. It is used
+/// wherever pads must be identified at run-time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DynPadNum {
+    Pad0,
+    Pad1,
+    Pad2,
+    Pad3,
+}
+
 /// Type-level `enum` representing a SERCOM pad number
-pub trait PadNum: Sealed {}
+pub trait PadNum: Sealed {
+    /// Corresponding [`DynPadNum`]
+    const DYN: DynPadNum;
+}
 
 macro_rules! padnum {
     ( $( $PadNum:ident),+ ) => {
@@ -88,7 +124,9 @@ macro_rules! padnum {
             /// Represents the corresponding SERCOM pad number
             pub enum $PadNum {}
             impl Sealed for $PadNum {}
-            impl PadNum for $PadNum {}
+            impl PadNum for $PadNum {
+                const DYN: DynPadNum = DynPadNum::$PadNum;
+            }
         )+
     };
 }
@@ -99,9 +137,27 @@ padnum!(Pad0, Pad1, Pad2, Pad3);
 //  IoSet
 //==============================================================================
 
+/// Value-level `enum` representing a SERCOM IOSET configuration
+///
+/// This is the value-level equivalent of the [`IoSet`] types.
+#[cfg(feature = "min-samd51g")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DynIoSet {
+    IoSet1,
+    IoSet2,
+    IoSet3,
+    IoSet4,
+    #[cfg(feature = "min-samd51j")]
+    IoSet5,
+    IoSet6,
+}
+
 /// Type-level `enum` representing a SERCOM IOSET configuration
 #[cfg(feature = "min-samd51g")]
-pub trait IoSet: Sealed {}
+pub trait IoSet: Sealed {
+    /// Corresponding [`DynIoSet`]
+    const DYN: DynIoSet;
+}
 
 #[cfg(feature = "min-samd51g")]
 macro_rules! ioset {
@@ -110,7 +166,9 @@ macro_rules! ioset {
             /// Represents the corresponding IOSET
             pub enum $IoSet {}
             impl Sealed for $IoSet {}
-            impl IoSet for $IoSet {}
+            impl IoSet for $IoSet {
+                const DYN: DynIoSet = DynIoSet::$IoSet;
+            }
         )+
     };
 }
@@ -417,3 +475,229 @@ where
         Pad::new(pin)
     }
 }
+
+//==============================================================================
+//  Error
+//==============================================================================
+
+/// Error `enum` for [`DynPad`] and [`DynPads`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PadError {
+    /// The pin can't be used as the requested SERCOM pad
+    InvalidPad,
+    /// The [`DynPads`] already contains a pad with the same [`DynPadNum`]
+    DuplicatePad,
+    /// The [`DynPad`] belongs to a different IOSET than the other pads
+    #[cfg(feature = "min-samd51g")]
+    IoSetMismatch,
+}
+
+//==============================================================================
+//  DynPad
+//==============================================================================
+
+/// Value-level equivalent of a [`Pad`]
+///
+/// A [`DynPad`] wraps a [`DynPin`] that has been validated, at run-time, as a
+/// pad for the [`Sercom`] `S`. Upon creation, the [`DynPin`] is placed in the
+/// correct [`DynAlternate`] mode.
+///
+/// Every [`Pad`] can be converted into a [`DynPad`] using [`From`]/[`Into`].
+pub struct DynPad<S: Sercom> {
+    num: DynPadNum,
+    #[cfg(feature = "min-samd51g")]
+    ioset: DynIoSet,
+    pin: DynPin,
+    sercom: PhantomData<S>,
+}
+
+impl<S: Sercom> DynPad<S> {
+    /// Create a new [`DynPad`] from a [`DynPin`]
+    ///
+    /// Return [`PadError::InvalidPad`] and the unchanged [`DynPin`] if it
+    /// can't act as the given pad for the given [`DynIoSet`].
+    #[cfg(feature = "min-samd51g")]
+    #[inline]
+    pub fn new(
+        mut pin: DynPin,
+        num: DynPadNum,
+        ioset: DynIoSet,
+    ) -> Result<Self, (PadError, DynPin)> {
+        match dyn_map(S::NUM, ioset, num) {
+            Some((id, mode)) if id == pin.id() => {
+                pin.into_alternate(mode);
+                Ok(DynPad {
+                    num,
+                    ioset,
+                    pin,
+                    sercom: PhantomData,
+                })
+            }
+            _ => Err((PadError::InvalidPad, pin)),
+        }
+    }
+
+    /// Create a new [`DynPad`] from a [`DynPin`]
+    ///
+    /// Return [`PadError::InvalidPad`] and the unchanged [`DynPin`] if it
+    /// can't act as the given pad.
+    #[cfg(any(feature = "samd11", feature = "samd21"))]
+    #[inline]
+    pub fn new(mut pin: DynPin, num: DynPadNum) -> Result<Self, (PadError, DynPin)> {
+        match dyn_map(S::NUM, num, pin.id()) {
+            Some(mode) => {
+                pin.into_alternate(mode);
+                Ok(DynPad {
+                    num,
+                    pin,
+                    sercom: PhantomData,
+                })
+            }
+            None => Err((PadError::InvalidPad, pin)),
+        }
+    }
+
+    /// Return the [`DynPadNum`]
+    #[inline]
+    pub fn num(&self) -> DynPadNum {
+        self.num
+    }
+
+    /// Return the [`DynIoSet`]
+    #[cfg(feature = "min-samd51g")]
+    #[inline]
+    pub fn ioset(&self) -> DynIoSet {
+        self.ioset
+    }
+
+    /// Return the [`DynPinId`] of the underlying [`DynPin`]
+    #[inline]
+    pub fn id(&self) -> DynPinId {
+        self.pin.id()
+    }
+
+    /// Consume the [`DynPad`] and release the corresponding [`DynPin`]
+    #[inline]
+    pub fn free(self) -> DynPin {
+        self.pin
+    }
+}
+
+#[cfg(feature = "min-samd51g")]
+impl<S, P, I> From<Pad<S, P, I>> for DynPad<S>
+where
+    S: Sercom,
+    P: PadNum,
+    I: IoSet + Map<S, P>,
+{
+    #[inline]
+    fn from(pad: Pad<S, P, I>) -> Self {
+        DynPad {
+            num: P::DYN,
+            ioset: I::DYN,
+            pin: pad.pin.into(),
+            sercom: PhantomData,
+        }
+    }
+}
+
+#[cfg(any(feature = "samd11", feature = "samd21"))]
+impl<S, P, M> From<Pad<S, P, M>> for DynPad<S>
+where
+    S: Sercom,
+    P: PadNum,
+    M: Map<S, P>,
+{
+    #[inline]
+    fn from(pad: Pad<S, P, M>) -> Self {
+        DynPad {
+            num: P::DYN,
+            pin: pad.pin.into(),
+            sercom: PhantomData,
+        }
+    }
+}
+
+//==============================================================================
+//  DynPads
+//==============================================================================
+
+/// Collection of up to four [`DynPad`]s for the same [`Sercom`]
+///
+/// Each [`DynPadNum`] can only be used once. For SAMD51 and SAME5x chips, all
+/// [`DynPad`]s must also belong to the same IOSET.
+pub struct DynPads<S: Sercom> {
+    pads: [Option<DynPad<S>>; 4],
+}
+
+impl<S: Sercom> DynPads<S> {
+    /// Create an empty set of [`DynPads`]
+    #[inline]
+    pub fn new() -> Self {
+        DynPads {
+            pads: [None, None, None, None],
+        }
+    }
+
+    /// Add a [`DynPad`]
+    ///
+    /// On error, the [`DynPad`] is returned alongside the [`PadError`].
+    #[inline]
+    pub fn insert(&mut self, pad: DynPad<S>) -> Result<(), (PadError, DynPad<S>)> {
+        if let Err(err) = self.check(&pad) {
+            return Err((err, pad));
+        }
+        let index = pad.num as usize;
+        self.pads[index] = Some(pad);
+        Ok(())
+    }
+
+    /// Check whether a [`DynPad`] could be added
+    pub fn check(&self, pad: &DynPad<S>) -> Result<(), PadError> {
+        if self.pads[pad.num as usize].is_some() {
+            return Err(PadError::DuplicatePad);
+        }
+        #[cfg(feature = "min-samd51g")]
+        {
+            let mismatch = self.pads.iter().flatten().any(|p| p.ioset != pad.ioset);
+            if mismatch {
+                return Err(PadError::IoSetMismatch);
+            }
+        }
+        Ok(())
+    }
+
+    /// Return a reference to the [`DynPad`] with the given [`DynPadNum`], if
+    /// present
+    #[inline]
+    pub fn get(&self, num: DynPadNum) -> Option<&DynPad<S>> {
+        self.pads[num as usize].as_ref()
+    }
+
+    /// Return `true` if a [`DynPad`] with the given [`DynPadNum`] is present
+    #[inline]
+    pub fn contains(&self, num: DynPadNum) -> bool {
+        self.get(num).is_some()
+    }
+
+    /// Remove and return the [`DynPad`] with the given [`DynPadNum`], if
+    /// present
+    #[inline]
+    pub fn take(&mut self, num: DynPadNum) -> Option<DynPad<S>> {
+        self.pads[num as usize].take()
+    }
+
+    /// Consume the [`DynPads`] and return the individual [`DynPad`]s, indexed
+    /// by [`DynPadNum`]
+    #[inline]
+    pub fn free(self) -> [Option<DynPad<S>>; 4] {
+        self.pads
+    }
+}
+
+impl<S: Sercom> Default for DynPads<S> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,319 @@
+//! Share a single SPI bus between multiple devices
+//!
+//! Boards frequently connect several SPI devices, e.g. a display, an SD card
+//! and an external flash chip, to a single SERCOM. Each device has its own
+//! chip-select (CS) line, and each device may require a different SPI mode or
+//! baud rate.
+//!
+//! The [`SpiBus`] type takes ownership of an enabled [`Spi`] struct and hands
+//! out [`SpiDevice`] handles. Each [`SpiDevice`] owns its CS [`Pin`] and an
+//! optional [`DeviceConfig`]. At the start of each transaction, the
+//! [`SpiDevice`] reapplies its [`DeviceConfig`] to the underlying [`Config`],
+//! if it differs from the configuration of the previous transaction, and then
+//! asserts its CS [`Pin`]. The CS [`Pin`] is deasserted once the transaction
+//! completes.
+//!
+//! ```
+//! use atsamd_hal::sercom::v2::spi_bus::SpiBus;
+//! use atsamd_hal::time::U32Ext;
+//! use embedded_hal::blocking::spi::Transfer;
+//! use embedded_hal::spi::{MODE_0, MODE_3};
+//!
+//! let bus = SpiBus::new(spi);
+//! let mut display = bus
+//!     .device(pins.pa10.into_push_pull_output())
+//!     .spi_mode(MODE_3)
+//!     .baud(12.mhz());
+//! let mut flash = bus
+//!     .device(pins.pa11.into_push_pull_output())
+//!     .spi_mode(MODE_0)
+//!     .baud(4.mhz());
+//!
+//! let mut id = [0x9F, 0, 0, 0];
+//! flash.transfer(&mut id)?;
+//! ```
+//!
+//! [`SpiBus`] uses interior mutability, so [`SpiDevice`] handles only need a
+//! shared reference to it. However, [`SpiBus`] is not [`Sync`]. To share a bus
+//! between interrupt handlers, wrap it in a critical-section based mutex, as
+//! with any other peripheral. Attempting to start a transaction while another
+//! transaction is in progress will return [`BusError::Busy`] rather than
+//! corrupting the bus.
+//!
+//! [`Spi`]: super::spi::Spi
+//! [`Config`]: super::spi::Config
+//! [`Pin`]: crate::gpio::v2::pin::Pin
+
+use core::cell::{Cell, RefCell};
+
+use embedded_hal::blocking;
+use embedded_hal::digital::v2::OutputPin;
+use embedded_hal::spi;
+
+use crate::gpio::v2::{Pin, PinId, PushPullOutput};
+use crate::time::Hertz;
+
+use super::spi::{AnySpi, Flags, MasterMode, SpecificSpi, SpiMode};
+
+//=============================================================================
+// DeviceConfig
+//=============================================================================
+
+/// Per-device SPI settings
+///
+/// Each field is optional. A value of `None` selects the setting used to create
+/// the [`Spi`](super::spi::Spi) shared by the [`SpiBus`].
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct DeviceConfig {
+    /// SPI mode (clock polarity & phase) used by the device
+    pub spi_mode: Option<spi::Mode>,
+    /// Baud rate used by the device
+    pub baud: Option<Hertz>,
+}
+
+impl DeviceConfig {
+    /// Merge `self` on top of `defaults`, returning the resulting settings
+    #[inline]
+    fn merge(&self, defaults: &DeviceConfig) -> DeviceConfig {
+        DeviceConfig {
+            spi_mode: self.spi_mode.or(defaults.spi_mode),
+            baud: self.baud.or(defaults.baud),
+        }
+    }
+}
+
+//=============================================================================
+// BusError
+//=============================================================================
+
+/// Error `enum` for transactions on a shared [`SpiBus`]
+#[derive(Debug)]
+pub enum BusError<E> {
+    /// Another transaction is already in progress on the bus
+    Busy,
+    /// The underlying [`Spi`](super::spi::Spi) returned an error
+    Spi(E),
+}
+
+//=============================================================================
+// SpiBus
+//=============================================================================
+
+/// An SPI bus shared between multiple [`SpiDevice`]s
+///
+/// See the [module-level](self) documentation for more details.
+pub struct SpiBus<S: AnySpi> {
+    spi: RefCell<S>,
+    defaults: DeviceConfig,
+    current: Cell<DeviceConfig>,
+}
+
+impl<S> SpiBus<S>
+where
+    S: AnySpi,
+    SpiMode<S::Config>: MasterMode,
+{
+    /// Create a new [`SpiBus`] from an enabled [`Spi`](super::spi::Spi)
+    ///
+    /// Only [`MasterMode`] [`Spi`](super::spi::Spi)s can be shared.
+    ///
+    /// The settings used to create the [`Spi`](super::spi::Spi) act as the
+    /// defaults for any [`SpiDevice`] that leaves a [`DeviceConfig`] field
+    /// unset.
+    #[inline]
+    pub fn new(spi: S) -> Self {
+        let specific: &SpecificSpi<S> = spi.as_ref();
+        let defaults = DeviceConfig {
+            spi_mode: Some(specific.get_spi_mode()),
+            baud: Some(specific.get_baud()),
+        };
+        SpiBus {
+            spi: RefCell::new(spi),
+            defaults,
+            current: Cell::new(defaults),
+        }
+    }
+
+    /// Create a new [`SpiDevice`] handle using the given CS [`Pin`]
+    ///
+    /// The CS [`Pin`] is immediately deasserted by bringing it high.
+    #[inline]
+    pub fn device<I: PinId>(&self, mut cs: Pin<I, PushPullOutput>) -> SpiDevice<'_, S, I> {
+        let _ = cs.set_high();
+        SpiDevice {
+            bus: self,
+            cs,
+            config: DeviceConfig::default(),
+        }
+    }
+
+    /// Consume the [`SpiBus`] and return the underlying
+    /// [`Spi`](super::spi::Spi)
+    ///
+    /// All [`SpiDevice`]s borrow the [`SpiBus`], so they must be freed first.
+    #[inline]
+    pub fn free(self) -> S {
+        self.spi.into_inner()
+    }
+}
+
+impl<S: AnySpi> SpiBus<S> {
+    /// Reapply the [`DeviceConfig`], if necessary
+    fn select(&self, spi: &mut SpecificSpi<S>, config: &DeviceConfig) {
+        let config = config.merge(&self.defaults);
+        if config != self.current.get() {
+            spi.reconfigure(|mut c| {
+                if let Some(mode) = config.spi_mode {
+                    c = c.spi_mode(mode);
+                }
+                if let Some(baud) = config.baud {
+                    c = c.baud(baud);
+                }
+                c
+            });
+            self.current.set(config);
+        }
+    }
+
+    /// Transmit a dummy word, with every CS [`Pin`] deasserted, to set the
+    /// `TXC` flag
+    fn prime(spi: &mut SpecificSpi<S>) {
+        // SAFETY: No transaction is in progress, and the received word is
+        // discarded below
+        unsafe { spi.write_data(0) };
+        while !spi.read_flags().contains(Flags::TXC) {}
+        while spi.read_flags().contains(Flags::RXC) {
+            // SAFETY: The dummy word isn't part of any transaction
+            let _ = unsafe { spi.read_data() };
+        }
+    }
+}
+
+//=============================================================================
+// SpiDevice
+//=============================================================================
+
+/// A handle to a single device on a shared [`SpiBus`]
+///
+/// Each [`SpiDevice`] owns its CS [`Pin`] and a [`DeviceConfig`], which is
+/// reapplied to the bus at the start of each transaction.
+///
+/// [`SpiDevice`] implements the embedded HAL [`Transfer`] and
+/// [`Write`](blocking::spi::Write) traits whenever the underlying
+/// [`Spi`](super::spi::Spi) does. Each call is a single transaction, framed by
+/// the CS [`Pin`]. Use [`SpiDevice::transaction`] to group several operations
+/// under one assertion of the CS [`Pin`].
+///
+/// [`Transfer`]: blocking::spi::Transfer
+pub struct SpiDevice<'a, S: AnySpi, I: PinId> {
+    bus: &'a SpiBus<S>,
+    cs: Pin<I, PushPullOutput>,
+    config: DeviceConfig,
+}
+
+impl<'a, S: AnySpi, I: PinId> SpiDevice<'a, S, I> {
+    /// Set the SPI mode used by this device
+    #[inline]
+    pub fn spi_mode(mut self, mode: spi::Mode) -> Self {
+        self.config.spi_mode = Some(mode);
+        self
+    }
+
+    /// Set the baud rate used by this device
+    ///
+    /// See [`Config::baud`](super::spi::Config::baud) for details on how the
+    /// baud rate is calculated.
+    #[inline]
+    pub fn baud(mut self, baud: impl Into<Hertz>) -> Self {
+        self.config.baud = Some(baud.into());
+        self
+    }
+
+    /// Replace the [`DeviceConfig`] used by this device
+    #[inline]
+    pub fn config(mut self, config: DeviceConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Return the [`DeviceConfig`] used by this device
+    #[inline]
+    pub fn get_config(&self) -> DeviceConfig {
+        self.config
+    }
+
+    /// Perform a transaction with this device
+    ///
+    /// Borrow the bus, reapply the [`DeviceConfig`] if necessary, and assert
+    /// the CS [`Pin`]. Then call `f` with the underlying
+    /// [`Spi`](super::spi::Spi). When `f` returns, wait for the `TXC` flag, so
+    /// that the last word has been shifted out, and deassert the CS [`Pin`].
+    ///
+    /// The `TXC` flag stays set after a transmission, until DATA is written
+    /// again, so the wait returns at once if `f` transmitted nothing. Until
+    /// the first transmission completes, `TXC` is not set, so the first
+    /// transaction on the bus is preceded by a dummy word, sent while every CS
+    /// [`Pin`] is deasserted.
+    ///
+    /// Returns [`BusError::Busy`] if the bus is already in use.
+    #[inline]
+    pub fn transaction<F, R, E>(&mut self, f: F) -> Result<R, BusError<E>>
+    where
+        F: FnOnce(&mut SpecificSpi<S>) -> Result<R, E>,
+    {
+        let mut guard = self.bus.spi.try_borrow_mut().map_err(|_| BusError::Busy)?;
+        let spi: &mut SpecificSpi<S> = (*guard).as_mut();
+        self.bus.select(spi, &self.config);
+        if !spi.read_flags().contains(Flags::TXC) {
+            SpiBus::<S>::prime(spi);
+        }
+        let _ = self.cs.set_low();
+        let result = f(spi);
+        while !spi.read_flags().contains(Flags::TXC) {}
+        let _ = self.cs.set_high();
+        result.map_err(BusError::Spi)
+    }
+
+    /// Consume the [`SpiDevice`] and return its CS [`Pin`]
+    #[inline]
+    pub fn free(self) -> Pin<I, PushPullOutput> {
+        self.cs
+    }
+}
+
+macro_rules! impl_blocking_traits {
+    ( $($Word:ty),+ ) => {
+        $(
+            impl<S, I> blocking::spi::Transfer<$Word> for SpiDevice<'_, S, I>
+            where
+                S: AnySpi,
+                I: PinId,
+                SpecificSpi<S>: blocking::spi::Transfer<$Word>,
+            {
+                type Error = BusError<<SpecificSpi<S> as blocking::spi::Transfer<$Word>>::Error>;
+
+                #[inline]
+                fn transfer<'w>(&mut self, words: &'w mut [$Word]) -> Result<&'w [$Word], Self::Error> {
+                    self.transaction(|spi| spi.transfer(words).map(|_| ()))?;
+                    Ok(words)
+                }
+            }
+
+            impl<S, I> blocking::spi::Write<$Word> for SpiDevice<'_, S, I>
+            where
+                S: AnySpi,
+                I: PinId,
+                SpecificSpi<S>: blocking::spi::Write<$Word>,
+            {
+                type Error = BusError<<SpecificSpi<S> as blocking::spi::Write<$Word>>::Error>;
+
+                #[inline]
+                fn write(&mut self, words: &[$Word]) -> Result<(), Self::Error> {
+                    self.transaction(|spi| spi.write(words))
+                }
+            }
+        )+
+    };
+}
+
+impl_blocking_traits!(u8, u16, u32);
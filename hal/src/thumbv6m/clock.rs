@@ -5,9 +5,15 @@
 //! that the peripherals have been correctly configured.
 use crate::target_device::gclk::clkctrl::GEN_A::*;
 use crate::target_device::gclk::clkctrl::ID_A::*;
+use crate::target_device::gclk::genctrl;
 use crate::target_device::gclk::genctrl::SRC_A::*;
 use crate::target_device::{self, GCLK, NVMCTRL, PM, SYSCTRL};
-use crate::time::{Hertz, U32Ext};
+use crate::time::Hertz;
+
+pub mod bus;
+pub mod config;
+#[cfg(feature = "samd21")]
+pub mod gclk_io;
 
 pub type ClockId = target_device::gclk::clkctrl::ID_A;
 pub type ClockGenId = target_device::gclk::clkctrl::GEN_A;
@@ -86,7 +92,8 @@ impl State {
             w.divsel().clear_bit();
             w.idc().bit(improve_duty_cycle);
             w.genen().set_bit();
-            w.oe().set_bit()
+            // GCLK_IO is an input when the source is GCLKIN
+            w.oe().bit(src != GCLKIN)
         });
         self.wait_for_sync();
     }
@@ -100,7 +107,10 @@ impl State {
         self.wait_for_sync();
     }
 
-    fn configure_standby(&mut self, gclk: ClockGenId, enable: bool) {
+    fn modify_genctrl<F>(&mut self, gclk: ClockGenId, f: F)
+    where
+        F: FnOnce(&mut genctrl::W) -> &mut genctrl::W,
+    {
         // We must first read out the configuration of genctrl to read/modify/write it.
         //   To do so, we must do an 8-bit write to GENCTRL.ID (ref 15.6.4.1 Indirect
         //   Access). 32-bit write did not work.
@@ -111,9 +121,13 @@ impl State {
         self.wait_for_sync();
 
         // Now that the configuration is loaded, modify it
-        self.gclk.genctrl.modify(|_, w| w.runstdby().bit(enable));
+        self.gclk.genctrl.modify(|_, w| f(w));
         self.wait_for_sync();
     }
+
+    fn configure_standby(&mut self, gclk: ClockGenId, enable: bool) {
+        self.modify_genctrl(gclk, |w| w.runstdby().bit(enable));
+    }
 }
 
 /// `GenericClockController` encapsulates the GCLK hardware.
@@ -122,9 +136,16 @@ impl State {
 /// the system to run at 48Mhz by setting gclk1 as a 32khz source
 /// and feeding it into the DFLL48 hardware which in turn drives
 /// gclk0 at 48Mhz.
+///
+/// To configure a different clock tree, e.g. using the XOSC crystal
+/// oscillator or the FDPLL96M, use a [`ClockConfig`](config::ClockConfig)
+/// instead.
 pub struct GenericClockController {
     state: State,
     gclks: [Hertz; 8],
+    xosc: Hertz,
+    dpll: Hertz,
+    gclkins: [Hertz; 8],
     used_clocks: u64,
 }
 
@@ -209,6 +230,9 @@ impl GenericClockController {
                 Hertz(0),
                 Hertz(0),
             ],
+            xosc: Hertz(0),
+            dpll: Hertz(0),
+            gclkins: [Hertz(0); 8],
             used_clocks: 1u64 << u8::from(ClockId::DFLL48),
         }
     }
@@ -255,6 +279,9 @@ impl GenericClockController {
                 Hertz(0),
                 Hertz(0),
             ],
+            xosc: Hertz(0),
+            dpll: Hertz(0),
+            gclkins: [Hertz(0); 8],
             used_clocks: 0,
         }
     }
@@ -297,8 +324,13 @@ impl GenericClockController {
     /// this function doesn't expose that functionality at this time.
     /// `improve_duty_cycle` is a boolean that, when set to true, enables
     /// a 5o/50 duty cycle for odd divider values.
-    /// Returns a `GClock` for the configured clock generator.
-    /// Returns `None` if the clock generator has already been configured.
+    /// Returns a `GClock` for the configured clock generator, carrying the
+    /// frequency of the source. Use [`get_gclk`](Self::get_gclk) for the
+    /// divided output frequency of the generator.
+    /// Returns `None` if the clock generator has already been configured,
+    /// or if the source is the XOSC or FDPLL96M and it is not running.
+    /// On the SAMD21, the `GCLKIN` source must first be set up with
+    /// `configure_gclk_in`.
     pub fn configure_gclk_divider_and_source(
         &mut self,
         gclk: ClockGenId,
@@ -310,20 +342,36 @@ impl GenericClockController {
         if self.gclks[idx].0 != 0 {
             return None;
         }
-        self.state
-            .set_gclk_divider_and_source(gclk, divider, src, improve_duty_cycle);
         let freq: Hertz = match src {
             XOSC32K | OSC32K | OSCULP32K => OSC32K_FREQ,
             GCLKGEN1 => self.gclks[1],
             OSC8M => OSC8M_FREQ,
             DFLL48M => OSC48M_FREQ,
-            DPLL96M => 96.mhz().into(),
-            GCLKIN | XOSC => unimplemented!(),
+            DPLL96M => self.dpll,
+            XOSC => self.xosc,
+            GCLKIN => self.gclkins[idx],
         };
+        if freq.0 == 0 {
+            return None;
+        }
+        self.state
+            .set_gclk_divider_and_source(gclk, divider, src, improve_duty_cycle);
         self.gclks[idx] = Hertz(freq.0 / divider as u32);
         Some(GClock { gclk, freq })
     }
 
+    /// Returns the frequency of the XOSC, if it was enabled by a
+    /// [`ClockConfig`](config::ClockConfig)
+    pub fn xosc_freq(&self) -> Option<Hertz> {
+        Some(self.xosc).filter(|f| f.0 != 0)
+    }
+
+    /// Returns the frequency of the FDPLL96M, if it was enabled by a
+    /// [`ClockConfig`](config::ClockConfig)
+    pub fn dpll_freq(&self) -> Option<Hertz> {
+        Some(self.dpll).filter(|f| f.0 != 0)
+    }
+
     /// Enables or disables the given GClk from operation in standby.
     pub fn configure_standby(&mut self, gclk: ClockGenId, enable: bool) {
         self.state.configure_standby(gclk, enable)
@@ -0,0 +1,306 @@
+//! # AHB and APB bus clocks
+//!
+//! The registers of a peripheral can only be accessed while its bus clock is
+//! enabled in one of the PM `AHBMASK` or `APBxMASK` registers. Gating the
+//! bus clocks of unused peripherals saves power.
+//!
+//! [`ApbClk`] and [`AhbClk`] are tokens representing an enabled bus clock. The
+//! clock is enabled when the token is created and gated off again when the
+//! token is dropped. Each peripheral is identified by a type in this module,
+//! e.g. [`Sercom0`] or [`Tcc0`].
+//!
+//! Drivers that own their peripheral keep the token for as long as they
+//! exist, so the bus clock is gated off when the driver is freed or dropped.
+//! This is the case for the v1 and v2 SERCOM drivers and
+//! [`TimerCounter`](crate::timer::TimerCounter).
+//!
+//! ```no_run
+//! # use atsamd_hal::clock::bus::{Ac, ApbClk};
+//! # fn f(pm: &mut atsamd_hal::target_device::PM) {
+//! // SAFETY: Nothing else has a token for the AC clock
+//! let apb = unsafe { ApbClk::<Ac>::enable(pm) };
+//! // Use the AC through the PAC
+//! apb.disable();
+//! # }
+//! ```
+//!
+//! Dropping any token gates its clock off, so only one token may exist for
+//! each bus clock. The drivers in this crate create the token for the
+//! peripheral they own. Creating a token directly is `unsafe`, because the
+//! caller must make sure that no driver or other token uses the same clock.
+//! Use [`ApbClk::leak`] to keep a clock running for good.
+
+use core::marker::PhantomData;
+
+use paste::paste;
+
+use crate::target_device::pm::RegisterBlock;
+use crate::target_device::PM;
+use crate::typelevel::Sealed;
+
+/// Read-modify-write the PM masks, which are shared by all tokens
+#[inline]
+fn modify(f: impl FnOnce(&RegisterBlock)) {
+    cortex_m::interrupt::free(|_| {
+        // SAFETY: Each token only touches its own bit, and the modification
+        // can't be interrupted
+        let pm = unsafe { &*PM::ptr() };
+        f(pm)
+    });
+}
+
+//==============================================================================
+// Bus clock IDs
+//==============================================================================
+
+/// A peripheral with a clock on one of the APB buses
+pub trait ApbId: Sealed {
+    #[doc(hidden)]
+    fn set(pm: &RegisterBlock, enabled: bool);
+    #[doc(hidden)]
+    fn is_enabled(pm: &RegisterBlock) -> bool;
+}
+
+/// A peripheral with a clock on the AHB bus
+pub trait AhbId: Sealed {
+    #[doc(hidden)]
+    fn set(pm: &RegisterBlock, enabled: bool);
+    #[doc(hidden)]
+    fn is_enabled(pm: &RegisterBlock) -> bool;
+}
+
+macro_rules! bus_ids {
+    (
+        $(
+            $( #[$cfg:meta] )?
+            $Type:ident
+        ),+ $(,)?
+    ) => {
+        $(
+            paste! {
+                $( #[$cfg] )?
+                #[doc = "Bus clock ID of the " $Type:upper]
+                pub enum $Type {}
+
+                $( #[$cfg] )?
+                impl Sealed for $Type {}
+            }
+        )+
+    };
+}
+
+macro_rules! bus_clocks {
+    (
+        $Trait:ident:
+        $(
+            $( #[$cfg:meta] )?
+            ($Type:ident, $mask:ident, $field:ident),
+        )+
+    ) => {
+        $(
+            $( #[$cfg] )?
+            impl $Trait for $Type {
+                #[inline]
+                fn set(pm: &RegisterBlock, enabled: bool) {
+                    pm.$mask.modify(|_, w| w.$field().bit(enabled));
+                }
+
+                #[inline]
+                fn is_enabled(pm: &RegisterBlock) -> bool {
+                    pm.$mask.read().$field().bit()
+                }
+            }
+        )+
+    };
+}
+
+bus_ids!(
+    Ac,
+    Adc,
+    Dac,
+    Dmac,
+    Eic,
+    Evsys,
+    Rtc,
+    Sercom0,
+    Sercom1,
+    #[cfg(feature = "samd21")]
+    Sercom2,
+    #[cfg(feature = "samd21")]
+    Sercom3,
+    #[cfg(feature = "min-samd21g")]
+    Sercom4,
+    #[cfg(feature = "min-samd21g")]
+    Sercom5,
+    #[cfg(feature = "samd11")]
+    Tc1,
+    #[cfg(feature = "samd11")]
+    Tc2,
+    #[cfg(feature = "samd21")]
+    Tc3,
+    #[cfg(feature = "samd21")]
+    Tc4,
+    #[cfg(feature = "samd21")]
+    Tc5,
+    #[cfg(feature = "samd21j")]
+    Tc6,
+    #[cfg(feature = "samd21j")]
+    Tc7,
+    Tcc0,
+    #[cfg(feature = "samd21")]
+    Tcc1,
+    #[cfg(feature = "samd21")]
+    Tcc2,
+    Usb,
+    Wdt,
+);
+
+bus_clocks!(
+    ApbId:
+    (Ac, apbcmask, ac_),
+    (Adc, apbcmask, adc_),
+    (Dac, apbcmask, dac_),
+    (Dmac, apbbmask, dmac_),
+    (Eic, apbamask, eic_),
+    (Evsys, apbcmask, evsys_),
+    (Rtc, apbamask, rtc_),
+    (Sercom0, apbcmask, sercom0_),
+    (Sercom1, apbcmask, sercom1_),
+    #[cfg(feature = "samd21")]
+    (Sercom2, apbcmask, sercom2_),
+    #[cfg(feature = "samd21")]
+    (Sercom3, apbcmask, sercom3_),
+    #[cfg(feature = "min-samd21g")]
+    (Sercom4, apbcmask, sercom4_),
+    #[cfg(feature = "min-samd21g")]
+    (Sercom5, apbcmask, sercom5_),
+    #[cfg(feature = "samd11")]
+    (Tc1, apbcmask, tc1_),
+    #[cfg(feature = "samd11")]
+    (Tc2, apbcmask, tc2_),
+    #[cfg(feature = "samd21")]
+    (Tc3, apbcmask, tc3_),
+    #[cfg(feature = "samd21")]
+    (Tc4, apbcmask, tc4_),
+    #[cfg(feature = "samd21")]
+    (Tc5, apbcmask, tc5_),
+    #[cfg(feature = "samd21j")]
+    (Tc6, apbcmask, tc6_),
+    #[cfg(feature = "samd21j")]
+    (Tc7, apbcmask, tc7_),
+    (Tcc0, apbcmask, tcc0_),
+    #[cfg(feature = "samd21")]
+    (Tcc1, apbcmask, tcc1_),
+    #[cfg(feature = "samd21")]
+    (Tcc2, apbcmask, tcc2_),
+    (Usb, apbbmask, usb_),
+    (Wdt, apbamask, wdt_),
+);
+
+bus_clocks!(
+    AhbId:
+    (Dmac, ahbmask, dmac_),
+    (Usb, ahbmask, usb_),
+);
+
+//==============================================================================
+// Tokens
+//==============================================================================
+
+/// An enabled APB clock
+///
+/// The clock is gated off when the token is dropped.
+pub struct ApbClk<A: ApbId> {
+    id: PhantomData<A>,
+}
+
+impl<A: ApbId> ApbClk<A> {
+    /// Enable the APB clock
+    ///
+    /// # Safety
+    ///
+    /// No other token may exist for the same clock. Dropping either token
+    /// would gate off the clock while the other one is still in use.
+    #[inline]
+    pub unsafe fn enable(_pm: &PM) -> Self {
+        modify(|pm| A::set(pm, true));
+        Self { id: PhantomData }
+    }
+
+    /// Create a token for a clock that was enabled without one
+    ///
+    /// # Safety
+    ///
+    /// The clock must be enabled, and no other token may exist for it.
+    #[inline]
+    pub(crate) unsafe fn assume_enabled() -> Self {
+        Self { id: PhantomData }
+    }
+
+    /// Returns `true` if the APB clock is enabled, with or without a token
+    #[inline]
+    pub fn is_enabled(pm: &PM) -> bool {
+        A::is_enabled(pm)
+    }
+
+    /// Gate off the APB clock
+    #[inline]
+    pub fn disable(self) {}
+
+    /// Consume the token, leaving the APB clock enabled for good
+    #[inline]
+    pub fn leak(self) {
+        core::mem::forget(self);
+    }
+}
+
+impl<A: ApbId> Drop for ApbClk<A> {
+    #[inline]
+    fn drop(&mut self) {
+        modify(|pm| A::set(pm, false));
+    }
+}
+
+/// An enabled AHB clock
+///
+/// The clock is gated off when the token is dropped.
+pub struct AhbClk<A: AhbId> {
+    id: PhantomData<A>,
+}
+
+impl<A: AhbId> AhbClk<A> {
+    /// Enable the AHB clock
+    ///
+    /// # Safety
+    ///
+    /// No other token may exist for the same clock. Dropping either token
+    /// would gate off the clock while the other one is still in use.
+    #[inline]
+    pub unsafe fn enable(_pm: &PM) -> Self {
+        modify(|pm| A::set(pm, true));
+        Self { id: PhantomData }
+    }
+
+    /// Returns `true` if the AHB clock is enabled, with or without a token
+    #[inline]
+    pub fn is_enabled(pm: &PM) -> bool {
+        A::is_enabled(pm)
+    }
+
+    /// Gate off the AHB clock
+    #[inline]
+    pub fn disable(self) {}
+
+    /// Consume the token, leaving the AHB clock enabled for good
+    #[inline]
+    pub fn leak(self) {
+        core::mem::forget(self);
+    }
+}
+
+impl<A: AhbId> Drop for AhbClk<A> {
+    #[inline]
+    fn drop(&mut self) {
+        modify(|pm| A::set(pm, false));
+    }
+}
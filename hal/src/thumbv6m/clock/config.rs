@@ -0,0 +1,846 @@
+//! Configurable clock tree
+//!
+//! [`GenericClockController::with_internal_32kosc`] and
+//! [`GenericClockController::with_external_32kosc`] always produce the same
+//! clock tree, with GCLK0 running at 48 MHz from the DFLL48M. [`ClockConfig`]
+//! describes an arbitrary clock tree instead. It configures the external
+//! multipurpose crystal oscillator (XOSC), the FDPLL96M, with any reference
+//! and any LDR/LDRFRAC ratio, and the source and divider of every GCLK. The
+//! DFLL48M is only enabled if a GCLK uses it as a source.
+//!
+//! All of the builder methods are `const fn`, and so is
+//! [`ClockConfig::frequencies`], which validates the clock tree and calculates
+//! the resulting frequencies. A clock tree can therefore be checked at
+//! compile-time. [`ClockConfig::freeze`] performs the same validation at
+//! run-time before touching any registers.
+//!
+//! Run the CPU at 48 MHz from a 96 MHz FDPLL96M, referenced to a 16 MHz
+//! crystal:
+//!
+//! ```
+//! use atsamd_hal::clock::config::*;
+//! use atsamd_hal::target_device::gclk::clkctrl::GEN_A::*;
+//! use atsamd_hal::target_device::gclk::genctrl::SRC_A::*;
+//! use atsamd_hal::time::Hertz;
+//!
+//! const CLOCKS: ClockConfig = ClockConfig::new()
+//!     // 16 MHz crystal, divided to a 1 MHz reference and multiplied by 96
+//!     .xosc(XoscConfig::crystal(Hertz(16_000_000)))
+//!     .dpll(DpllConfig::new(DpllReference::Xosc { div: 7 }, 95, 0))
+//!     .gclk(GCLK0, GclkConfig::new(DPLL96M, 2));
+//!
+//! // Reject an invalid clock tree at compile-time
+//! const FREQS: ClockFrequencies = match CLOCKS.frequencies() {
+//!     Ok(freqs) => freqs,
+//!     Err(_) => panic!("Invalid clock tree"),
+//! };
+//!
+//! let mut clocks = CLOCKS.freeze(
+//!     peripherals.GCLK,
+//!     &mut peripherals.PM,
+//!     &mut peripherals.SYSCTRL,
+//!     &mut peripherals.NVMCTRL,
+//! )?;
+//! ```
+//!
+//! The USB peripheral requires an accurate 48 MHz clock. Rather than relying
+//! on the USB clock recovery mode of the DFLL48M, it can be derived from a
+//! crystal through the FDPLL96M:
+//!
+//! ```
+//! # use atsamd_hal::clock::config::*;
+//! # use atsamd_hal::target_device::gclk::clkctrl::GEN_A::*;
+//! # use atsamd_hal::target_device::gclk::genctrl::SRC_A::*;
+//! # use atsamd_hal::time::Hertz;
+//! const CLOCKS: ClockConfig = ClockConfig::new()
+//!     // 12 MHz crystal, divided to a 2 MHz reference and multiplied by 24
+//!     .xosc(XoscConfig::crystal(Hertz(12_000_000)))
+//!     .dpll(DpllConfig::from_frequencies(
+//!         DpllReference::Xosc { div: 2 },
+//!         Hertz(2_000_000),
+//!         Hertz(48_000_000),
+//!     ))
+//!     .gclk(GCLK0, GclkConfig::new(DPLL96M, 1));
+//!
+//! let mut clocks = CLOCKS.freeze(/* ... */)?;
+//! let gclk0 = clocks.gclk0();
+//! let usb_clock = clocks.usb(&gclk0).unwrap();
+//! ```
+//!
+//! The `GCLKIN` source is not supported by [`ClockConfig`].
+
+use crate::target_device::gclk::genctrl::SRC_A::*;
+use crate::target_device::sysctrl::dpllctrlb::REFCLK_A;
+use crate::target_device::sysctrl::xosc::GAIN_A;
+use crate::target_device::{GCLK, NVMCTRL, PM, SYSCTRL};
+use crate::time::Hertz;
+
+use super::*;
+
+/// Frequency of the DFLL48M in closed-loop mode
+const DFLL_FREQ: u32 = OSC48M_FREQ.0;
+
+/// Frequency of the 32 kHz oscillators
+const OSC32K_HZ: u32 = OSC32K_FREQ.0;
+
+//==============================================================================
+// ClockError
+//==============================================================================
+
+/// Error `enum` for an invalid [`ClockConfig`]
+///
+/// Where relevant, the variant contains the number of the offending GCLK.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClockError {
+    /// The XOSC frequency is out of range for its [`XoscMode`]
+    InvalidXosc,
+    /// The FDPLL96M reference frequency is out of range, or its ratio or XOSC
+    /// divider doesn't fit in the corresponding register field
+    InvalidDpllReference,
+    /// The FDPLL96M output frequency is out of range
+    InvalidDpllOutput,
+    /// The DFLL48M is used, but GCLK1 doesn't provide its 32 kHz reference
+    InvalidDfllReference,
+    /// The GCLK divider doesn't fit in the DIV field, or the GCLK uses itself
+    /// or an unsupported source
+    InvalidGclk(u8),
+    /// The GCLK output frequency is too high
+    InvalidGclkOutput(u8),
+    /// GCLK0 must always be enabled
+    Gclk0Disabled,
+    /// The FDPLL96M or a GCLK uses a source that is not enabled
+    SourceNotEnabled,
+    /// The FDPLL96M and GCLKs depend on each other in a loop
+    DependencyLoop,
+}
+
+//==============================================================================
+// XoscConfig
+//==============================================================================
+
+/// Operating mode of the external multipurpose oscillator (XOSC)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XoscMode {
+    /// A crystal is connected between XIN and XOUT
+    Crystal,
+    /// An external clock signal is connected to XIN
+    ExternalClock,
+}
+
+/// Configuration of the XOSC
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct XoscConfig {
+    freq: Hertz,
+    mode: XoscMode,
+    startup: u8,
+    on_demand: bool,
+    run_standby: bool,
+}
+
+impl XoscConfig {
+    /// Create an [`XoscConfig`] for a crystal of the given frequency
+    ///
+    /// Crystals from 400 kHz to 32 MHz are supported.
+    #[inline]
+    pub const fn crystal(freq: Hertz) -> Self {
+        XoscConfig {
+            freq,
+            mode: XoscMode::Crystal,
+            startup: 0,
+            on_demand: false,
+            run_standby: false,
+        }
+    }
+
+    /// Create an [`XoscConfig`] for an external clock of the given frequency
+    ///
+    /// External clocks up to 32 MHz are supported.
+    #[inline]
+    pub const fn external_clock(freq: Hertz) -> Self {
+        XoscConfig {
+            freq,
+            mode: XoscMode::ExternalClock,
+            startup: 0,
+            on_demand: false,
+            run_standby: false,
+        }
+    }
+
+    /// Set the STARTUP field
+    ///
+    /// The start-up time is `2^startup` cycles of OSCULP32K. Values greater
+    /// than 15 saturate.
+    #[inline]
+    pub const fn startup(mut self, startup: u8) -> Self {
+        self.startup = if startup > 15 { 15 } else { startup };
+        self
+    }
+
+    /// Only run the oscillator when a peripheral requests it
+    #[inline]
+    pub const fn on_demand(mut self, on_demand: bool) -> Self {
+        self.on_demand = on_demand;
+        self
+    }
+
+    /// Keep running the oscillator in standby sleep mode
+    #[inline]
+    pub const fn run_standby(mut self, run_standby: bool) -> Self {
+        self.run_standby = run_standby;
+        self
+    }
+
+    /// Return the oscillator frequency
+    #[inline]
+    pub const fn freq(&self) -> Hertz {
+        self.freq
+    }
+
+    /// Return the [`XoscMode`]
+    #[inline]
+    pub const fn mode(&self) -> XoscMode {
+        self.mode
+    }
+
+    const fn is_valid(&self) -> bool {
+        match self.mode {
+            XoscMode::Crystal => self.freq.0 >= 400_000 && self.freq.0 <= 32_000_000,
+            XoscMode::ExternalClock => self.freq.0 > 0 && self.freq.0 <= 32_000_000,
+        }
+    }
+
+    /// Enable the oscillator
+    ///
+    /// The GAIN setting is the lowest one rated for the crystal frequency.
+    /// Automatic amplitude gain control is enabled for crystals.
+    fn enable(&self, sysctrl: &mut SYSCTRL) {
+        let crystal = self.mode == XoscMode::Crystal;
+        let gain = match self.freq.0 {
+            0..=2_000_000 => GAIN_A::_0,
+            2_000_001..=4_000_000 => GAIN_A::_1,
+            4_000_001..=8_000_000 => GAIN_A::_2,
+            8_000_001..=16_000_000 => GAIN_A::_3,
+            _ => GAIN_A::_4,
+        };
+        sysctrl.xosc.write(|w| {
+            unsafe {
+                w.startup().bits(self.startup);
+            }
+            w.gain().variant(gain);
+            w.xtalen().bit(crystal);
+            w.ondemand().bit(self.on_demand);
+            w.runstdby().bit(self.run_standby);
+            w.enable().set_bit()
+        });
+        if !self.on_demand {
+            while sysctrl.pclksr.read().xoscrdy().bit_is_clear() {}
+        }
+        // The datasheet recommends enabling AMPGC only once the oscillator is
+        // stable
+        if crystal {
+            sysctrl.xosc.modify(|_, w| w.ampgc().set_bit());
+        }
+    }
+}
+
+//==============================================================================
+// DpllConfig
+//==============================================================================
+
+/// Reference clock for the FDPLL96M
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DpllReference {
+    /// Use the dedicated peripheral channel, fed by the given GCLK
+    Gclk(ClockGenId),
+    /// Use XOSC32K directly
+    Xosc32k,
+    /// Use the XOSC, divided by `2 * (div + 1)`
+    Xosc { div: u16 },
+}
+
+/// Configuration of the FDPLL96M
+///
+/// The output frequency is `f_ref * (LDR + 1 + LDRFRAC / 16)`. The reference
+/// frequency must be between 32 kHz and 2 MHz, and the output frequency must
+/// be between 48 MHz and 96 MHz.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DpllConfig {
+    reference: DpllReference,
+    ldr: u16,
+    ldrfrac: u8,
+    on_demand: bool,
+    run_standby: bool,
+}
+
+impl DpllConfig {
+    /// Create a [`DpllConfig`] from the raw LDR and LDRFRAC values
+    #[inline]
+    pub const fn new(reference: DpllReference, ldr: u16, ldrfrac: u8) -> Self {
+        DpllConfig {
+            reference,
+            ldr,
+            ldrfrac,
+            on_demand: false,
+            run_standby: false,
+        }
+    }
+
+    /// Create a [`DpllConfig`] that multiplies the reference frequency as close
+    /// as possible to the requested output frequency
+    ///
+    /// `fref` is the frequency at the DPLL input, i.e. after the XOSC divider,
+    /// if any. The ratio is rounded down to the nearest 1/16.
+    #[inline]
+    pub const fn from_frequencies(reference: DpllReference, fref: Hertz, fout: Hertz) -> Self {
+        let ratio = if fref.0 == 0 {
+            0
+        } else {
+            fout.0 as u64 * 16 / fref.0 as u64
+        };
+        let ldr = if ratio >= 16 {
+            (ratio / 16 - 1) as u16
+        } else {
+            0
+        };
+        let ldrfrac = (ratio % 16) as u8;
+        Self::new(reference, ldr, ldrfrac)
+    }
+
+    /// Only run the DPLL when a peripheral requests it
+    #[inline]
+    pub const fn on_demand(mut self, on_demand: bool) -> Self {
+        self.on_demand = on_demand;
+        self
+    }
+
+    /// Keep running the DPLL in standby sleep mode
+    #[inline]
+    pub const fn run_standby(mut self, run_standby: bool) -> Self {
+        self.run_standby = run_standby;
+        self
+    }
+
+    /// Return the [`DpllReference`]
+    #[inline]
+    pub const fn reference(&self) -> DpllReference {
+        self.reference
+    }
+
+    /// Return the output frequency for a given reference frequency
+    #[inline]
+    pub const fn output(&self, fref: Hertz) -> Hertz {
+        let mult = (self.ldr as u64 + 1) * 16 + self.ldrfrac as u64;
+        Hertz((fref.0 as u64 * mult / 16) as u32)
+    }
+
+    /// Enable the DPLL
+    fn enable(&self, sysctrl: &mut SYSCTRL, state: &mut State) {
+        sysctrl.dpllctrla.write(|w| w.enable().clear_bit());
+        while sysctrl.dpllstatus.read().enable().bit_is_set() {}
+
+        let (refclk, div) = match self.reference {
+            DpllReference::Gclk(gen) => {
+                state.enable_clock_generator(ClockId::FDPLL, gen);
+                (REFCLK_A::GCLK, 0)
+            }
+            DpllReference::Xosc32k => (REFCLK_A::REF0, 0),
+            DpllReference::Xosc { div } => (REFCLK_A::REF1, div),
+        };
+        sysctrl.dpllratio.write(|w| unsafe {
+            w.ldr().bits(self.ldr);
+            w.ldrfrac().bits(self.ldrfrac)
+        });
+        sysctrl.dpllctrlb.write(|w| unsafe {
+            w.refclk().variant(refclk);
+            w.div().bits(div)
+        });
+        sysctrl.dpllctrla.write(|w| {
+            w.ondemand().bit(self.on_demand);
+            w.runstdby().bit(self.run_standby);
+            w.enable().set_bit()
+        });
+        while sysctrl.dpllstatus.read().enable().bit_is_clear() {}
+        if !self.on_demand {
+            while sysctrl.dpllstatus.read().lock().bit_is_clear()
+                || sysctrl.dpllstatus.read().clkrdy().bit_is_clear()
+            {}
+        }
+    }
+}
+
+//==============================================================================
+// GclkConfig
+//==============================================================================
+
+/// Configuration of a single GCLK
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GclkConfig {
+    src: ClockSource,
+    div: u16,
+    improve_duty_cycle: bool,
+    output_enable: bool,
+    run_standby: bool,
+}
+
+impl GclkConfig {
+    /// Create a [`GclkConfig`] with the given source and linear divider
+    ///
+    /// A divider of zero is treated as one. GCLK1 accepts dividers up to
+    /// 65535 and GCLK2 accepts dividers up to 31, while all other GCLKs
+    /// accept dividers up to 255.
+    #[inline]
+    pub const fn new(src: ClockSource, div: u16) -> Self {
+        GclkConfig {
+            src,
+            div,
+            improve_duty_cycle: false,
+            output_enable: false,
+            run_standby: false,
+        }
+    }
+
+    /// Produce a 50/50 duty cycle for odd divider values
+    #[inline]
+    pub const fn improve_duty_cycle(mut self, idc: bool) -> Self {
+        self.improve_duty_cycle = idc;
+        self
+    }
+
+    /// Drive the generator on its GCLK_IO pin, if it is configured
+    #[inline]
+    pub const fn output_enable(mut self, oe: bool) -> Self {
+        self.output_enable = oe;
+        self
+    }
+
+    /// Keep running the generator in standby sleep mode
+    #[inline]
+    pub const fn run_standby(mut self, run_standby: bool) -> Self {
+        self.run_standby = run_standby;
+        self
+    }
+
+    /// Return the [`ClockSource`]
+    #[inline]
+    pub const fn source(&self) -> ClockSource {
+        self.src
+    }
+
+    /// Return the effective divider
+    #[inline]
+    pub const fn divider(&self) -> u16 {
+        if self.div == 0 {
+            1
+        } else {
+            self.div
+        }
+    }
+
+    /// Write the GENDIV and GENCTRL registers for the given generator
+    fn apply(&self, state: &mut State, index: usize) {
+        state.gclk.gendiv.write(|w| unsafe {
+            w.id().bits(index as u8);
+            w.div().bits(self.divider())
+        });
+        state.wait_for_sync();
+        state.gclk.genctrl.write(|w| unsafe {
+            w.id().bits(index as u8);
+            w.src().variant(self.src);
+            // divide directly by divider, rather than 2^(n+1)
+            w.divsel().clear_bit();
+            w.idc().bit(self.improve_duty_cycle);
+            w.oe().bit(self.output_enable);
+            w.runstdby().bit(self.run_standby);
+            w.genen().set_bit()
+        });
+        state.wait_for_sync();
+    }
+}
+
+//==============================================================================
+// ClockFrequencies
+//==============================================================================
+
+/// Frequencies produced by a valid [`ClockConfig`]
+///
+/// A frequency of zero indicates that the corresponding clock is disabled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClockFrequencies {
+    /// Frequency of the XOSC
+    pub xosc: Hertz,
+    /// Frequency of the FDPLL96M
+    pub dpll: Hertz,
+    /// Frequencies of GCLK0 to GCLK7
+    pub gclk: [Hertz; NUM_GCLKS],
+}
+
+//==============================================================================
+// ClockConfig
+//==============================================================================
+
+/// Number of GCLK generators
+pub const NUM_GCLKS: usize = 8;
+
+/// Maximum frequency of GCLK0, which drives the CPU
+pub const MAX_GCLK0_FREQ: Hertz = OSC48M_FREQ;
+
+/// Maximum frequency of all other GCLKs
+pub const MAX_GCLK_FREQ: Hertz = Hertz(96_000_000);
+
+/// Description of a complete clock tree
+///
+/// See the [module-level](self) documentation for more details.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClockConfig {
+    external_32kosc: bool,
+    xosc: Option<XoscConfig>,
+    dpll: Option<DpllConfig>,
+    gclk: [Option<GclkConfig>; NUM_GCLKS],
+}
+
+impl ClockConfig {
+    /// Create a new [`ClockConfig`] matching the default clock tree
+    ///
+    /// This is the same clock tree produced by
+    /// [`GenericClockController::with_internal_32kosc`]. GCLK0 runs at
+    /// 48 MHz from the DFLL48M, which is referenced to GCLK1. GCLK1 runs from
+    /// the internal 32 kHz oscillator.
+    pub const fn new() -> Self {
+        let mut gclk = [None; NUM_GCLKS];
+        gclk[0] = Some(
+            GclkConfig::new(DFLL48M, 1)
+                .improve_duty_cycle(true)
+                .output_enable(true),
+        );
+        gclk[1] = Some(GclkConfig::new(OSC32K, 1).output_enable(true));
+        ClockConfig {
+            external_32kosc: false,
+            xosc: None,
+            dpll: None,
+            gclk,
+        }
+    }
+
+    /// Use the external 32 kHz crystal oscillator, XOSC32K
+    ///
+    /// When enabled, GCLK1 is fed by XOSC32K rather than OSC32K, as in
+    /// [`GenericClockController::with_external_32kosc`].
+    pub const fn external_32kosc(mut self, enable: bool) -> Self {
+        self.external_32kosc = enable;
+        let src = if enable { XOSC32K } else { OSC32K };
+        self.gclk[1] = Some(GclkConfig::new(src, 1).output_enable(true));
+        self
+    }
+
+    /// Enable the XOSC
+    #[inline]
+    pub const fn xosc(mut self, config: XoscConfig) -> Self {
+        self.xosc = Some(config);
+        self
+    }
+
+    /// Enable the FDPLL96M
+    #[inline]
+    pub const fn dpll(mut self, config: DpllConfig) -> Self {
+        self.dpll = Some(config);
+        self
+    }
+
+    /// Configure a GCLK
+    #[inline]
+    pub const fn gclk(mut self, gen: ClockGenId, config: GclkConfig) -> Self {
+        self.gclk[gen as usize] = Some(config);
+        self
+    }
+
+    /// Disable a GCLK
+    ///
+    /// GCLK0 can't be disabled.
+    #[inline]
+    pub const fn disable_gclk(mut self, gen: ClockGenId) -> Self {
+        self.gclk[gen as usize] = None;
+        self
+    }
+
+    /// Return `true` if any GCLK is fed by the DFLL48M
+    const fn uses_dfll(&self) -> bool {
+        let mut i = 0;
+        while i < NUM_GCLKS {
+            if let Some(gen) = self.gclk[i] {
+                if matches!(gen.src, DFLL48M) {
+                    return true;
+                }
+            }
+            i += 1;
+        }
+        false
+    }
+
+    /// Validate the clock tree and calculate its frequencies
+    ///
+    /// This function can be evaluated at compile-time. See the
+    /// [module-level](self) documentation for an example.
+    pub const fn frequencies(&self) -> Result<ClockFrequencies, ClockError> {
+        let mut freqs = ClockFrequencies {
+            xosc: Hertz(0),
+            dpll: Hertz(0),
+            gclk: [Hertz(0); NUM_GCLKS],
+        };
+        if self.gclk[0].is_none() {
+            return Err(ClockError::Gclk0Disabled);
+        }
+
+        if let Some(xosc) = self.xosc {
+            if !xosc.is_valid() {
+                return Err(ClockError::InvalidXosc);
+            }
+            freqs.xosc = xosc.freq;
+        }
+
+        // The DFLL48M runs in closed-loop mode, referenced to GCLK1
+        if self.uses_dfll() {
+            match self.gclk[1] {
+                Some(gen) if matches!(gen.src, OSC32K | XOSC32K) && gen.divider() == 1 => (),
+                _ => return Err(ClockError::InvalidDfllReference),
+            }
+        }
+
+        // The DPLL and GCLKs can depend on each other, so resolve them
+        // iteratively. Each pass must resolve at least one clock.
+        let mut dpll_done = false;
+        let mut gclk_done = [false; NUM_GCLKS];
+        loop {
+            let mut progress = false;
+            let mut pending = false;
+
+            if let (Some(dpll), false) = (self.dpll, dpll_done) {
+                let fref = match dpll.reference {
+                    DpllReference::Gclk(gen) => {
+                        let gen = gen as usize;
+                        if self.gclk[gen].is_none() {
+                            return Err(ClockError::SourceNotEnabled);
+                        }
+                        if gclk_done[gen] {
+                            Some(freqs.gclk[gen].0)
+                        } else {
+                            None
+                        }
+                    }
+                    DpllReference::Xosc32k => {
+                        if !self.external_32kosc {
+                            return Err(ClockError::SourceNotEnabled);
+                        }
+                        Some(OSC32K_HZ)
+                    }
+                    DpllReference::Xosc { div } => {
+                        if self.xosc.is_none() {
+                            return Err(ClockError::SourceNotEnabled);
+                        }
+                        if div > 0x7FF {
+                            return Err(ClockError::InvalidDpllReference);
+                        }
+                        Some(freqs.xosc.0 / (2 * (div as u32 + 1)))
+                    }
+                };
+                if let Some(fref) = fref {
+                    if fref < 32_000 || fref > 2_000_000 || dpll.ldr > 0xFFF || dpll.ldrfrac > 15 {
+                        return Err(ClockError::InvalidDpllReference);
+                    }
+                    let fout = dpll.output(Hertz(fref)).0;
+                    if fout < 48_000_000 || fout > 96_000_000 {
+                        return Err(ClockError::InvalidDpllOutput);
+                    }
+                    freqs.dpll = Hertz(fout);
+                    dpll_done = true;
+                    progress = true;
+                } else {
+                    pending = true;
+                }
+            }
+
+            let mut i = 0;
+            while i < NUM_GCLKS {
+                if let (Some(gen), false) = (self.gclk[i], gclk_done[i]) {
+                    let fsrc = match gen.src {
+                        XOSC => {
+                            if self.xosc.is_none() {
+                                return Err(ClockError::SourceNotEnabled);
+                            }
+                            Some(freqs.xosc.0)
+                        }
+                        GCLKIN => return Err(ClockError::InvalidGclk(i as u8)),
+                        GCLKGEN1 => {
+                            if i == 1 {
+                                return Err(ClockError::InvalidGclk(i as u8));
+                            }
+                            if self.gclk[1].is_none() {
+                                return Err(ClockError::SourceNotEnabled);
+                            }
+                            if gclk_done[1] {
+                                Some(freqs.gclk[1].0)
+                            } else {
+                                None
+                            }
+                        }
+                        OSCULP32K => Some(OSC32K_HZ),
+                        OSC32K => {
+                            if self.external_32kosc {
+                                return Err(ClockError::SourceNotEnabled);
+                            }
+                            Some(OSC32K_HZ)
+                        }
+                        XOSC32K => {
+                            if !self.external_32kosc {
+                                return Err(ClockError::SourceNotEnabled);
+                            }
+                            Some(OSC32K_HZ)
+                        }
+                        OSC8M => Some(OSC8M_FREQ.0),
+                        DFLL48M => Some(DFLL_FREQ),
+                        DPLL96M => {
+                            if self.dpll.is_none() {
+                                return Err(ClockError::SourceNotEnabled);
+                            }
+                            if dpll_done {
+                                Some(freqs.dpll.0)
+                            } else {
+                                None
+                            }
+                        }
+                    };
+                    if let Some(fsrc) = fsrc {
+                        let max_div = match i {
+                            1 => u16::MAX,
+                            2 => 31,
+                            _ => u8::MAX as u16,
+                        };
+                        if gen.divider() > max_div {
+                            return Err(ClockError::InvalidGclk(i as u8));
+                        }
+                        let fout = fsrc / gen.divider() as u32;
+                        let max = if i == 0 {
+                            MAX_GCLK0_FREQ.0
+                        } else {
+                            MAX_GCLK_FREQ.0
+                        };
+                        if fout > max {
+                            return Err(ClockError::InvalidGclkOutput(i as u8));
+                        }
+                        freqs.gclk[i] = Hertz(fout);
+                        gclk_done[i] = true;
+                        progress = true;
+                    } else {
+                        pending = true;
+                    }
+                }
+                i += 1;
+            }
+
+            if !pending {
+                return Ok(freqs);
+            }
+            if !progress {
+                return Err(ClockError::DependencyLoop);
+            }
+        }
+    }
+
+    /// Validate the clock tree and apply it to the hardware
+    ///
+    /// Like [`GenericClockController::with_internal_32kosc`], this resets the
+    /// GCLK peripheral and takes ownership of it. The clocks are enabled in
+    /// dependency order, and GCLK0 is switched last.
+    ///
+    /// Returns an error, without touching any registers, if the clock tree is
+    /// invalid.
+    pub fn freeze(
+        self,
+        gclk: GCLK,
+        pm: &mut PM,
+        sysctrl: &mut SYSCTRL,
+        nvmctrl: &mut NVMCTRL,
+    ) -> Result<GenericClockController, ClockError> {
+        let freqs = self.frequencies()?;
+        let mut state = State { gclk };
+
+        set_flash_to_half_auto_wait_state(nvmctrl);
+        #[cfg(feature = "samd21")]
+        set_flash_manual_write(nvmctrl);
+        enable_gclk_apb(pm);
+
+        if self.external_32kosc {
+            enable_external_32kosc(sysctrl);
+        } else {
+            enable_internal_32kosc(sysctrl);
+        }
+        // After reset, GCLK0 runs from OSC8M
+        state.reset_gclk();
+        sysctrl.osc8m.modify(|_, w| {
+            w.presc()._0();
+            w.ondemand().clear_bit()
+        });
+
+        if let Some(xosc) = self.xosc {
+            xosc.enable(sysctrl);
+        }
+
+        // Generators fed directly by an oscillator, so they can act as DFLL48M
+        // and DPLL references
+        let first = |g: &GclkConfig| !matches!(g.src, DFLL48M | DPLL96M);
+        for (i, gen) in self.gclk.iter().enumerate().skip(1) {
+            if let Some(gen) = gen.filter(first) {
+                gen.apply(&mut state, i);
+            }
+        }
+
+        let mut used_clocks = 0;
+        if self.uses_dfll() {
+            state.enable_clock_generator(DFLL48, GCLK1);
+            configure_and_enable_dfll48m(sysctrl, self.external_32kosc);
+            used_clocks |= 1u64 << u8::from(DFLL48);
+            for (i, gen) in self.gclk.iter().enumerate().skip(1) {
+                if let Some(gen) = gen.filter(|g| g.src == DFLL48M) {
+                    gen.apply(&mut state, i);
+                }
+            }
+        }
+
+        if let Some(dpll) = self.dpll {
+            dpll.enable(sysctrl, &mut state);
+            if let DpllReference::Gclk(_) = dpll.reference {
+                used_clocks |= 1u64 << u8::from(FDPLL);
+            }
+            for (i, gen) in self.gclk.iter().enumerate().skip(1) {
+                if let Some(gen) = gen.filter(|g| g.src == DPLL96M) {
+                    gen.apply(&mut state, i);
+                }
+            }
+        }
+
+        // GCLK0 is always present in a valid configuration
+        if let Some(gen) = self.gclk[0] {
+            gen.apply(&mut state, 0);
+        }
+
+        pm.cpusel.write(|w| w.cpudiv().div1());
+        pm.apbasel.write(|w| w.apbadiv().div1());
+        pm.apbbsel.write(|w| w.apbbdiv().div1());
+        pm.apbcsel.write(|w| w.apbcdiv().div1());
+
+        Ok(GenericClockController {
+            state,
+            gclks: freqs.gclk,
+            xosc: freqs.xosc,
+            dpll: freqs.dpll,
+            gclkins: [Hertz(0); NUM_GCLKS],
+            used_clocks,
+        })
+    }
+}
+
+impl Default for ClockConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,206 @@
+//! # GCLK_IO pins
+//!
+//! Each GCLK generator has one or more GCLK_IO pins. A generator can
+//! drive its clock out on one of them, or use an external clock on one of them
+//! as its `GCLKIN` source. The [`GclkIo`] trait maps each pin to its
+//! generator, and is only implemented for the pins that exist on the selected
+//! chip.
+//!
+//! ```no_run
+//! # use atsamd_hal::clock::{ClockGenId, ClockSource, GenericClockController};
+//! # use atsamd_hal::gpio::v2::{Pin, FloatingDisabled, PA10, PA16};
+//! # use atsamd_hal::time::U32Ext;
+//! # fn f(
+//! #     clocks: &mut GenericClockController,
+//! #     pa16: Pin<PA16, FloatingDisabled>,
+//! #     pa10: Pin<PA10, FloatingDisabled>,
+//! # ) {
+//! // Drive GCLK2 at 8 MHz on PA16
+//! let gclk2 = clocks
+//!     .configure_gclk_divider_and_source(ClockGenId::GCLK2, 6, ClockSource::DFLL48M, false)
+//!     .unwrap();
+//! let out = clocks.enable_gclk_out(&gclk2, pa16, false, false);
+//!
+//! // Run GCLK4 from an external 10 MHz clock on PA10
+//! let input = clocks.configure_gclk_in(pa10, 10_000_000.hz());
+//! let gclk4 = clocks
+//!     .configure_gclk_divider_and_source(ClockGenId::GCLK4, 1, ClockSource::GCLKIN, false)
+//!     .unwrap();
+//! # }
+//! ```
+
+use crate::gpio::v2::{AlternateH, Pin, PinId};
+use crate::time::Hertz;
+
+use super::{ClockGenId, GClock, GenericClockController};
+
+//==============================================================================
+// GclkIo
+//==============================================================================
+
+/// A GCLK_IO pin, mapped to its GCLK generator
+pub trait GclkIo: PinId {
+    /// The generator of this pin
+    const GEN: ClockGenId;
+}
+
+macro_rules! gclk_io {
+    (
+        $(
+            $( #[$cfg:meta] )?
+            ($Id:ident, $Gen:ident),
+        )+
+    ) => {
+        $(
+            $( #[$cfg] )?
+            impl GclkIo for crate::gpio::v2::$Id {
+                const GEN: ClockGenId = ClockGenId::$Gen;
+            }
+        )+
+    };
+}
+
+gclk_io!(
+    (PA10, GCLK4),
+    (PA11, GCLK5),
+    (PA14, GCLK0),
+    (PA15, GCLK1),
+    (PA16, GCLK2),
+    (PA17, GCLK3),
+    #[cfg(feature = "min-samd21g")]
+    (PA20, GCLK4),
+    #[cfg(feature = "min-samd21g")]
+    (PA21, GCLK5),
+    (PA22, GCLK6),
+    (PA23, GCLK7),
+    (PA27, GCLK0),
+    (PA28, GCLK0),
+    (PA30, GCLK0),
+    #[cfg(feature = "min-samd21g")]
+    (PB10, GCLK4),
+    #[cfg(feature = "min-samd21g")]
+    (PB11, GCLK5),
+    #[cfg(feature = "min-samd21j")]
+    (PB12, GCLK6),
+    #[cfg(feature = "min-samd21j")]
+    (PB13, GCLK7),
+    #[cfg(feature = "min-samd21j")]
+    (PB14, GCLK0),
+    #[cfg(feature = "min-samd21j")]
+    (PB15, GCLK1),
+    #[cfg(feature = "min-samd21j")]
+    (PB16, GCLK2),
+    #[cfg(feature = "min-samd21j")]
+    (PB17, GCLK3),
+    #[cfg(feature = "min-samd21g")]
+    (PB22, GCLK0),
+    #[cfg(feature = "min-samd21g")]
+    (PB23, GCLK1),
+);
+
+//==============================================================================
+// GclkOut and GclkIn
+//==============================================================================
+
+/// A GCLK generator driving its clock out on a GCLK_IO pin
+pub struct GclkOut<I: GclkIo> {
+    pin: Pin<I, AlternateH>,
+    freq: Hertz,
+}
+
+impl<I: GclkIo> GclkOut<I> {
+    /// Returns the frequency of the output clock, after the generator divider
+    #[inline]
+    pub fn freq(&self) -> Hertz {
+        self.freq
+    }
+}
+
+/// An external clock on a GCLK_IO pin, used as the `GCLKIN` source of its
+/// generator
+pub struct GclkIn<I: GclkIo> {
+    pin: Pin<I, AlternateH>,
+    freq: Hertz,
+}
+
+impl<I: GclkIo> GclkIn<I> {
+    /// Returns the frequency of the external clock
+    #[inline]
+    pub fn freq(&self) -> Hertz {
+        self.freq
+    }
+}
+
+//==============================================================================
+// GenericClockController
+//==============================================================================
+
+impl GenericClockController {
+    /// Drive a GCLK generator on one of its GCLK_IO pins
+    ///
+    /// `off_value` is the level of the pin while the generator is turned off.
+    /// `improve_duty_cycle` enables a 50/50 duty cycle for odd dividers,
+    /// overriding the value given when the generator was configured.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pin` is not a GCLK_IO pin of the generator of `gclk`.
+    pub fn enable_gclk_out<I: GclkIo>(
+        &mut self,
+        gclk: &GClock,
+        pin: impl Into<Pin<I, AlternateH>>,
+        off_value: bool,
+        improve_duty_cycle: bool,
+    ) -> GclkOut<I> {
+        assert!(
+            gclk.gclk == I::GEN,
+            "pin is not a GCLK_IO pin of GCLK {}",
+            u8::from(gclk.gclk)
+        );
+        let pin = pin.into();
+        self.state.modify_genctrl(I::GEN, |w| {
+            w.oov().bit(off_value);
+            w.idc().bit(improve_duty_cycle);
+            w.oe().set_bit()
+        });
+        GclkOut {
+            pin,
+            freq: self.gclks[u8::from(I::GEN) as usize],
+        }
+    }
+
+    /// Stop driving a GCLK generator on its GCLK_IO pin and return the pin
+    pub fn disable_gclk_out<I: GclkIo>(&mut self, out: GclkOut<I>) -> Pin<I, AlternateH> {
+        self.state.modify_genctrl(I::GEN, |w| w.oe().clear_bit());
+        out.pin
+    }
+
+    /// Use an external clock on a GCLK_IO pin as the `GCLKIN` source of its
+    /// generator
+    ///
+    /// Afterwards, the generator can be configured with
+    /// [`configure_gclk_divider_and_source`](Self::configure_gclk_divider_and_source)
+    /// and the `GCLKIN` source. Only one pin per generator can be used as an
+    /// input.
+    pub fn configure_gclk_in<I: GclkIo>(
+        &mut self,
+        pin: impl Into<Pin<I, AlternateH>>,
+        freq: impl Into<Hertz>,
+    ) -> GclkIn<I> {
+        let freq = freq.into();
+        self.gclkins[u8::from(I::GEN) as usize] = freq;
+        GclkIn {
+            pin: pin.into(),
+            freq,
+        }
+    }
+
+    /// Release a GCLK_IO input and return the pin
+    ///
+    /// The generator is not reconfigured, so it should be switched to a
+    /// different source first.
+    pub fn free_gclk_in<I: GclkIo>(&mut self, input: GclkIn<I>) -> Pin<I, AlternateH> {
+        self.gclkins[u8::from(I::GEN) as usize] = Hertz(0);
+        input.pin
+    }
+}
@@ -0,0 +1,138 @@
+//! # Event system
+//!
+//! The EVSYS routes events from one peripheral, the generator, to another, the
+//! user, without involving the CPU. Each of the [`NUM_CHANNELS`] channels
+//! carries the events of one generator, and any number of users can listen to
+//! a channel.
+//!
+//! Generators and users are identified by the IDs listed in the EVSYS chapter
+//! of the datasheet. The ones used by this HAL are in the [`generator`] and
+//! [`user`] modules.
+//!
+//! Channels are configured with the asynchronous path, so events reach the
+//! user directly, without a GCLK_EVSYS_CHANNEL clock, and also in Standby.
+//!
+//! ```no_run
+//! # use atsamd_hal::evsys::{generator, user, Evsys};
+//! # fn f(
+//! #     evsys: atsamd_hal::target_device::EVSYS,
+//! #     pm: &mut atsamd_hal::target_device::PM,
+//! # ) {
+//! let mut evsys = Evsys::new(evsys, pm);
+//! // Route EXTINT 4 to the TC event input on channel 0
+//! evsys.connect(0, generator::eic_extint(4), user::TC3);
+//! # }
+//! ```
+
+use crate::clock::bus::{self, ApbClk};
+use crate::eic::pin::ExternalInterruptID;
+use crate::target_device::{EVSYS, PM};
+
+/// Number of EVSYS channels
+#[cfg(feature = "samd11")]
+pub const NUM_CHANNELS: u8 = 6;
+/// Number of EVSYS channels
+#[cfg(feature = "samd21")]
+pub const NUM_CHANNELS: u8 = 12;
+
+/// Event generator ID
+pub type GeneratorId = u8;
+
+/// Event user ID
+pub type UserId = u8;
+
+/// Event generator IDs
+pub mod generator {
+    use super::{ExternalInterruptID, GeneratorId};
+
+    /// EIC external interrupt 0
+    pub const EIC_EXTINT_0: GeneratorId = 0x0C;
+
+    /// EIC external interrupt `n`
+    #[inline]
+    pub const fn eic_extint(n: ExternalInterruptID) -> GeneratorId {
+        EIC_EXTINT_0 + n as GeneratorId
+    }
+}
+
+/// Event user IDs
+pub mod user {
+    use super::UserId;
+
+    /// TC1 event input
+    #[cfg(feature = "samd11")]
+    pub const TC1: UserId = 0x0A;
+    /// TC2 event input
+    #[cfg(feature = "samd11")]
+    pub const TC2: UserId = 0x0B;
+
+    /// TC3 event input
+    #[cfg(feature = "samd21")]
+    pub const TC3: UserId = 0x12;
+    /// TC4 event input
+    #[cfg(feature = "samd21")]
+    pub const TC4: UserId = 0x13;
+    /// TC5 event input
+    #[cfg(feature = "samd21")]
+    pub const TC5: UserId = 0x14;
+    /// TC6 event input
+    #[cfg(feature = "samd21")]
+    pub const TC6: UserId = 0x15;
+    /// TC7 event input
+    #[cfg(feature = "samd21")]
+    pub const TC7: UserId = 0x16;
+}
+
+/// The event system
+pub struct Evsys {
+    evsys: EVSYS,
+    apb_clk: ApbClk<bus::Evsys>,
+}
+
+impl Evsys {
+    /// Power on and reset the EVSYS
+    pub fn new(evsys: EVSYS, pm: &mut PM) -> Self {
+        // SAFETY: We own the EVSYS, so no other token exists for its clock
+        let apb_clk = unsafe { ApbClk::enable(pm) };
+        evsys.ctrl.write(|w| w.swrst().set_bit());
+        Self { evsys, apb_clk }
+    }
+
+    /// Route the events of `generator` to `user` through `channel`
+    ///
+    /// Any user previously connected to the channel stays connected.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel` is not less than [`NUM_CHANNELS`].
+    pub fn connect(&mut self, channel: u8, generator: GeneratorId, user: UserId) {
+        assert!(channel < NUM_CHANNELS, "EVSYS channel out of range");
+        // SAFETY: The channel was checked, and unknown generator IDs don't
+        // generate any event
+        self.evsys.channel.write(|w| unsafe {
+            w.channel().bits(channel);
+            w.evgen().bits(generator);
+            w.path().asynchronous();
+            w.edgsel().no_evt_output()
+        });
+        // The user is connected to the channel number plus one, zero
+        // disconnects it
+        self.evsys.user.write(|w| unsafe {
+            w.user().bits(user);
+            w.channel().bits(channel + 1)
+        });
+    }
+
+    /// Disconnect `user` from its channel
+    #[inline]
+    pub fn disconnect(&mut self, user: UserId) {
+        self.evsys.user.write(|w| unsafe { w.user().bits(user) });
+    }
+
+    /// Reset the EVSYS, gate off its APB clock and return it
+    pub fn free(self) -> EVSYS {
+        self.evsys.ctrl.write(|w| w.swrst().set_bit());
+        self.apb_clk.disable();
+        self.evsys
+    }
+}
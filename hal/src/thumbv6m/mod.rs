@@ -8,6 +8,9 @@ pub use serial_number::*;
 
 pub mod calibration;
 pub mod clock;
+pub mod evsys;
+pub mod sleep;
+pub mod supc;
 pub mod timer;
 
 #[cfg(feature = "unproven")]
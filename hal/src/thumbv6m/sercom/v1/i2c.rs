@@ -1,6 +1,7 @@
 // Note: section 7.2.3 shows which pins support I2C Hs mode
 
 use crate::clock;
+use crate::clock::bus::{self, ApbClk};
 use crate::hal::blocking::i2c::{Read, Write, WriteRead};
 use crate::target_device::sercom0::I2CM;
 use crate::target_device::{PM, SERCOM0, SERCOM1};
@@ -20,7 +21,7 @@ const MASTER_ACT_STOP: u8 = 3;
 /// Define an I2C master type for the given SERCOM and pad pair.
 macro_rules! i2c {
     ([
-        $($Type:ident: ($pad0:ident, $pad1:ident, $SERCOM:ident, $powermask:ident, $clock:ident),)+
+        $($Type:ident: ($pad0:ident, $pad1:ident, $SERCOM:ident, $Sercom:ident, $clock:ident),)+
     ]) => {
         $(
 /// Represents the Sercom instance configured to act as an I2C Master.
@@ -29,6 +30,7 @@ pub struct $Type<$pad0, $pad1> {
     sda: $pad0,
     scl: $pad1,
     sercom: $SERCOM,
+    apb_clk: ApbClk<bus::$Sercom>,
 }
 
 impl<$pad0, $pad1> $Type<$pad0, $pad1> {
@@ -62,8 +64,8 @@ impl<$pad0, $pad1> $Type<$pad0, $pad1> {
         scl: $pad1,
     ) -> Self {
         // Power up the peripheral bus clock.
-        // safe because we're exclusively owning SERCOM
-        pm.apbcmask.modify(|_, w| w.$powermask().set_bit());
+        // SAFETY: We own the SERCOM, so no other token exists for its clock
+        let apb_clk = unsafe { ApbClk::enable(pm) };
 
         unsafe {
             // reset the sercom instance
@@ -96,15 +98,39 @@ impl<$pad0, $pad1> $Type<$pad0, $pad1> {
             while sercom.i2cm().syncbusy.read().sysop().bit_is_set() {}
         }
 
-        Self { sda, scl, sercom }
+        Self {
+            sda,
+            scl,
+            sercom,
+            apb_clk,
+        }
     }
 
     /// Breaks the sercom device up into its constituent pins and the SERCOM
-    /// instance.  Does not make any changes to power management.
+    /// instance, and gates off the APB clock of the SERCOM.
     pub fn free(self) -> ($pad0, $pad1, $SERCOM) {
+        self.apb_clk.disable();
         (self.sda, self.scl, self.sercom)
     }
 
+    /// Run in standby mode
+    ///
+    /// When set, the I2C master keeps running while the chip is in standby.
+    /// This is disabled by default.
+    pub fn run_in_standby(&mut self, set: bool) {
+        let i2cm = self.i2cm();
+        i2cm.ctrla.modify(|_, w| w.enable().clear_bit());
+        while i2cm.syncbusy.read().enable().bit_is_set() {}
+        i2cm.ctrla.modify(|_, w| w.runstdby().bit(set));
+        i2cm.ctrla.modify(|_, w| w.enable().set_bit());
+        while i2cm.syncbusy.read().enable().bit_is_set() {}
+        // The bus state is unknown after enabling, so force it back to idle
+        unsafe {
+            i2cm.status.modify(|_, w| w.busstate().bits(BUS_STATE_IDLE));
+        }
+        while i2cm.syncbusy.read().sysop().bit_is_set() {}
+    }
+
     fn start_tx_write(&mut self, addr: u8) -> Result<(), I2CError> {
         let status = self.i2cm().status.read();
         if status.busstate().bits() == BUS_STATE_BUSY
@@ -313,7 +339,7 @@ i2c!([
             Sercom0Pad0,
             Sercom0Pad1,
             SERCOM0,
-            sercom0_,
+            Sercom0,
             Sercom0CoreClock
         ),
     I2CMaster1:
@@ -321,7 +347,7 @@ i2c!([
             Sercom1Pad0,
             Sercom1Pad1,
             SERCOM1,
-            sercom1_,
+            Sercom1,
             Sercom1CoreClock
         ),
 ]);
@@ -333,7 +359,7 @@ i2c!([
             Sercom2Pad0,
             Sercom2Pad1,
             SERCOM2,
-            sercom2_,
+            Sercom2,
             Sercom2CoreClock
         ),
     I2CMaster3:
@@ -341,7 +367,7 @@ i2c!([
             Sercom3Pad0,
             Sercom3Pad1,
             SERCOM3,
-            sercom3_,
+            Sercom3,
             Sercom3CoreClock
         ),
 ]);
@@ -353,7 +379,7 @@ i2c!([
             Sercom4Pad0,
             Sercom4Pad1,
             SERCOM4,
-            sercom4_,
+            Sercom4,
             Sercom4CoreClock
         ),
     I2CMaster5:
@@ -361,7 +387,7 @@ i2c!([
             Sercom5Pad0,
             Sercom5Pad1,
             SERCOM5,
-            sercom5_,
+            Sercom5,
             Sercom5CoreClock
         ),
 ]);
@@ -1,4 +1,5 @@
 use crate::clock;
+use crate::clock::bus::{self, ApbClk};
 use crate::hal::spi::{FullDuplex, Mode, Phase, Polarity};
 use crate::sercom::pads::*;
 use crate::spi_common::CommonSpi;
@@ -28,7 +29,7 @@ pub trait DipoDopo {
 /// Also defines the valid "pad to spi function" mappings for this instance so
 /// that construction is restricted to correct configurations.
 macro_rules! spi_master {
-    ($Type:ident: ($Sercom:ident, $SERCOM:ident, $powermask:ident, $clock:ident)) => {
+    ($Type:ident: ($Sercom:ident, $SERCOM:ident, $clock:ident)) => {
         $crate::paste::item! {
             /// A pad mapping configuration for the SERCOM in SPI master mode.
             ///
@@ -101,6 +102,7 @@ macro_rules! spi_master {
             pub struct $Type<MISO, MOSI, SCK> {
                 padout: [<$Type Padout>]<MISO, MOSI, SCK>,
                 sercom: $SERCOM,
+                apb_clk: ApbClk<bus::$Sercom>,
             }
 
             impl<MISO, MOSI, SCK> CommonSpi for $Type<MISO, MOSI, SCK> {
@@ -134,8 +136,8 @@ macro_rules! spi_master {
                     let padout = padout.into();
 
                     // Power up the peripheral bus clock.
-                    // safe because we're exclusively owning SERCOM
-                    pm.apbcmask.modify(|_, w| w.$powermask().set_bit());
+                    // SAFETY: We own the SERCOM, so no other token exists for its clock
+                    let apb_clk = unsafe { ApbClk::enable(pm) };
 
                     // reset the sercom instance
                     sercom.spi().ctrla.modify(|_, w| w.swrst().set_bit());
@@ -190,6 +192,7 @@ macro_rules! spi_master {
                     Self {
                         padout,
                         sercom,
+                        apb_clk,
                     }
                 }
 
@@ -203,9 +206,21 @@ macro_rules! spi_master {
                     self.enable();
                 }
 
+                /// Run in standby mode
+                ///
+                /// When set, the SPI keeps running while the chip is in
+                /// standby. This is disabled by default.
+                pub fn run_in_standby(&mut self, set: bool) {
+                    self.disable();
+                    self.spi_mut().ctrla.modify(|_, w| w.runstdby().bit(set));
+                    self.enable();
+                }
+
                 /// Tear down the SPI instance and yield the constituent pins and
-                /// SERCOM instance.  No explicit de-initialization is performed.
+                /// SERCOM instance.  The APB clock of the SERCOM is gated off, but no
+                /// other de-initialization is performed.
                 pub fn free(self) -> ([<$Type Padout>]<MISO, MOSI, SCK>, $SERCOM) {
+                    self.apb_clk.disable();
                     (self.padout, self.sercom)
                 }
             }
@@ -250,13 +265,13 @@ macro_rules! spi_master {
 
 }
 
-spi_master!(SPIMaster0: (Sercom0, SERCOM0, sercom0_, Sercom0CoreClock));
-spi_master!(SPIMaster1: (Sercom1, SERCOM1, sercom1_, Sercom1CoreClock));
+spi_master!(SPIMaster0: (Sercom0, SERCOM0, Sercom0CoreClock));
+spi_master!(SPIMaster1: (Sercom1, SERCOM1, Sercom1CoreClock));
 #[cfg(feature = "samd21")]
-spi_master!(SPIMaster2: (Sercom2, SERCOM2, sercom2_, Sercom2CoreClock));
+spi_master!(SPIMaster2: (Sercom2, SERCOM2, Sercom2CoreClock));
 #[cfg(feature = "samd21")]
-spi_master!(SPIMaster3: (Sercom3, SERCOM3, sercom3_, Sercom3CoreClock));
+spi_master!(SPIMaster3: (Sercom3, SERCOM3, Sercom3CoreClock));
 #[cfg(feature = "min-samd21g")]
-spi_master!(SPIMaster4: (Sercom4, SERCOM4, sercom4_, Sercom4CoreClock));
+spi_master!(SPIMaster4: (Sercom4, SERCOM4, Sercom4CoreClock));
 #[cfg(feature = "min-samd21g")]
-spi_master!(SPIMaster5: (Sercom5, SERCOM5, sercom5_, Sercom5CoreClock));
+spi_master!(SPIMaster5: (Sercom5, SERCOM5, Sercom5CoreClock));
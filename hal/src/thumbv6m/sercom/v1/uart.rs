@@ -1,4 +1,5 @@
 use crate::clock;
+use crate::clock::bus::{self, ApbClk};
 use crate::hal::blocking::serial::{write::Default, Write};
 use crate::hal::serial;
 use crate::sercom::pads::*;
@@ -20,12 +21,33 @@ pub trait RxpoTxpo {
     fn rxpo_txpo(&self) -> (u8, u8);
 }
 
+/// Direction of the XCK clock signal for a USART in synchronous mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XckMode {
+    /// XCK is an output, generated by the internal baud rate generator
+    Master,
+    /// XCK is an input, driven by the remote device
+    Slave,
+}
+
+/// XCK clock polarity for a USART in synchronous mode
+///
+/// Selects the XCK edge on which TxD changes. RxD is always sampled on the
+/// opposite edge.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XckPolarity {
+    /// TxD changes on the rising XCK edge, RxD is sampled on the falling edge
+    RisingEdgeTx,
+    /// TxD changes on the falling XCK edge, RxD is sampled on the rising edge
+    FallingEdgeTx,
+}
+
 /// Define a UARTX type for the given Sercom.
 ///
 /// Also defines the valid "pad to uart function" mappings for this instance so
 /// that construction is restricted to valid configurations.
 macro_rules! uart {
-    ($Type:ident: ($Sercom:ident, $SERCOM:ident, $powermask:ident, $clock:ident)) => {
+    ($Type:ident: ($Sercom:ident, $SERCOM:ident, $clock:ident)) => {
         $crate::paste::item! {
             /// A pad mapping configuration for the SERCOM in UART mode.
             ///
@@ -52,6 +74,26 @@ macro_rules! uart {
                 rts: RTS,
             }
 
+            /// A pad mapping configuration for the SERCOM in synchronous
+            /// (USRT) mode.
+            ///
+            /// This type can only be constructed using the From implementations
+            /// in this module, which are restricted to valid configurations.
+            ///
+            /// Defines which sercom pad is mapped to the RX, TX and XCK functions.
+            pub struct [<$Type SyncPadout>]<RX, TX, XCK> {
+                rx: RX,
+                tx: TX,
+                xck: XCK,
+            }
+
+            impl<RX, TX, XCK> [<$Type SyncPadout>]<RX, TX, XCK> {
+                /// Consumes the padout and returns the RX, TX and XCK pads
+                pub fn free(self) -> (RX, TX, XCK) {
+                    (self.rx, self.tx, self.xck)
+                }
+            }
+
             impl<RX, TX, RTS, CTS> [<$Type Padout>]<RX, TX, RTS, CTS> {
                 /// Splits the padout into transmit and receive halves
                 pub fn split(self) -> ([<$Type TxPadout>]<TX, RTS>, [<$Type RxPadout>]<RX, CTS>) {
@@ -152,6 +194,48 @@ macro_rules! uart {
         padout!((3, 0) => Pad3, Pad0);
         padout!((3, 1) => Pad3, Pad2);
 
+        /// Define a From instance for a tuple of three SercomXPadX instances
+        /// that converts them into an UARTXSyncPadout instance.
+        ///
+        /// Also defines a RxpoTxpo instance for the constructed padout
+        /// instance.
+        macro_rules! sync_padout {
+            ($rxpo_txpo:expr => $pad0:ident, $pad1:ident, $pad2:ident) => {
+                $crate::paste::item! {
+                    /// Convert from a tuple of (RX, TX, XCK) to UARTXSyncPadout
+                    impl<PIN0, PIN1, PIN2> From<([<$Sercom $pad0>]<PIN0>, [<$Sercom $pad1>]<PIN1>, [<$Sercom $pad2>]<PIN2>)> for [<$Type SyncPadout>]<[<$Sercom $pad0>]<PIN0>, [<$Sercom $pad1>]<PIN1>, [<$Sercom $pad2>]<PIN2>>
+                    where
+                        PIN0: Map<$Sercom, $pad0>,
+                        PIN1: Map<$Sercom, $pad1>,
+                        PIN2: Map<$Sercom, $pad2>,
+                    {
+                        fn from(pads: ([<$Sercom $pad0>]<PIN0>, [<$Sercom $pad1>]<PIN1>, [<$Sercom $pad2>]<PIN2>)) -> [<$Type SyncPadout>]<[<$Sercom $pad0>]<PIN0>, [<$Sercom $pad1>]<PIN1>, [<$Sercom $pad2>]<PIN2>> {
+                            [<$Type SyncPadout>] { rx: pads.0, tx: pads.1, xck: pads.2 }
+                        }
+                    }
+
+                    impl<PIN0, PIN1, PIN2> RxpoTxpo for [<$Type SyncPadout>]<[<$Sercom $pad0>]<PIN0>, [<$Sercom $pad1>]<PIN1>, [<$Sercom $pad2>]<PIN2>>
+                    where
+                        PIN0: Map<$Sercom, $pad0>,
+                        PIN1: Map<$Sercom, $pad1>,
+                        PIN2: Map<$Sercom, $pad2>,
+                    {
+                        fn rxpo_txpo(&self) -> (u8, u8) {
+                            $rxpo_txpo
+                        }
+                    }
+                }
+            };
+        }
+
+        // In synchronous mode, txpo 0 places TX on PAD 0 and XCK on PAD 1,
+        // while txpo 1 places TX on PAD 2 and XCK on PAD 3
+        // (rxpo_txpo) => (RX, TX, XCK)
+        sync_padout!((2, 0) => Pad2, Pad0, Pad1);
+        sync_padout!((3, 0) => Pad3, Pad0, Pad1);
+        sync_padout!((0, 1) => Pad0, Pad2, Pad3);
+        sync_padout!((1, 1) => Pad1, Pad2, Pad3);
+
         $crate::paste::item! {
             /// UARTX represents the corresponding SERCOMX instance
             /// configured to act in the role of a UART Master.
@@ -163,6 +247,7 @@ macro_rules! uart {
             pub struct $Type<RX, TX, RTS, CTS> {
                 padout: [<$Type Padout>]<RX, TX, RTS, CTS>,
                 sercom: $SERCOM,
+                apb_clk: ApbClk<bus::$Sercom>,
             }
 
             impl<RX, TX, RTS, CTS> $Type<RX, TX, RTS, CTS> {
@@ -184,7 +269,8 @@ macro_rules! uart {
                     [<$Type Padout>]<RX, TX, RTS, CTS>: RxpoTxpo {
                     let padout = padout.into();
 
-                    pm.apbcmask.modify(|_, w| w.$powermask().set_bit());
+                    // SAFETY: We own the SERCOM, so no other token exists for its clock
+                    let apb_clk = unsafe { ApbClk::enable(pm) };
 
                     // Lots of union fields which require unsafe access
                     unsafe {
@@ -250,16 +336,23 @@ macro_rules! uart {
                     Self {
                         padout,
                         sercom,
+                        apb_clk,
                     }
                 }
 
                 pub fn free(self) -> ([<$Type Padout>]<RX, TX, RTS, CTS>, $SERCOM) {
+                    self.apb_clk.disable();
                     (self.padout, self.sercom)
                 }
 
                 /// Splits the UART into transmit and receive halves
+                ///
+                /// Either half can be dropped while the other one is still in
+                /// use, so the APB clock stays enabled until the halves are
+                /// joined again and the UART is freed.
                 pub fn split(self) -> ([<$Type Tx>]<TX, RTS>, [<$Type Rx>]<RX, CTS>) {
                     let (tx_pads, rx_pads) = self.padout.split();
+                    self.apb_clk.leak();
                     (
                         [<$Type Tx>] {
                             padout: tx_pads,
@@ -277,6 +370,9 @@ macro_rules! uart {
                     Self {
                         padout: [<$Type Padout>]::join(tx.padout, rx.padout),
                         sercom: tx.sercom,
+                        // SAFETY: The token was leaked by `split`, and both
+                        // halves are needed to recreate it
+                        apb_clk: unsafe { ApbClk::assume_enabled() },
                     }
                 }
 
@@ -314,6 +410,20 @@ macro_rules! uart {
                         self.usart().status.read()
                     }
                 }
+
+                /// Run in standby mode
+                ///
+                /// When set, the UART keeps running while the chip is in
+                /// standby, so received data can wake up the CPU. This is
+                /// enabled by default.
+                pub fn run_in_standby(&mut self, set: bool) {
+                    let usart = unsafe { self.usart() };
+                    usart.ctrla.modify(|_, w| w.enable().clear_bit());
+                    while usart.syncbusy.read().enable().bit_is_set() {}
+                    usart.ctrla.modify(|_, w| w.runstdby().bit(set));
+                    usart.ctrla.modify(|_, w| w.enable().set_bit());
+                    while usart.syncbusy.read().enable().bit_is_set() {}
+                }
             }
 
             /// The transmitting half of the corresponding UARTX instance (as returned by `UARTX::split`)
@@ -438,20 +548,212 @@ macro_rules! uart {
                     self.bwrite_all(s.as_bytes()).map_err(|_| fmt::Error)
                 }
             }
+
+            /// UARTXSync represents the corresponding SERCOMX instance
+            /// configured to act as a synchronous USART (USRT), with the
+            /// serial clock on the XCK pad.
+            /// Objects of this type implement the HAL `serial::Read`,
+            /// `serial::Write` traits.
+            ///
+            /// In [`XckMode::Master`] mode, the baud rate generator drives the
+            /// XCK pad. In [`XckMode::Slave`] mode, XCK is an input and the
+            /// baud rate is set by the remote device.
+            pub struct [<$Type Sync>]<RX, TX, XCK> {
+                padout: [<$Type SyncPadout>]<RX, TX, XCK>,
+                sercom: $SERCOM,
+                apb_clk: ApbClk<bus::$Sercom>,
+            }
+
+            impl<RX, TX, XCK> [<$Type Sync>]<RX, TX, XCK> {
+                /// Power on and configure SERCOMX to work as a synchronous
+                /// USART with the XCK pad as a clock output, running at the
+                /// specified frequency.
+                ///
+                /// You can use any tuple of three SercomXPadY instances for
+                /// which there exists a From implementation for
+                /// UARTXSyncPadout.
+                pub fn master<F: Into<Hertz>, T: Into<[<$Type SyncPadout>]<RX, TX, XCK>>>(
+                    clock: &clock::$clock,
+                    freq: F,
+                    sercom: $SERCOM,
+                    pm: &mut PM,
+                    padout: T,
+                    polarity: XckPolarity,
+                ) -> Self where
+                    [<$Type SyncPadout>]<RX, TX, XCK>: RxpoTxpo {
+                    let baud = calculate_sync_baud_value(freq.into().0, clock.freq().0);
+                    Self::create(sercom, pm, padout.into(), XckMode::Master, polarity, baud)
+                }
+
+                /// Power on and configure SERCOMX to work as a synchronous
+                /// USART clocked by an external signal on the XCK pad.
+                ///
+                /// The maximum XCK frequency is one quarter of the SERCOM core
+                /// clock frequency.
+                pub fn slave<T: Into<[<$Type SyncPadout>]<RX, TX, XCK>>>(
+                    sercom: $SERCOM,
+                    pm: &mut PM,
+                    padout: T,
+                    polarity: XckPolarity,
+                ) -> Self where
+                    [<$Type SyncPadout>]<RX, TX, XCK>: RxpoTxpo {
+                    Self::create(sercom, pm, padout.into(), XckMode::Slave, polarity, 0)
+                }
+
+                fn create(
+                    sercom: $SERCOM,
+                    pm: &mut PM,
+                    padout: [<$Type SyncPadout>]<RX, TX, XCK>,
+                    mode: XckMode,
+                    polarity: XckPolarity,
+                    baud: u16,
+                ) -> Self where
+                    [<$Type SyncPadout>]<RX, TX, XCK>: RxpoTxpo {
+                    // SAFETY: We own the SERCOM, so no other token exists for its clock
+                    let apb_clk = unsafe { ApbClk::enable(pm) };
+
+                    // Lots of union fields which require unsafe access
+                    unsafe {
+                        // Reset
+                        sercom.usart().ctrla.modify(|_, w| w.swrst().set_bit());
+                        while sercom.usart().syncbusy.read().swrst().bit_is_set()
+                            || sercom.usart().ctrla.read().swrst().bit_is_set() {
+                            // wait for sync of CTRLA.SWRST
+                        }
+
+                        // Unsafe b/c of direct call to bits on rxpo/txpo
+                        sercom.usart().ctrla.modify(|_, w| {
+                            w.dord().set_bit();
+
+                            let (rxpo, txpo) = padout.rxpo_txpo();
+                            w.rxpo().bits(rxpo);
+                            w.txpo().bits(txpo);
+
+                            w.form().bits(0); // 0 is no parity bits
+                            w.runstdby().set_bit(); // Run in standby
+                            w.cpol().bit(polarity == XckPolarity::FallingEdgeTx);
+
+                            match mode {
+                                XckMode::Master => w.mode().usart_int_clk(), // XCK is an output
+                                XckMode::Slave => w.mode().usart_ext_clk(), // XCK is an input
+                            };
+                            w.cmode().set_bit() // Synchronous mode
+                        });
+
+                        // The BAUD register is only used to generate XCK in
+                        // master mode
+                        sercom.usart().baud().modify(|_, w| {
+                            w.baud().bits(baud)
+                        });
+
+                        sercom.usart().ctrlb.modify(|_, w| {
+                            w.sbmode().clear_bit(); // 0 is one stop bit
+                            w.chsize().bits(0x0);
+                            w.txen().set_bit();
+                            w.rxen().set_bit()
+                        });
+
+                        while sercom.usart().syncbusy.read().ctrlb().bit_is_set() {}
+
+                        sercom.usart().ctrla.modify(|_, w| w.enable().set_bit());
+                        // wait for sync of ENABLE
+                        while sercom.usart().syncbusy.read().enable().bit_is_set() {}
+                    }
+
+                    Self {
+                        padout,
+                        sercom,
+                        apb_clk,
+                    }
+                }
+
+                pub fn free(self) -> ([<$Type SyncPadout>]<RX, TX, XCK>, $SERCOM) {
+                    self.apb_clk.disable();
+                    (self.padout, self.sercom)
+                }
+
+                fn usart(&self) -> &USART {
+                    return &self.sercom.usart();
+                }
+
+                pub fn intenset<F>(&mut self, f: F)
+                where F: FnOnce(&mut crate::target_device::sercom0::usart::intenset::W)
+                {
+                    self.usart().intenset.write(|w| {
+                        f(w);
+                        w
+                    });
+                }
+
+                pub fn intenclr<F>(&mut self, f: F)
+                where F: FnOnce(&mut crate::target_device::sercom0::usart::intenclr::W)
+                {
+                    self.usart().intenclr.write(|w| {
+                        f(w);
+                        w
+                    });
+                }
+
+                pub fn flags(&self) -> crate::target_device::sercom0::usart::status::R {
+                    self.usart().status.read()
+                }
+
+                /// Run in standby mode
+                ///
+                /// When set, the UART keeps running while the chip is in
+                /// standby, so received data can wake up the CPU. This is
+                /// enabled by default.
+                pub fn run_in_standby(&mut self, set: bool) {
+                    let usart = self.usart();
+                    usart.ctrla.modify(|_, w| w.enable().clear_bit());
+                    while usart.syncbusy.read().enable().bit_is_set() {}
+                    usart.ctrla.modify(|_, w| w.runstdby().bit(set));
+                    usart.ctrla.modify(|_, w| w.enable().set_bit());
+                    while usart.syncbusy.read().enable().bit_is_set() {}
+                }
+            }
+
+            impl<RX, TX, XCK> serial::Write<u8> for [<$Type Sync>]<RX, TX, XCK> {
+                type Error = ();
+
+                fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+                    [<$Type Tx>]::<TX, ()>::do_write(self.usart(), word)
+                }
+
+                fn flush(&mut self) -> nb::Result<(), Self::Error> {
+                    [<$Type Tx>]::<TX, ()>::do_flush(self.usart())
+                }
+            }
+
+            impl<RX, TX, XCK> serial::Read<u8> for [<$Type Sync>]<RX, TX, XCK> {
+                type Error = ();
+
+                fn read(&mut self) -> nb::Result<u8, Self::Error> {
+                    [<$Type Rx>]::<RX, ()>::do_read(self.usart())
+                }
+            }
+
+            impl<RX, TX, XCK> Default<u8> for [<$Type Sync>]<RX, TX, XCK> {}
+
+            impl<RX, TX, XCK> fmt::Write for [<$Type Sync>]<RX, TX, XCK> {
+                fn write_str(&mut self, s: &str) -> fmt::Result {
+                    self.bwrite_all(s.as_bytes()).map_err(|_| fmt::Error)
+                }
+            }
         }
     }
 }
 
-uart!(UART0: (Sercom0, SERCOM0, sercom0_, Sercom0CoreClock));
-uart!(UART1: (Sercom1, SERCOM1, sercom1_, Sercom1CoreClock));
+uart!(UART0: (Sercom0, SERCOM0, Sercom0CoreClock));
+uart!(UART1: (Sercom1, SERCOM1, Sercom1CoreClock));
 #[cfg(feature = "samd21")]
-uart!(UART2: (Sercom2, SERCOM2, sercom2_, Sercom2CoreClock));
+uart!(UART2: (Sercom2, SERCOM2, Sercom2CoreClock));
 #[cfg(feature = "samd21")]
-uart!(UART3: (Sercom3, SERCOM3, sercom3_, Sercom3CoreClock));
+uart!(UART3: (Sercom3, SERCOM3, Sercom3CoreClock));
 #[cfg(feature = "min-samd21g")]
-uart!(UART4: (Sercom4, SERCOM4, sercom4_, Sercom4CoreClock));
+uart!(UART4: (Sercom4, SERCOM4, Sercom4CoreClock));
 #[cfg(feature = "min-samd21g")]
-uart!(UART5: (Sercom5, SERCOM5, sercom5_, Sercom5CoreClock));
+uart!(UART5: (Sercom5, SERCOM5, Sercom5CoreClock));
 
 const SHIFT: u8 = 32;
 
@@ -463,3 +765,16 @@ fn calculate_baud_value(baudrate: u32, clk_freq: u32, n_samples: u8) -> u16 {
 
     baud_calculated as u16
 }
+
+/// Calculate the BAUD register value for synchronous mode
+///
+/// In synchronous mode, the XCK frequency is `clk_freq / (2 * (BAUD + 1))`.
+/// Requests outside the achievable range saturate at the extremes.
+fn calculate_sync_baud_value(baudrate: u32, clk_freq: u32) -> u16 {
+    let baud = (clk_freq / 2 / baudrate).saturating_sub(1);
+    if baud <= u16::MAX as u32 {
+        baud as u16
+    } else {
+        u16::MAX
+    }
+}
@@ -51,7 +51,13 @@ macro_rules! map {
     };
 }
 
+/// Value-level equivalent of a single [`Map`] implementation
+type DynMap = (usize, DynPadNum, DynAlternate);
+
 // Feature gates can be placed on individual configurations or on the entire pin
+//
+// Alongside the Map implementations, the table also produces an equivalent
+// PAD_MAP constant, which is used to validate DynPads at run-time.
 macro_rules! pad_table {
     (
         $(
@@ -71,6 +77,20 @@ macro_rules! pad_table {
                 )+
             );
         )+
+        const PAD_MAP: &[(DynPinId, &[DynMap])] = &[
+            $(
+                $( #[$pinid_cfg] )?
+                (
+                    $PinId::DYN,
+                    &[
+                        $(
+                            $( #[$padnum_cfg] )?
+                            ($Sercom::NUM, DynPadNum::$PadNum, DynAlternate::$Cfg),
+                        )+
+                    ],
+                ),
+            )+
+        ];
     };
 }
 
@@ -187,14 +207,14 @@ pad_table!(
         C: (Sercom5, Pad3),
         D: (Sercom3, Pad3),
     }
+    #[cfg(feature = "samd21")]
     PA22 {
-        #[cfg(feature = "samd21")]
         C: (Sercom3, Pad0),
         #[cfg(feature = "min-samd21g")]
         D: (Sercom5, Pad0),
     }
+    #[cfg(feature = "samd21")]
     PA23 {
-        #[cfg(feature = "samd21")]
         C: (Sercom3, Pad1),
         #[cfg(feature = "min-samd21g")]
         D: (Sercom5, Pad1),
@@ -298,3 +318,42 @@ pad_table!(
         D: (Sercom5, Pad1),
     }
 );
+
+//==============================================================================
+//  Run-time lookup
+//==============================================================================
+
+/// Look up the [`DynAlternate`] mode for a given SERCOM number, [`DynPadNum`]
+/// and [`DynPinId`]
+///
+/// This is the value-level equivalent of the [`Map`] trait. It returns `None`
+/// if the combination does not exist.
+pub(crate) fn dyn_map(sercom: usize, pad: DynPadNum, id: DynPinId) -> Option<DynAlternate> {
+    PAD_MAP
+        .iter()
+        .filter(|(i, _)| *i == id)
+        .flat_map(|(_, maps)| maps.iter())
+        .find(|(s, p, _)| *s == sercom && *p == pad)
+        .map(|&(_, _, mode)| mode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dyn_map_matches_pad_table() {
+        assert!(dyn_map(0, DynPadNum::Pad0, PA04::DYN) == Some(DynAlternate::D));
+        #[cfg(feature = "samd21")]
+        assert!(dyn_map(3, DynPadNum::Pad0, PA22::DYN) == Some(DynAlternate::C));
+        #[cfg(feature = "samd11")]
+        assert!(dyn_map(1, DynPadNum::Pad0, PA30::DYN) == Some(DynAlternate::C));
+    }
+
+    #[test]
+    fn dyn_map_rejects_invalid_pads() {
+        // PA04 is SERCOM0 PAD0, not PAD1, and not on SERCOM1
+        assert!(dyn_map(0, DynPadNum::Pad1, PA04::DYN).is_none());
+        assert!(dyn_map(1, DynPadNum::Pad0, PA04::DYN).is_none());
+    }
+}
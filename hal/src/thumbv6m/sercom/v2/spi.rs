@@ -113,7 +113,7 @@
 
 use core::convert::{TryFrom, TryInto};
 use core::marker::PhantomData;
-use core::mem::transmute;
+use core::mem::{transmute, ManuallyDrop};
 
 use bitflags::bitflags;
 use embedded_hal::blocking;
@@ -129,6 +129,7 @@ use pac::sercom0::spi::ctrla::MODE_A;
 use pac::sercom0::RegisterBlock;
 use pac::PM;
 
+use crate::clock::bus::ApbClk;
 use crate::gpio::v2::{AnyPin, SpecificPin};
 use crate::sercom::v2::pads::{Map, Pad0, Pad1, Pad2, Pad3, PadNum};
 use crate::sercom::v2::pads::{OptionalPad, Pad, SomePad};
@@ -999,6 +1000,7 @@ where
 {
     sercom: P::Sercom,
     pads: P,
+    apb_clk: ApbClk<<P::Sercom as Sercom>::ApbClkId>,
     mode: PhantomData<M>,
     chsize: PhantomData<C>,
     freq: Hertz,
@@ -1006,7 +1008,12 @@ where
 
 impl<P: DipoDopo> Config<P> {
     /// Create a new [`Config`] in the default configuration
-    fn create(sercom: P::Sercom, pads: P, freq: impl Into<Hertz>) -> Self {
+    fn create(
+        sercom: P::Sercom,
+        pads: P,
+        apb_clk: ApbClk<<P::Sercom as Sercom>::ApbClkId>,
+        freq: impl Into<Hertz>,
+    ) -> Self {
         Self::swrst(&sercom);
         Master::configure(&sercom);
         P::configure(&sercom);
@@ -1014,6 +1021,7 @@ impl<P: DipoDopo> Config<P> {
         Self {
             sercom,
             pads,
+            apb_clk,
             mode: PhantomData,
             chsize: PhantomData,
             freq: freq.into(),
@@ -1025,14 +1033,16 @@ impl<P: DipoDopo> Config<P> {
     /// This function will enable the corresponding APB clock, reset the
     /// [`Sercom`] peripheral, and return a [`Config`] in the default
     /// configuration, [`Master`] [`Mode`] with an [`EightBit`] [`CharSize`].
-    /// [`Config`] takes ownership of the [`Sercom`] and [`Pads`].
+    /// [`Config`] takes ownership of the [`Sercom`] and [`Pads`]. The APB clock
+    /// is gated off again when the [`Config`] is freed or dropped.
     ///
     /// Users must configure GCLK manually. The `freq` parameter represents the
     /// GCLK frequency for this [`Sercom`] instance.
     #[inline]
-    pub fn new(pm: &PM, mut sercom: P::Sercom, pads: P, freq: impl Into<Hertz>) -> Self {
-        sercom.enable_apb_clock(pm);
-        Self::create(sercom, pads, freq)
+    pub fn new(pm: &PM, sercom: P::Sercom, pads: P, freq: impl Into<Hertz>) -> Self {
+        // SAFETY: We own the SERCOM, so no other token exists for its clock
+        let apb_clk = unsafe { ApbClk::enable(pm) };
+        Self::create(sercom, pads, apb_clk, freq)
     }
 }
 
@@ -1069,6 +1079,7 @@ where
         Config {
             sercom: self.sercom,
             pads: self.pads,
+            apb_clk: self.apb_clk,
             mode: PhantomData,
             chsize: PhantomData,
             freq: self.freq,
@@ -1079,7 +1090,7 @@ where
     /// default configuration.
     #[inline]
     pub fn reset(self) -> Config<P> {
-        Config::create(self.sercom, self.pads, self.freq)
+        Config::create(self.sercom, self.pads, self.apb_clk, self.freq)
     }
 
     /// Obtain a reference to the PAC `SERCOM` struct
@@ -1091,11 +1102,12 @@ where
         &self.sercom
     }
 
-    /// Consume the [`Config`], reset the peripheral, and return the [`Sercom`]
-    /// and [`Pads`]
+    /// Consume the [`Config`], reset the peripheral, gate off its APB clock,
+    /// and return the [`Sercom`] and [`Pads`]
     #[inline]
     pub fn free(self) -> (P::Sercom, P) {
         Self::swrst(&self.sercom);
+        self.apb_clk.disable();
         (self.sercom, self.pads)
     }
 
@@ -1185,6 +1197,38 @@ where
         self
     }
 
+    /// Return the current SPI mode (clock polarity & phase)
+    #[inline]
+    pub fn get_spi_mode(&self) -> spi::Mode {
+        let ctrla = self.sercom.spi().ctrla.read();
+        let polarity = if ctrla.cpol().bit_is_set() {
+            Polarity::IdleHigh
+        } else {
+            Polarity::IdleLow
+        };
+        let phase = if ctrla.cpha().bit_is_set() {
+            Phase::CaptureOnSecondTransition
+        } else {
+            Phase::CaptureOnFirstTransition
+        };
+        spi::Mode { polarity, phase }
+    }
+
+    /// Return the current baud rate
+    ///
+    /// The baud rate is calculated from the BAUD register and the stored GCLK
+    /// frequency, so it can be lower than the value passed to
+    /// [`Config::baud`]. Passing it back to [`Config::baud`] restores the same
+    /// BAUD register setting as long as the GCLK frequency is at least
+    /// `2 * (BAUD + 1) * (BAUD + 2)` Hz, which holds for any BAUD value with a
+    /// GCLK of 132 kHz or more. Below that, the baud rate can't be expressed
+    /// precisely enough in whole Hertz.
+    #[inline]
+    pub fn get_baud(&self) -> Hertz {
+        let baud = self.sercom.spi().baud.read().baud().bits() as u32;
+        Hertz(self.freq.0 / 2 / (baud + 1))
+    }
+
     /// Control the buffer overflow notification
     ///
     /// If set to true, an [`Error::Overflow`] will be issued as soon as an
@@ -1451,11 +1495,16 @@ impl<C: ValidConfig> Spi<C> {
         self.config.as_mut().enable_peripheral(false);
 
         // Perform a bitwise copy of the old configuration. This will be used as default
-        // in case the call to update(self.config) panics. This should be safe
-        // as either one of self.config or old_config will be used, and Config
-        // does not deallocate when dropped.
-        let old_config = unsafe { core::ptr::read(&mut self.config as *const _) };
-        replace_with::replace_with(&mut self.config, || old_config, |c| update(c.into()).into());
+        // in case the call to update(self.config) panics. The copy is wrapped in
+        // ManuallyDrop, because the Config owns the APB clock token, which gates
+        // off the clock when dropped. It is only unwrapped on the panic path, so
+        // exactly one of self.config or old_config is ever dropped.
+        let old_config = ManuallyDrop::new(unsafe { core::ptr::read(&self.config) });
+        replace_with::replace_with(
+            &mut self.config,
+            || ManuallyDrop::into_inner(old_config),
+            |c| update(c.into()).into(),
+        );
 
         self.config.as_mut().enable_peripheral(true);
     }
@@ -1515,6 +1564,20 @@ impl<C: ValidConfig> Spi<C> {
         Ok(self.read_flags())
     }
 
+    /// Return the current SPI mode (clock polarity & phase)
+    #[inline]
+    pub fn get_spi_mode(&self) -> spi::Mode {
+        self.config.as_ref().get_spi_mode()
+    }
+
+    /// Return the current baud rate
+    ///
+    /// See [`Config::get_baud`] for more details.
+    #[inline]
+    pub fn get_baud(&self) -> Hertz {
+        self.config.as_ref().get_baud()
+    }
+
     /// Read from the DATA register
     ///
     /// Reading from the data register directly is `unsafe`, because it will
@@ -0,0 +1,87 @@
+//! # Sleep modes
+//!
+//! The chip goes to sleep when the CPU executes WFI. The depth of sleep is
+//! selected by `SCR.SLEEPDEEP` and, for the idle modes, `PM.SLEEP.IDLE`:
+//!
+//! - [`SleepMode::Idle0`] stops the CPU clock.
+//! - [`SleepMode::Idle1`] also stops the AHB clocks.
+//! - [`SleepMode::Idle2`] also stops the APB clocks.
+//! - [`SleepMode::Standby`] stops all clocks, except those requested by
+//!   peripherals configured to run in standby. RAM is always retained.
+//!
+//! [`sleep`] returns the reason the chip woke up.
+//!
+//! ```no_run
+//! # use atsamd_hal::sleep::{self, SleepMode, WakeReason};
+//! # use atsamd_hal::target_device::{Interrupt, CorePeripherals, PM};
+//! # fn f(pm: &mut PM, core: &mut CorePeripherals) {
+//! match sleep::sleep(pm, &mut core.SCB, SleepMode::Standby) {
+//!     WakeReason::Interrupt(irq) if irq == Interrupt::RTC as u16 => {
+//!         // Woken by the RTC
+//!     }
+//!     _ => {}
+//! }
+//! # }
+//! ```
+
+use cortex_m::peripheral::{NVIC, SCB};
+
+use crate::target_device::pm::sleep::IDLE_A;
+use crate::target_device::PM;
+
+/// Sleep mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SleepMode {
+    Idle0,
+    Idle1,
+    Idle2,
+    Standby,
+}
+
+/// Reason the chip woke up
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WakeReason {
+    /// An enabled interrupt is pending. Contains its IRQ number, which can be
+    /// compared with the PAC `Interrupt` values.
+    Interrupt(u16),
+    /// No enabled interrupt is pending, e.g. the chip woke up on an event
+    Unknown,
+}
+
+/// Enter a sleep mode and return the reason the chip woke up
+///
+/// Interrupts are masked while the chip sleeps and the wake reason is read,
+/// so the interrupt that woke it up is only handled once this returns.
+pub fn sleep(pm: &mut PM, scb: &mut SCB, mode: SleepMode) -> WakeReason {
+    let idle = match mode {
+        SleepMode::Idle0 => Some(IDLE_A::CPU),
+        SleepMode::Idle1 => Some(IDLE_A::AHB),
+        SleepMode::Idle2 => Some(IDLE_A::APB),
+        SleepMode::Standby => None,
+    };
+    match idle {
+        Some(idle) => {
+            pm.sleep.write(|w| w.idle().variant(idle));
+            scb.clear_sleepdeep();
+        }
+        None => scb.set_sleepdeep(),
+    }
+
+    cortex_m::interrupt::free(|_| {
+        cortex_m::asm::dsb();
+        cortex_m::asm::wfi();
+        pending_interrupt()
+    })
+}
+
+/// Find the lowest-numbered interrupt that is both enabled and pending
+fn pending_interrupt() -> WakeReason {
+    // SAFETY: Read-only access to the NVIC
+    let nvic = unsafe { &*NVIC::ptr() };
+    let pending = nvic.ispr[0].read() & nvic.iser[0].read();
+    if pending != 0 {
+        WakeReason::Interrupt(pending.trailing_zeros() as u16)
+    } else {
+        WakeReason::Unknown
+    }
+}
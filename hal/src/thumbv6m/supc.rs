@@ -0,0 +1,177 @@
+//! # Supply control
+//!
+//! The SAMD11 and SAMD21 have no separate supply controller. The 3.3V
+//! brown-out detector, voltage regulator and voltage reference are controlled
+//! through SYSCTRL instead:
+//!
+//! - The BOD33 monitors VDD. When VDD drops below the configured threshold, it
+//!   can reset the chip or raise an interrupt. See [`Bod33Config`].
+//! - On the SAMD21, the voltage regulator can be forced to stay in LDO mode in
+//!   Standby. See [`force_ldo`].
+//! - The bandgap voltage reference can be routed to the ADC and DAC. See
+//!   [`set_bandgap_output`].
+//!
+//! ```no_run
+//! # use atsamd_hal::supc::{self, Bod33Action, Bod33Config};
+//! # fn f(sysctrl: &mut atsamd_hal::target_device::SYSCTRL) {
+//! // Interrupt rather than reset on a brown-out
+//! let bod33 = Bod33Config::new(39)
+//!     .hysteresis(true)
+//!     .action(Bod33Action::Interrupt);
+//! supc::configure_bod33(sysctrl, bod33);
+//! supc::enable_bod33_interrupt(sysctrl);
+//! # }
+//! ```
+
+use crate::target_device::sysctrl::bod33;
+use crate::target_device::SYSCTRL;
+
+//==============================================================================
+// BOD33
+//==============================================================================
+
+/// Action taken when the BOD33 detects a brown-out
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bod33Action {
+    /// No action
+    None,
+    /// Reset the chip
+    Reset,
+    /// Raise the BOD33DET interrupt
+    Interrupt,
+}
+
+/// BOD33 configuration
+///
+/// The threshold level is a raw register value. See the electrical
+/// characteristics in the datasheet for the matching voltages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bod33Config {
+    level: u8,
+    hysteresis: bool,
+    action: Bod33Action,
+    run_standby: bool,
+}
+
+impl Bod33Config {
+    /// Create a new configuration with the threshold level, from 0 to 63
+    ///
+    /// By default, the BOD33 resets the chip, has no hysteresis and only runs
+    /// in active mode.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `level` is greater than 63.
+    #[inline]
+    pub const fn new(level: u8) -> Self {
+        assert!(level <= 0x3F, "BOD33 level out of range");
+        Self {
+            level,
+            hysteresis: false,
+            action: Bod33Action::Reset,
+            run_standby: false,
+        }
+    }
+
+    /// Enable the hysteresis
+    #[inline]
+    pub const fn hysteresis(mut self, hysteresis: bool) -> Self {
+        self.hysteresis = hysteresis;
+        self
+    }
+
+    /// Set the action taken on a brown-out
+    #[inline]
+    pub const fn action(mut self, action: Bod33Action) -> Self {
+        self.action = action;
+        self
+    }
+
+    /// Keep the BOD33 running in Standby
+    #[inline]
+    pub const fn run_standby(mut self, run_standby: bool) -> Self {
+        self.run_standby = run_standby;
+        self
+    }
+}
+
+/// Disable the BOD33 and wait for the change to take effect
+fn bod33_disable(sysctrl: &mut SYSCTRL) {
+    sysctrl.bod33.modify(|_, w| w.enable().clear_bit());
+    while sysctrl.pclksr.read().b33srdy().bit_is_clear() {}
+}
+
+/// Configure and enable the BOD33
+///
+/// The BOD33 is disabled while it is reconfigured, so this waits until it is
+/// ready again before returning.
+pub fn configure_bod33(sysctrl: &mut SYSCTRL, config: Bod33Config) {
+    use bod33::ACTION_A;
+    let action = match config.action {
+        Bod33Action::None => ACTION_A::NONE,
+        Bod33Action::Reset => ACTION_A::RESET,
+        Bod33Action::Interrupt => ACTION_A::INTERRUPT,
+    };
+    bod33_disable(sysctrl);
+    sysctrl.bod33.modify(|_, w| {
+        // SAFETY: The level was checked by the config
+        unsafe { w.level().bits(config.level) };
+        w.hyst().bit(config.hysteresis);
+        w.action().variant(action);
+        w.runstdby().bit(config.run_standby);
+        // Continuous mode
+        w.mode().clear_bit()
+    });
+    sysctrl.bod33.modify(|_, w| w.enable().set_bit());
+    while sysctrl.pclksr.read().b33srdy().bit_is_clear() {}
+    while sysctrl.pclksr.read().bod33rdy().bit_is_clear() {}
+}
+
+/// Disable the BOD33
+pub fn disable_bod33(sysctrl: &mut SYSCTRL) {
+    bod33_disable(sysctrl);
+}
+
+/// Returns `true` if VDD is currently below the BOD33 threshold
+#[inline]
+pub fn bod33_detected(sysctrl: &SYSCTRL) -> bool {
+    sysctrl.pclksr.read().bod33det().bit_is_set()
+}
+
+/// Enable the BOD33DET interrupt
+#[inline]
+pub fn enable_bod33_interrupt(sysctrl: &mut SYSCTRL) {
+    sysctrl.intenset.write(|w| w.bod33det().set_bit());
+}
+
+/// Disable the BOD33DET interrupt
+#[inline]
+pub fn disable_bod33_interrupt(sysctrl: &mut SYSCTRL) {
+    sysctrl.intenclr.write(|w| w.bod33det().set_bit());
+}
+
+/// Clear the BOD33DET interrupt flag
+#[inline]
+pub fn clear_bod33_interrupt(sysctrl: &mut SYSCTRL) {
+    sysctrl.intflag.write(|w| w.bod33det().set_bit());
+}
+
+//==============================================================================
+// Voltage regulator and reference
+//==============================================================================
+
+/// Keep the voltage regulator in LDO mode in Standby
+///
+/// By default, the regulator switches to a low-power mode in Standby, which
+/// can only supply a limited current.
+#[cfg(feature = "samd21")]
+#[inline]
+pub fn force_ldo(sysctrl: &mut SYSCTRL, force: bool) {
+    sysctrl.vreg.modify(|_, w| w.forceldo().bit(force));
+}
+
+/// Route the bandgap voltage reference to the ADC and DAC
+#[inline]
+pub fn set_bandgap_output(sysctrl: &mut SYSCTRL, enable: bool) {
+    sysctrl.vref.modify(|_, w| w.bgouten().bit(enable));
+}
@@ -2,7 +2,7 @@
 #[cfg(feature = "samd11")]
 use crate::target_device::tc1::COUNT16;
 #[cfg(feature = "samd21")]
-use crate::target_device::tc3::COUNT16;
+use crate::target_device::tc3::{COUNT16, COUNT32};
 #[allow(unused)]
 #[cfg(feature = "samd11")]
 use crate::target_device::{PM, TC1};
@@ -13,39 +13,61 @@ use crate::timer_params::TimerParams;
 use hal::timer::{CountDown, Periodic};
 
 use crate::clock;
-use crate::time::{Hertz, Nanoseconds};
+use crate::clock::bus::{self, ApbClk, ApbId};
+use crate::time::{Hertz, Microseconds, Nanoseconds};
 use crate::timer_traits::InterruptDrivenTimer;
 use void::Void;
 
 use cortex_m::asm::delay as cycle_delay;
 
+pub mod capture;
+#[cfg(feature = "samd21")]
+pub mod monotonic;
+
 // Note:
-// TC3 + TC4 can be paired to make a 32-bit counter
-// TC5 + TC6 can be paired to make a 32-bit counter
+// On the SAMD21, TC4 + TC5 can be paired to make a 32-bit counter
+// On the SAMD21, TC6 + TC7 can be paired to make a 32-bit counter
 
 /// A generic hardware timer counter.
-/// The counters are exposed in 16-bit mode only.
-/// The hardware allows configuring the 8-bit mode
-/// and pairing up some instances to run in 32-bit
-/// mode, but that functionality is not currently
-/// exposed by this hal implementation.
+/// The counters are exposed in 16-bit mode.
+/// The hardware allows configuring the 8-bit mode,
+/// but that functionality is not currently
+/// exposed by this hal implementation. On the SAMD21,
+/// pairs of instances are exposed in 32-bit mode by
+/// `TimerCounter32`.
 /// TimerCounter implements both the `Periodic` and
 /// the `CountDown` embedded_hal timer traits.
 /// Before a hardware timer can be used, it must first
 /// have a clock configured.
-pub struct TimerCounter<TC> {
+pub struct TimerCounter<TC: Count16> {
     freq: Hertz,
     tc: TC,
+    apb_clk: ApbClk<TC::ApbId>,
+    run_standby: bool,
+}
+
+impl<TC: Count16> TimerCounter<TC> {
+    /// Run in standby mode
+    ///
+    /// When set, the timer keeps counting while the chip is in standby. This
+    /// is enabled by default and takes effect the next time the timer is
+    /// started.
+    pub fn run_in_standby(&mut self, set: bool) {
+        self.run_standby = set;
+    }
 }
 
 /// This is a helper trait to make it easier to make most of the
 /// TimerCounter impl generic.  It doesn't make too much sense to
 /// to try to implement this trait outside of this module.
 pub trait Count16 {
+    /// Bus clock ID of the TC
+    type ApbId: ApbId;
+
     fn count_16(&self) -> &COUNT16;
 }
 
-impl<TC> Periodic for TimerCounter<TC> {}
+impl<TC: Count16> Periodic for TimerCounter<TC> {}
 impl<TC> CountDown for TimerCounter<TC>
 where
     TC: Count16,
@@ -98,8 +120,8 @@ where
             };
             // Enable Match Frequency Waveform generation
             w.wavegen().mfrq();
-            w.enable().set_bit();
-            w.runstdby().set_bit()
+            w.runstdby().bit(self.run_standby);
+            w.enable().set_bit()
         });
     }
 
@@ -136,12 +158,145 @@ where
     }
 }
 
+/// A 32-bit hardware timer counter, built from a pair of TCs.
+/// The even-numbered master TC counts the lower 16 bits and
+/// the odd-numbered slave TC the upper 16 bits. The slave
+/// only provides its registers to the master, and is owned by
+/// the pair until it is freed.
+/// Periods that fit in 32 bits run without a prescaler, at
+/// the full resolution of the clock, and timeouts of up to
+/// 2^32 microseconds are accepted.
+/// TimerCounter32 implements both the `Periodic` and
+/// the `CountDown` embedded_hal timer traits. It doesn't
+/// implement `InterruptDrivenTimer`, which takes its timeouts
+/// in nanoseconds, so use a [`TimerCounter`] for a
+/// `SleepingDelay`.
+#[cfg(feature = "samd21")]
+pub struct TimerCounter32<TC: Count16, SLAVE: Count16> {
+    freq: Hertz,
+    tc: TC,
+    slave: SLAVE,
+    apb_clk: ApbClk<TC::ApbId>,
+    slave_apb_clk: ApbClk<SLAVE::ApbId>,
+    run_standby: bool,
+}
+
+#[cfg(feature = "samd21")]
+impl<TC: Count16, SLAVE: Count16> TimerCounter32<TC, SLAVE> {
+    /// Run in standby mode
+    ///
+    /// When set, the timer keeps counting while the chip is in standby. This
+    /// is enabled by default and takes effect the next time the timer is
+    /// started.
+    pub fn run_in_standby(&mut self, set: bool) {
+        self.run_standby = set;
+    }
+}
+
+/// The 32-bit counterpart of [`Count16`], implemented by the
+/// master TC of each pair.
+#[cfg(feature = "samd21")]
+pub trait Count32: Count16 {
+    fn count_32(&self) -> &COUNT32;
+}
+
+#[cfg(feature = "samd21")]
+impl<TC: Count16, SLAVE: Count16> Periodic for TimerCounter32<TC, SLAVE> {}
+#[cfg(feature = "samd21")]
+impl<TC, SLAVE> CountDown for TimerCounter32<TC, SLAVE>
+where
+    TC: Count32,
+    SLAVE: Count16,
+{
+    type Time = Microseconds;
+
+    fn start<T>(&mut self, timeout: T)
+    where
+        T: Into<Self::Time>,
+    {
+        let params = TimerParams::new_us_32(timeout, self.freq.0);
+        let count = self.tc.count_32();
+
+        // Disable the timer while we reconfigure it
+        count.ctrla.modify(|_, w| w.enable().clear_bit());
+        while count.status.read().syncbusy().bit_is_set() {}
+
+        count.ctrla.write(|w| w.swrst().set_bit());
+        while count.status.read().syncbusy().bit_is_set() {}
+        // the SVD erroneously marks swrst as write-only, so we
+        // need to manually read the bit here
+        while count.ctrla.read().bits() & 1 != 0 {}
+
+        // The CC registers are only 32 bits wide in 32-bit mode
+        count.ctrla.write(|w| w.mode().count32());
+
+        count.ctrlbset.write(|w| {
+            // Count up when the direction bit is zero
+            w.dir().clear_bit();
+            // Periodic
+            w.oneshot().clear_bit()
+        });
+
+        // Set TOP value for mfrq mode
+        count.cc[0].write(|w| unsafe { w.cc().bits(params.cycles) });
+
+        count.ctrla.modify(|_, w| {
+            match params.divider {
+                1 => w.prescaler().div1(),
+                2 => w.prescaler().div2(),
+                4 => w.prescaler().div4(),
+                8 => w.prescaler().div8(),
+                16 => w.prescaler().div16(),
+                64 => w.prescaler().div64(),
+                256 => w.prescaler().div256(),
+                1024 => w.prescaler().div1024(),
+                _ => unreachable!(),
+            };
+            // Enable Match Frequency Waveform generation
+            w.wavegen().mfrq();
+            w.runstdby().bit(self.run_standby);
+            w.enable().set_bit()
+        });
+    }
+
+    fn wait(&mut self) -> nb::Result<(), Void> {
+        let count = self.tc.count_32();
+        if count.intflag.read().ovf().bit_is_set() {
+            // Writing a 1 clears the flag
+            count.intflag.modify(|_, w| w.ovf().set_bit());
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
+#[cfg(feature = "samd21")]
+impl<TC, SLAVE> TimerCounter32<TC, SLAVE>
+where
+    TC: Count32,
+    SLAVE: Count16,
+{
+    /// Enable the interrupt generation for this hardware timer.
+    /// The interrupt is raised by the master TC.
+    pub fn enable_interrupt(&mut self) {
+        self.tc.count_32().intenset.write(|w| w.ovf().set_bit());
+    }
+
+    /// Disables interrupt generation for this hardware timer.
+    pub fn disable_interrupt(&mut self) {
+        self.tc.count_32().intenclr.write(|w| w.ovf().set_bit());
+    }
+}
+
 macro_rules! tc {
-    ($($TYPE:ident: ($TC:ident, $pm:ident, $clock:ident),)+) => {
+    ($($TYPE:ident: ($TC:ident, $pm:ident, $clock:ident, $Tc:ident),)+) => {
         $(
 pub type $TYPE = TimerCounter<$TC>;
 
 impl Count16 for $TC {
+    type ApbId = bus::$Tc;
+
     fn count_16(&self) -> &COUNT16 {
         self.count16()
     }
@@ -156,8 +311,8 @@ impl TimerCounter<$TC>
     /// Note that some hardware timer instances share the same clock
     /// generator instance and thus will be clocked at the same rate.
     pub fn $pm(clock: &clock::$clock, tc: $TC, pm: &mut PM) -> Self {
-        // this is safe because we're constrained to just the tc3 bit
-        pm.apbcmask.modify(|_, w| w.$pm().set_bit());
+        // SAFETY: We own the TC, so no other token exists for its clock
+        let apb_clk = unsafe { ApbClk::enable(pm) };
         {
             let count = tc.count_16();
 
@@ -168,8 +323,19 @@ impl TimerCounter<$TC>
         Self {
             freq: clock.freq(),
             tc,
+            apb_clk,
+            run_standby: true,
         }
     }
+
+    /// Disable the timer counter, gate off its APB clock and return the TC
+    pub fn free(self) -> $TC {
+        let count = self.tc.count_16();
+        count.ctrla.modify(|_, w| w.enable().clear_bit());
+        while count.status.read().syncbusy().bit_is_set() {}
+        self.apb_clk.disable();
+        self.tc
+    }
 }
         )+
     }
@@ -178,14 +344,78 @@ impl TimerCounter<$TC>
 // samd11
 #[cfg(feature = "samd11")]
 tc! {
-    TimerCounter1: (TC1, tc1_, Tc1Tc2Clock),
+    TimerCounter1: (TC1, tc1_, Tc1Tc2Clock, Tc1),
 }
 // samd21
 #[cfg(feature = "samd21")]
 tc! {
-    TimerCounter3: (TC3, tc3_, Tcc2Tc3Clock),
-    TimerCounter4: (TC4, tc4_, Tc4Tc5Clock),
-    TimerCounter5: (TC5, tc5_, Tc4Tc5Clock),
+    TimerCounter3: (TC3, tc3_, Tcc2Tc3Clock, Tc3),
+    TimerCounter4: (TC4, tc4_, Tc4Tc5Clock, Tc4),
+    TimerCounter5: (TC5, tc5_, Tc4Tc5Clock, Tc5),
+}
+
+#[cfg(feature = "samd21")]
+macro_rules! tc32 {
+    ($($TYPE:ident: ($TC:ident, $SLAVE:ident, $pm:ident, $clock:ident),)+) => {
+        $(
+pub type $TYPE = TimerCounter32<$TC, $SLAVE>;
+
+impl Count32 for $TC {
+    fn count_32(&self) -> &COUNT32 {
+        self.count32()
+    }
+}
+
+impl TimerCounter32<$TC, $SLAVE>
+{
+    /// Configure this pair of timer counter instances.
+    /// The clock is obtained from the `GenericClockController` instance
+    /// and its frequency impacts the resolution and maximum range of
+    /// the timeout values that can be passed to the `start` method.
+    /// Both instances of the pair share this clock.
+    pub fn $pm(clock: &clock::$clock, tc: $TC, slave: $SLAVE, pm: &mut PM) -> Self {
+        // SAFETY: We own both TCs, so no other tokens exist for their clocks
+        let (apb_clk, slave_apb_clk) = unsafe { (ApbClk::enable(pm), ApbClk::enable(pm)) };
+        {
+            let count = tc.count_32();
+
+            // Disable the timer while we reconfigure it
+            count.ctrla.modify(|_, w| w.enable().clear_bit());
+            while count.status.read().syncbusy().bit_is_set() {}
+        }
+        Self {
+            freq: clock.freq(),
+            tc,
+            slave,
+            apb_clk,
+            slave_apb_clk,
+            run_standby: true,
+        }
+    }
+
+    /// Disable the timer counter, gate off the APB clocks and return the
+    /// TCs
+    pub fn free(self) -> ($TC, $SLAVE) {
+        let count = self.tc.count_32();
+        count.ctrla.modify(|_, w| w.enable().clear_bit());
+        while count.status.read().syncbusy().bit_is_set() {}
+        // Return the master to 16-bit mode, so the slave can be used on its
+        // own again
+        count.ctrla.write(|w| w.swrst().set_bit());
+        while count.status.read().syncbusy().bit_is_set() {}
+        while count.ctrla.read().bits() & 1 != 0 {}
+        self.apb_clk.disable();
+        self.slave_apb_clk.disable();
+        (self.tc, self.slave)
+    }
+}
+        )+
+    }
+}
+
+#[cfg(feature = "samd21")]
+tc32! {
+    TimerCounter4_5: (TC4, TC5, tc4_tc5, Tc4Tc5Clock),
 }
 
 #[derive(Clone, Copy)]
@@ -0,0 +1,237 @@
+//! # Monotonic timer
+//!
+//! [`TcMonotonic`] turns a [`TimerCounter32`] into a 64-bit monotonic timer.
+//! The pair of TCs runs freely, the upper 32 bits are counted in software on
+//! the overflow interrupt, and compare register 0 raises an interrupt at a
+//! scheduled instant, so no periodic tick is needed. Like the TC, it can keep
+//! counting in Standby with [`TimerCounter32::run_in_standby`].
+//!
+//! With the `rtic` feature, [`TcMonotonic`] implements the RTIC `Monotonic`
+//! trait. Bind the interrupt of the master TC to the monotonic, e.g.
+//! `#[monotonic(binds = TC4, default = true)]`.
+//!
+//! Without RTIC, call [`TcMonotonic::on_interrupt`] from the interrupt
+//! handler, and schedule wake-ups with [`TcMonotonic::set_alarm`]. Instants
+//! are plain tick counts, so the time between two instants is their
+//! difference divided by the frequency.
+//!
+//! ```no_run
+//! # use atsamd_hal::timer::TimerCounter4_5;
+//! # use atsamd_hal::timer::monotonic::TcMonotonic;
+//! # fn f(timer: TimerCounter4_5) {
+//! let mut mono = TcMonotonic::<_, _, 48_000_000>::new(timer);
+//! let start = mono.now();
+//! mono.set_alarm(start + 48_000);
+//! mono.enable_alarm();
+//! # }
+//! ```
+
+use super::{Count16, Count32, TimerCounter32};
+use crate::time::Hertz;
+
+/// A 64-bit monotonic timer running at `FREQ` Hz, built on a pair of TCs
+///
+/// See the [module-level](self) documentation for more details.
+pub struct TcMonotonic<TC: Count16, SLAVE: Count16, const FREQ: u32> {
+    timer: TimerCounter32<TC, SLAVE>,
+    overflows: u32,
+}
+
+impl<TC: Count32, SLAVE: Count16, const FREQ: u32> TcMonotonic<TC, SLAVE, FREQ> {
+    /// Start the monotonic timer from zero
+    ///
+    /// The counter runs without a prescaler up to its maximum value, and the
+    /// overflow interrupt is enabled. The interrupt of the master TC must also
+    /// be unmasked in the NVIC. The run in standby setting of the timer is
+    /// kept.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the TC clock frequency is not `FREQ`.
+    pub fn new(timer: TimerCounter32<TC, SLAVE>) -> Self {
+        assert_eq!(timer.freq.0, FREQ, "TC clock frequency mismatch");
+
+        let count = timer.tc.count_32();
+        count.ctrla.modify(|_, w| w.enable().clear_bit());
+        while count.status.read().syncbusy().bit_is_set() {}
+        count.ctrla.write(|w| w.swrst().set_bit());
+        while count.status.read().syncbusy().bit_is_set() {}
+        // the SVD erroneously marks swrst as write-only, so we
+        // need to manually read the bit here
+        while count.ctrla.read().bits() & 1 != 0 {}
+
+        // The waveform generation is reset to normal frequency, so the counter
+        // wraps around at its maximum value
+        count.ctrla.write(|w| {
+            w.mode().count32();
+            w.prescaler().div1();
+            w.runstdby().bit(timer.run_standby);
+            w.enable().set_bit()
+        });
+        while count.status.read().syncbusy().bit_is_set() {}
+
+        let mut mono = Self {
+            timer,
+            overflows: 0,
+        };
+        // SAFETY: The timer isn't shared with anything else
+        unsafe { mono.restart() };
+        mono
+    }
+
+    /// Reset the counter to zero and enable the overflow interrupt
+    ///
+    /// # Safety
+    ///
+    /// Instants taken before the reset are no longer comparable with new ones.
+    unsafe fn restart(&mut self) {
+        let count = self.timer.tc.count_32();
+        count.count.write(|w| w.count().bits(0));
+        while count.status.read().syncbusy().bit_is_set() {}
+        self.overflows = 0;
+        count.intflag.write(|w| w.ovf().set_bit().mc0().set_bit());
+        count.intenset.write(|w| w.ovf().set_bit());
+    }
+
+    /// Synchronize and read the counter
+    fn count(&self) -> u32 {
+        let count = self.timer.tc.count_32();
+        // SAFETY: The address is the offset of COUNT, 0x10
+        count
+            .readreq
+            .write(|w| unsafe { w.rreq().set_bit().addr().bits(0x10) });
+        while count.status.read().syncbusy().bit_is_set() {}
+        count.count.read().count().bits()
+    }
+
+    /// Returns the counting frequency
+    #[inline]
+    pub fn freq(&self) -> Hertz {
+        Hertz(FREQ)
+    }
+
+    /// Returns the current number of ticks
+    pub fn now(&mut self) -> u64 {
+        cortex_m::interrupt::free(|_| {
+            let mut count = self.count();
+            let mut overflows = self.overflows;
+            // The counter overflowed, but the interrupt hasn't been handled yet
+            if self.timer.tc.count_32().intflag.read().ovf().bit_is_set() {
+                overflows = overflows.wrapping_add(1);
+                // The overflow may have happened after reading the counter
+                count = self.count();
+            }
+            ((overflows as u64) << 32) | count as u64
+        })
+    }
+
+    /// Schedule the compare interrupt at `instant`, in ticks
+    ///
+    /// Only the lower 32 bits are compared, so an alarm more than 2^32 ticks
+    /// ahead fires early, and an alarm in the past only fires once the counter
+    /// wraps around. Check [`now`](Self::now) after setting the alarm and when
+    /// it fires.
+    pub fn set_alarm(&mut self, instant: u64) {
+        let count = self.timer.tc.count_32();
+        count.cc[0].write(|w| unsafe { w.cc().bits(instant as u32) });
+        while count.status.read().syncbusy().bit_is_set() {}
+    }
+
+    /// Clear the compare interrupt flag
+    #[inline]
+    pub fn clear_alarm(&mut self) {
+        self.timer
+            .tc
+            .count_32()
+            .intflag
+            .write(|w| w.mc0().set_bit());
+    }
+
+    /// Enable the compare interrupt
+    #[inline]
+    pub fn enable_alarm(&mut self) {
+        self.timer
+            .tc
+            .count_32()
+            .intenset
+            .write(|w| w.mc0().set_bit());
+    }
+
+    /// Disable the compare interrupt
+    #[inline]
+    pub fn disable_alarm(&mut self) {
+        self.timer
+            .tc
+            .count_32()
+            .intenclr
+            .write(|w| w.mc0().set_bit());
+    }
+
+    /// Handle the overflow interrupt
+    ///
+    /// Call this from the interrupt handler of the master TC. The compare
+    /// interrupt flag is left for the caller to clear.
+    pub fn on_interrupt(&mut self) {
+        let intflag = &self.timer.tc.count_32().intflag;
+        if intflag.read().ovf().bit_is_set() {
+            intflag.write(|w| w.ovf().set_bit());
+            self.overflows = self.overflows.wrapping_add(1);
+        }
+    }
+
+    /// Disable the interrupts and the timer, and return it
+    pub fn free(self) -> TimerCounter32<TC, SLAVE> {
+        let count = self.timer.tc.count_32();
+        count.intenclr.write(|w| w.ovf().set_bit().mc0().set_bit());
+        count.ctrla.modify(|_, w| w.enable().clear_bit());
+        while count.status.read().syncbusy().bit_is_set() {}
+        self.timer
+    }
+}
+
+#[cfg(feature = "rtic")]
+impl<TC: Count32, SLAVE: Count16, const FREQ: u32> rtic_monotonic::Monotonic
+    for TcMonotonic<TC, SLAVE, FREQ>
+{
+    type Instant = fugit::TimerInstantU64<FREQ>;
+    type Duration = fugit::TimerDurationU64<FREQ>;
+
+    #[inline]
+    fn now(&mut self) -> Self::Instant {
+        Self::Instant::from_ticks(TcMonotonic::now(self))
+    }
+
+    #[inline]
+    fn zero() -> Self::Instant {
+        Self::Instant::from_ticks(0)
+    }
+
+    unsafe fn reset(&mut self) {
+        self.restart();
+    }
+
+    #[inline]
+    fn set_compare(&mut self, instant: Self::Instant) {
+        self.set_alarm(instant.ticks());
+    }
+
+    #[inline]
+    fn clear_compare_flag(&mut self) {
+        self.clear_alarm();
+    }
+
+    #[inline]
+    fn on_interrupt(&mut self) {
+        TcMonotonic::on_interrupt(self);
+    }
+
+    #[inline]
+    fn enable_timer(&mut self) {
+        self.enable_alarm();
+    }
+
+    #[inline]
+    fn disable_timer(&mut self) {
+        self.disable_alarm();
+    }
+}
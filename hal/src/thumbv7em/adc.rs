@@ -90,6 +90,33 @@ impl Adc<$ADC> {
         while self.adc.syncbusy.read().refctrl().bit_is_set() {}
     }
 
+    /// Run in standby mode
+    ///
+    /// When set, the ADC keeps converting while the chip is in standby, e.g.
+    /// to wake up the CPU on a window comparison. This is disabled by default.
+    pub fn run_in_standby(&mut self, set: bool) {
+        let enabled = self.adc.ctrla.read().enable().bit_is_set();
+        self.power_down();
+        self.adc.ctrla.modify(|_, w| w.runstdby().bit(set));
+        if enabled {
+            self.power_up();
+        }
+    }
+
+    /// Request the generic clock on demand
+    ///
+    /// When set, the ADC only requests its generic clock while converting, so
+    /// the clock source can stop in standby between conversions. This is
+    /// disabled by default.
+    pub fn on_demand(&mut self, set: bool) {
+        let enabled = self.adc.ctrla.read().enable().bit_is_set();
+        self.power_down();
+        self.adc.ctrla.modify(|_, w| w.ondemand().bit(set));
+        if enabled {
+            self.power_up();
+        }
+    }
+
     fn power_up(&mut self) {
         while self.adc.syncbusy.read().enable().bit_is_set() {}
         self.adc.ctrla.modify(|_, w| w.enable().set_bit());
@@ -0,0 +1,170 @@
+//! # Backup RAM
+//!
+//! The SAMD51 has 8 KiB of backup RAM, BKUPRAM, starting at
+//! [`BKUPRAM_START`]. It is powered by the backup domain, so unlike the system
+//! RAM, it keeps its contents in Backup and while running from VBAT.
+//! Retention in Hibernate and Backup is configured with
+//! [`sleep::set_hibernate_ram`](crate::sleep::set_hibernate_ram) and
+//! [`sleep::set_backup_ram`](crate::sleep::set_backup_ram).
+//!
+//! Statics are placed in backup RAM with the [`backup_ram!`](crate::backup_ram)
+//! macro. This requires a `BKUPRAM` region and a `.bkupram` section in the
+//! linker script, e.g. in `memory.x`:
+//!
+//! ```text
+//! MEMORY
+//! {
+//!   /* FLASH and RAM as usual */
+//!   BKUPRAM : ORIGIN = 0x47000000, LENGTH = 8K
+//! }
+//!
+//! SECTIONS
+//! {
+//!   .bkupram (NOLOAD) : ALIGN(4)
+//!   {
+//!     *(.bkupram .bkupram.*);
+//!     . = ALIGN(4);
+//!   } > BKUPRAM
+//! }
+//! ```
+//!
+//! The section is `NOLOAD`, so it is neither zeroed nor initialized at
+//! startup, and it keeps its contents across resets. Its contents are
+//! undefined after a power-on reset, so only [`Retained`] types, for which any
+//! bit pattern is valid, can be stored there. Check the reset cause to decide
+//! whether the contents can be trusted.
+//!
+//! ```no_run
+//! # use atsamd_hal::{reset_cause, ResetCause};
+//! atsamd_hal::backup_ram! {
+//!     /// Number of wake-ups from Backup
+//!     static WAKE_COUNT: u32;
+//! }
+//!
+//! # fn f(rstc: &atsamd_hal::target_device::RSTC) {
+//! if let ResetCause::Backup = reset_cause(rstc) {
+//!     WAKE_COUNT.update(|count| count + 1);
+//! } else {
+//!     WAKE_COUNT.write(0);
+//! }
+//! # }
+//! ```
+//!
+//! The RTC also has backup registers, which are retained in the same way. See
+//! [`Rtc::backup_register`](crate::rtc::Rtc::backup_register).
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::ptr;
+
+/// Start address of the backup RAM
+pub const BKUPRAM_START: usize = 0x4700_0000;
+
+/// Size of the backup RAM, in bytes
+pub const BKUPRAM_SIZE: usize = 8 * 1024;
+
+/// Types for which any bit pattern is a valid value
+///
+/// # Safety
+///
+/// Implementors must be `Copy`, have no padding, and be valid for any bit
+/// pattern.
+pub unsafe trait Retained: Copy {}
+
+macro_rules! retained {
+    ($($Type:ty),+) => {
+        $(
+            unsafe impl Retained for $Type {}
+        )+
+    };
+}
+
+retained!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+unsafe impl<T: Retained, const N: usize> Retained for [T; N] {}
+
+/// A value in backup RAM
+///
+/// Create one with the [`backup_ram!`](crate::backup_ram) macro. All accesses
+/// are volatile and happen inside a critical section, so they can't be torn by
+/// interrupts.
+#[repr(transparent)]
+pub struct BackupCell<T: Retained> {
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+// SAFETY: All accesses happen inside a critical section
+unsafe impl<T: Retained + Send> Sync for BackupCell<T> {}
+
+impl<T: Retained> BackupCell<T> {
+    /// Create a cell without initializing it
+    ///
+    /// Only meant to be used by [`backup_ram!`](crate::backup_ram), which
+    /// places the cell in the `NOLOAD` backup RAM section.
+    #[doc(hidden)]
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Read the value
+    #[inline]
+    pub fn read(&self) -> T {
+        // SAFETY: `T` is valid for any bit pattern
+        cortex_m::interrupt::free(|_| unsafe { ptr::read_volatile(self.value.get() as *const T) })
+    }
+
+    /// Write the value
+    #[inline]
+    pub fn write(&self, value: T) {
+        // SAFETY: The pointer is valid and aligned for `T`
+        cortex_m::interrupt::free(|_| unsafe {
+            ptr::write_volatile(self.value.get() as *mut T, value)
+        })
+    }
+
+    /// Read-modify-write the value and return the new value
+    #[inline]
+    pub fn update(&self, f: impl FnOnce(T) -> T) -> T {
+        cortex_m::interrupt::free(|_| {
+            let ptr = self.value.get() as *mut T;
+            // SAFETY: `T` is valid for any bit pattern, and the pointer is
+            // valid and aligned for `T`
+            unsafe {
+                let value = f(ptr::read_volatile(ptr));
+                ptr::write_volatile(ptr, value);
+                value
+            }
+        })
+    }
+}
+
+/// Place statics in backup RAM
+///
+/// Each static becomes a [`BackupCell`] in the `.bkupram` section. See the
+/// [`backup`](crate::backup) module for the required linker script.
+///
+/// ```no_run
+/// atsamd_hal::backup_ram! {
+///     static STATE: [u32; 4];
+///     pub static BOOT_COUNT: u16;
+/// }
+/// ```
+#[macro_export]
+macro_rules! backup_ram {
+    (
+        $(
+            $( #[$attr:meta] )*
+            $vis:vis static $NAME:ident: $Type:ty;
+        )+
+    ) => {
+        $(
+            $( #[$attr] )*
+            #[link_section = ".bkupram"]
+            $vis static $NAME: $crate::backup::BackupCell<$Type> =
+                $crate::backup::BackupCell::uninit();
+        )+
+    };
+}
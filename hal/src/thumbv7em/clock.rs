@@ -8,10 +8,19 @@ use crate::target_device::gclk::pchctrl::GEN_A::*;
 use crate::target_device::{self, GCLK, MCLK, NVMCTRL, OSC32KCTRL, OSCCTRL};
 use crate::time::{Hertz, MegaHertz};
 
+pub mod bus;
+pub mod cfd;
+pub mod config;
+pub mod gclk_io;
+pub mod scaling;
+pub mod tree;
+pub mod v2;
+
 pub type ClockGenId = target_device::gclk::pchctrl::GEN_A;
 pub type ClockSource = target_device::gclk::genctrl::SRC_A;
 
 #[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ClockId {
     DFLL48 = 0,
     FDPLL0,
@@ -130,7 +139,8 @@ impl State {
             w.divsel().clear_bit();
             w.idc().bit(improve_duty_cycle);
             w.genen().set_bit();
-            w.oe().set_bit()
+            // GCLK_IO is an input when the source is GCLKIN
+            w.oe().bit(src != GCLKIN)
         });
 
         self.wait_for_sync();
@@ -156,9 +166,23 @@ impl State {
 /// the system to run at 120MHz by taking the DFLL48
 /// and feeding it into the DPLL0 hardware which multiplies the
 /// signal by 2.5x.
+///
+/// To configure a different clock tree, e.g. using the XOSC0 or XOSC1 crystal
+/// oscillators or DPLL1, use a [`ClockConfig`](config::ClockConfig) instead.
+///
+/// The CPU frequency can be changed at run-time with the functions in the
+/// [`scaling`] module.
+///
+/// `GenericClockController` does not stop a GCLK from being reconfigured while
+/// peripherals depend on it. The [`v2`] module tracks the users of each clock
+/// at the type level instead.
 pub struct GenericClockController {
     state: State,
     gclks: [Hertz; 12],
+    xoscs: [Hertz; 2],
+    dplls: [Hertz; 2],
+    gclkins: [Hertz; 8],
+    cpu_div: u8,
     used_clocks: u64,
 }
 
@@ -259,6 +283,10 @@ impl GenericClockController {
                 Hertz(0),
                 Hertz(0),
             ],
+            xoscs: [Hertz(0); 2],
+            dplls: [OSC120M_FREQ, Hertz(0)],
+            gclkins: [Hertz(0); 8],
+            cpu_div: 1,
             used_clocks: 1u64 << u8::from(ClockId::FDPLL0),
         }
     }
@@ -302,7 +330,10 @@ impl GenericClockController {
     /// `improve_duty_cycle` is a boolean that, when set to true, enables
     /// a 50/50 duty cycle for odd divider values.
     /// Returns a `GClock` for the configured clock generator.
-    /// Returns `None` if the clock generator has already been configured.
+    /// Returns `None` if the clock generator has already been configured,
+    /// or if the source is an oscillator or DPLL that is not running.
+    /// The `GCLKIN` source must first be set up with
+    /// [`configure_gclk_in`](Self::configure_gclk_in).
     pub fn configure_gclk_divider_and_source(
         &mut self,
         gclk: ClockGenId,
@@ -314,17 +345,44 @@ impl GenericClockController {
         if self.gclks[idx].0 != 0 {
             return None;
         }
+        let freq = self.source_freq(gclk, src);
+        if freq.0 == 0 {
+            return None;
+        }
         self.state
             .set_gclk_divider_and_source(gclk, divider, src, improve_duty_cycle);
-        let freq: Hertz = match src {
+        self.gclks[idx] = Hertz(freq.0 / divider as u32);
+        Some(GClock { gclk, freq })
+    }
+
+    /// Returns the frequency of a clock source for the given generator, or
+    /// zero if it is not running
+    fn source_freq(&self, gclk: ClockGenId, src: ClockSource) -> Hertz {
+        match src {
             XOSC32K | OSCULP32K => OSC32K_FREQ,
             GCLKGEN1 => self.gclks[1],
             DFLL => OSC48M_FREQ,
-            DPLL0 => OSC120M_FREQ,
-            XOSC0 | XOSC1 | GCLKIN | DPLL1 => unimplemented!(),
-        };
-        self.gclks[idx] = Hertz(freq.0 / divider as u32);
-        Some(GClock { gclk, freq })
+            DPLL0 => self.dplls[0],
+            DPLL1 => self.dplls[1],
+            XOSC0 => self.xoscs[0],
+            XOSC1 => self.xoscs[1],
+            GCLKIN => self
+                .gclkins
+                .get(u8::from(gclk) as usize)
+                .copied()
+                .unwrap_or(Hertz(0)),
+        }
+    }
+
+    /// Returns the frequency of XOSC0 or XOSC1, if it was enabled by a
+    /// [`ClockConfig`](config::ClockConfig)
+    pub fn xosc_freq(&self, index: usize) -> Option<Hertz> {
+        self.xoscs.get(index).copied().filter(|f| f.0 != 0)
+    }
+
+    /// Returns the frequency of DPLL0 or DPLL1, if it is enabled
+    pub fn dpll_freq(&self, index: usize) -> Option<Hertz> {
+        self.dplls.get(index).copied().filter(|f| f.0 != 0)
     }
 
     /// Enables or disables the given GClk from operation in standby.
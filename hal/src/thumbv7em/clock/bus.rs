@@ -0,0 +1,324 @@
+//! # AHB and APB bus clocks
+//!
+//! The registers of a peripheral can only be accessed while its bus clock is
+//! enabled in one of the MCLK `AHBMASK` or `APBxMASK` registers. Gating the
+//! bus clocks of unused peripherals saves power.
+//!
+//! [`ApbClk`] and [`AhbClk`] are tokens representing an enabled bus clock. The
+//! clock is enabled when the token is created and gated off again when the
+//! token is dropped. Each peripheral is identified by a type in this module,
+//! e.g. [`Sercom0`] or [`Tc3`].
+//!
+//! Drivers that own their peripheral keep the token for as long as they
+//! exist, so the bus clock is gated off when the driver is freed or dropped.
+//! This is the case for the v1 and v2 SERCOM drivers,
+//! [`TimerCounter`](crate::timer::TimerCounter),
+//! [`Trng`](crate::trng::Trng) and [`Freqm`](crate::freqm::Freqm).
+//!
+//! ```no_run
+//! # use atsamd_hal::clock::bus::{Ac, ApbClk};
+//! # fn f(mclk: &mut atsamd_hal::target_device::MCLK) {
+//! // SAFETY: Nothing else has a token for the AC clock
+//! let apb = unsafe { ApbClk::<Ac>::enable(mclk) };
+//! // Use the AC through the PAC
+//! apb.disable();
+//! # }
+//! ```
+//!
+//! Dropping any token gates its clock off, so only one token may exist for
+//! each bus clock. The drivers in this crate create the token for the
+//! peripheral they own. Creating a token directly is `unsafe`, because the
+//! caller must make sure that no driver or other token uses the same clock.
+//! Use [`ApbClk::leak`] to keep a clock running for good.
+
+use core::marker::PhantomData;
+
+use paste::paste;
+
+use crate::target_device::mclk::RegisterBlock;
+use crate::target_device::MCLK;
+use crate::typelevel::Sealed;
+
+/// Read-modify-write the MCLK masks, which are shared by all tokens
+#[inline]
+fn modify(f: impl FnOnce(&RegisterBlock)) {
+    cortex_m::interrupt::free(|_| {
+        // SAFETY: Each token only touches its own bit, and the modification
+        // can't be interrupted
+        let mclk = unsafe { &*MCLK::ptr() };
+        f(mclk)
+    });
+}
+
+//==============================================================================
+// Bus clock IDs
+//==============================================================================
+
+/// A peripheral with a clock on one of the APB buses
+pub trait ApbId: Sealed {
+    #[doc(hidden)]
+    fn set(mclk: &RegisterBlock, enabled: bool);
+    #[doc(hidden)]
+    fn is_enabled(mclk: &RegisterBlock) -> bool;
+}
+
+/// A peripheral with a clock on the AHB bus
+pub trait AhbId: Sealed {
+    #[doc(hidden)]
+    fn set(mclk: &RegisterBlock, enabled: bool);
+    #[doc(hidden)]
+    fn is_enabled(mclk: &RegisterBlock) -> bool;
+}
+
+macro_rules! bus_ids {
+    (
+        $(
+            $( #[$cfg:meta] )?
+            $Type:ident
+        ),+ $(,)?
+    ) => {
+        $(
+            paste! {
+                $( #[$cfg] )?
+                #[doc = "Bus clock ID of the " $Type:upper]
+                pub enum $Type {}
+
+                $( #[$cfg] )?
+                impl Sealed for $Type {}
+            }
+        )+
+    };
+}
+
+macro_rules! bus_clocks {
+    (
+        $Trait:ident:
+        $(
+            $( #[$cfg:meta] )?
+            ($Type:ident, $mask:ident, $field:ident),
+        )+
+    ) => {
+        $(
+            $( #[$cfg] )?
+            impl $Trait for $Type {
+                #[inline]
+                fn set(mclk: &RegisterBlock, enabled: bool) {
+                    mclk.$mask.modify(|_, w| w.$field().bit(enabled));
+                }
+
+                #[inline]
+                fn is_enabled(mclk: &RegisterBlock) -> bool {
+                    mclk.$mask.read().$field().bit()
+                }
+            }
+        )+
+    };
+}
+
+bus_ids!(
+    Ac,
+    Adc0,
+    Adc1,
+    Aes,
+    Ccl,
+    Dac,
+    Dmac,
+    Eic,
+    Evsys,
+    Freqm,
+    Icm,
+    Pdec,
+    Qspi,
+    Rtc,
+    Sercom0,
+    Sercom1,
+    Sercom2,
+    Sercom3,
+    Sercom4,
+    Sercom5,
+    #[cfg(feature = "min-samd51n")]
+    Sercom6,
+    #[cfg(feature = "min-samd51n")]
+    Sercom7,
+    Tc0,
+    Tc1,
+    Tc2,
+    Tc3,
+    #[cfg(feature = "min-samd51j")]
+    Tc4,
+    #[cfg(feature = "min-samd51j")]
+    Tc5,
+    #[cfg(feature = "min-samd51n")]
+    Tc6,
+    #[cfg(feature = "min-samd51n")]
+    Tc7,
+    Tcc0,
+    Tcc1,
+    Tcc2,
+    #[cfg(feature = "min-samd51j")]
+    Tcc3,
+    #[cfg(feature = "min-samd51j")]
+    Tcc4,
+    Trng,
+    Usb,
+    Wdt,
+);
+
+bus_clocks!(
+    ApbId:
+    (Ac, apbcmask, ac_),
+    (Adc0, apbdmask, adc0_),
+    (Adc1, apbdmask, adc1_),
+    (Aes, apbcmask, aes_),
+    (Ccl, apbcmask, ccl_),
+    (Dac, apbdmask, dac_),
+    (Eic, apbamask, eic_),
+    (Evsys, apbbmask, evsys_),
+    (Freqm, apbamask, freqm_),
+    (Icm, apbcmask, icm_),
+    (Pdec, apbcmask, pdec_),
+    (Qspi, apbcmask, qspi_),
+    (Rtc, apbamask, rtc_),
+    (Sercom0, apbamask, sercom0_),
+    (Sercom1, apbamask, sercom1_),
+    (Sercom2, apbbmask, sercom2_),
+    (Sercom3, apbbmask, sercom3_),
+    (Sercom4, apbdmask, sercom4_),
+    (Sercom5, apbdmask, sercom5_),
+    #[cfg(feature = "min-samd51n")]
+    (Sercom6, apbdmask, sercom6_),
+    #[cfg(feature = "min-samd51n")]
+    (Sercom7, apbdmask, sercom7_),
+    (Tc0, apbamask, tc0_),
+    (Tc1, apbamask, tc1_),
+    (Tc2, apbbmask, tc2_),
+    (Tc3, apbbmask, tc3_),
+    #[cfg(feature = "min-samd51j")]
+    (Tc4, apbcmask, tc4_),
+    #[cfg(feature = "min-samd51j")]
+    (Tc5, apbcmask, tc5_),
+    #[cfg(feature = "min-samd51n")]
+    (Tc6, apbdmask, tc6_),
+    #[cfg(feature = "min-samd51n")]
+    (Tc7, apbdmask, tc7_),
+    (Tcc0, apbbmask, tcc0_),
+    (Tcc1, apbbmask, tcc1_),
+    (Tcc2, apbcmask, tcc2_),
+    #[cfg(feature = "min-samd51j")]
+    (Tcc3, apbcmask, tcc3_),
+    #[cfg(feature = "min-samd51j")]
+    (Tcc4, apbdmask, tcc4_),
+    (Trng, apbcmask, trng_),
+    (Usb, apbbmask, usb_),
+    (Wdt, apbamask, wdt_),
+);
+
+bus_clocks!(
+    AhbId:
+    (Dmac, ahbmask, dmac_),
+    (Icm, ahbmask, icm_),
+    (Qspi, ahbmask, qspi_),
+    (Usb, ahbmask, usb_),
+);
+
+//==============================================================================
+// Tokens
+//==============================================================================
+
+/// An enabled APB clock
+///
+/// The clock is gated off when the token is dropped.
+pub struct ApbClk<A: ApbId> {
+    id: PhantomData<A>,
+}
+
+impl<A: ApbId> ApbClk<A> {
+    /// Enable the APB clock
+    ///
+    /// # Safety
+    ///
+    /// No other token may exist for the same clock. Dropping either token
+    /// would gate off the clock while the other one is still in use.
+    #[inline]
+    pub unsafe fn enable(_mclk: &MCLK) -> Self {
+        modify(|mclk| A::set(mclk, true));
+        Self { id: PhantomData }
+    }
+
+    /// Create a token for a clock that was enabled without one
+    ///
+    /// # Safety
+    ///
+    /// The clock must be enabled, and no other token may exist for it.
+    #[inline]
+    pub(crate) unsafe fn assume_enabled() -> Self {
+        Self { id: PhantomData }
+    }
+
+    /// Returns `true` if the APB clock is enabled, with or without a token
+    #[inline]
+    pub fn is_enabled(mclk: &MCLK) -> bool {
+        A::is_enabled(mclk)
+    }
+
+    /// Gate off the APB clock
+    #[inline]
+    pub fn disable(self) {}
+
+    /// Consume the token, leaving the APB clock enabled for good
+    #[inline]
+    pub fn leak(self) {
+        core::mem::forget(self);
+    }
+}
+
+impl<A: ApbId> Drop for ApbClk<A> {
+    #[inline]
+    fn drop(&mut self) {
+        modify(|mclk| A::set(mclk, false));
+    }
+}
+
+/// An enabled AHB clock
+///
+/// The clock is gated off when the token is dropped.
+pub struct AhbClk<A: AhbId> {
+    id: PhantomData<A>,
+}
+
+impl<A: AhbId> AhbClk<A> {
+    /// Enable the AHB clock
+    ///
+    /// # Safety
+    ///
+    /// No other token may exist for the same clock. Dropping either token
+    /// would gate off the clock while the other one is still in use.
+    #[inline]
+    pub unsafe fn enable(_mclk: &MCLK) -> Self {
+        modify(|mclk| A::set(mclk, true));
+        Self { id: PhantomData }
+    }
+
+    /// Returns `true` if the AHB clock is enabled, with or without a token
+    #[inline]
+    pub fn is_enabled(mclk: &MCLK) -> bool {
+        A::is_enabled(mclk)
+    }
+
+    /// Gate off the AHB clock
+    #[inline]
+    pub fn disable(self) {}
+
+    /// Consume the token, leaving the AHB clock enabled for good
+    #[inline]
+    pub fn leak(self) {
+        core::mem::forget(self);
+    }
+}
+
+impl<A: AhbId> Drop for AhbClk<A> {
+    #[inline]
+    fn drop(&mut self) {
+        modify(|mclk| A::set(mclk, false));
+    }
+}
@@ -0,0 +1,221 @@
+//! # Clock failure detection
+//!
+//! XOSC0, XOSC1 and XOSC32K each have a clock failure detector (CFD). When the
+//! CFD is armed and the oscillator stops, its output is automatically switched
+//! to a safe clock, so everything downstream keeps running instead of hanging.
+//! The failure is flagged in the STATUS register and can raise an interrupt.
+//!
+//! - For XOSC0 and XOSC1, the safe clock is the DFLL divided by
+//!   `2^prescaler`, so the DFLL must be running. The interrupts are
+//!   `OSCCTRL_XOSC0` and `OSCCTRL_XOSC1`. If switch back is enabled, the
+//!   oscillator output returns to the crystal once it recovers.
+//! - For XOSC32K, the safe clock is OSCULP32K, optionally divided by two. The
+//!   interrupt is `OSC32KCTRL`. Switching back to the crystal is requested with
+//!   [`Cfd::switch_back`].
+//!
+//! With the [`GenericClockController`](super::GenericClockController), arm the
+//! CFD once the oscillator is running, e.g. after
+//! [`with_external_32kosc`](super::GenericClockController::with_external_32kosc):
+//!
+//! ```no_run
+//! # use atsamd_hal::clock::cfd::Cfd;
+//! # fn f(osc32kctrl: &mut atsamd_hal::target_device::OSC32KCTRL) {
+//! let cfd = Cfd::xosc32k(osc32kctrl, false);
+//! cfd.enable_interrupt();
+//! // In the OSC32KCTRL interrupt handler
+//! if cfd.is_interrupt_pending() {
+//!     cfd.clear_interrupt();
+//!     // XOSC32K failed, everything it fed now runs from OSCULP32K
+//! }
+//! # }
+//! ```
+//!
+//! With the [`v2`](super::v2) API, use the `enable_failure_detection` methods
+//! of the enabled [`Xosc`](super::v2::Xosc) and
+//! [`Xosc32k`](super::v2::Xosc32k) instead.
+
+use crate::target_device::{osc32kctrl, oscctrl, OSC32KCTRL, OSCCTRL};
+
+/// An oscillator with a clock failure detector
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CfdOsc {
+    Xosc0,
+    Xosc1,
+    Xosc32k,
+}
+
+/// Arm the CFD of XOSC0 or XOSC1
+///
+/// # Panics
+///
+/// Panics if `prescaler` is greater than 15.
+pub(super) fn arm_xosc(
+    oscctrl: &oscctrl::RegisterBlock,
+    index: usize,
+    prescaler: u8,
+    switch_back: bool,
+) {
+    assert!(prescaler <= 15, "CFD prescaler out of range");
+    oscctrl.xoscctrl[index].modify(|_, w| {
+        unsafe { w.cfdpresc().bits(prescaler) };
+        w.swben().bit(switch_back);
+        w.cfden().set_bit()
+    });
+}
+
+/// Arm the CFD of XOSC32K
+pub(super) fn arm_xosc32k(osc32kctrl: &osc32kctrl::RegisterBlock, divide_by_2: bool) {
+    osc32kctrl.cfdctrl.write(|w| {
+        w.cfdpresc().bit(divide_by_2);
+        w.cfden().set_bit()
+    });
+}
+
+/// Handle to an armed clock failure detector
+///
+/// The handle only accesses the status, interrupt and CFD bits of its own
+/// oscillator, so it can be moved into an interrupt handler.
+pub struct Cfd {
+    osc: CfdOsc,
+}
+
+impl Cfd {
+    /// Create a handle for an already-armed CFD
+    #[inline]
+    pub(super) fn new(osc: CfdOsc) -> Self {
+        Cfd { osc }
+    }
+
+    /// Arm the CFD of XOSC0 (`index` 0) or XOSC1 (`index` 1)
+    ///
+    /// The safe clock is the DFLL divided by `2^prescaler`. If `switch_back`
+    /// is set, the oscillator output returns to the crystal once it recovers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not 0 or 1, or if `prescaler` is greater than 15.
+    pub fn xosc(oscctrl: &mut OSCCTRL, index: usize, prescaler: u8, switch_back: bool) -> Self {
+        let osc = match index {
+            0 => CfdOsc::Xosc0,
+            1 => CfdOsc::Xosc1,
+            _ => panic!("invalid XOSC index {}", index),
+        };
+        arm_xosc(oscctrl, index, prescaler, switch_back);
+        Self::new(osc)
+    }
+
+    /// Arm the CFD of XOSC32K
+    ///
+    /// The safe clock is OSCULP32K, divided by two if `divide_by_2` is set.
+    pub fn xosc32k(osc32kctrl: &mut OSC32KCTRL, divide_by_2: bool) -> Self {
+        arm_xosc32k(osc32kctrl, divide_by_2);
+        Self::new(CfdOsc::Xosc32k)
+    }
+
+    #[inline]
+    fn oscctrl(&self) -> &oscctrl::RegisterBlock {
+        // SAFETY: Only the bits of this oscillator are accessed, and the
+        // interrupt registers are write-one-to-set/clear
+        unsafe { &*OSCCTRL::ptr() }
+    }
+
+    #[inline]
+    fn osc32kctrl(&self) -> &osc32kctrl::RegisterBlock {
+        // SAFETY: Only the bits of this oscillator are accessed, and the
+        // interrupt registers are write-one-to-set/clear
+        unsafe { &*OSC32KCTRL::ptr() }
+    }
+
+    /// Returns the monitored oscillator
+    #[inline]
+    pub fn oscillator(&self) -> CfdOsc {
+        self.osc
+    }
+
+    /// Returns `true` if the oscillator is currently failing
+    pub fn has_failed(&self) -> bool {
+        match self.osc {
+            CfdOsc::Xosc0 => self.oscctrl().status.read().xoscfail0().bit_is_set(),
+            CfdOsc::Xosc1 => self.oscctrl().status.read().xoscfail1().bit_is_set(),
+            CfdOsc::Xosc32k => self.osc32kctrl().status.read().xosc32kfail().bit_is_set(),
+        }
+    }
+
+    /// Returns `true` if the oscillator output is switched to the safe clock
+    pub fn is_switched(&self) -> bool {
+        match self.osc {
+            CfdOsc::Xosc0 => self.oscctrl().status.read().xosccksw0().bit_is_set(),
+            CfdOsc::Xosc1 => self.oscctrl().status.read().xosccksw1().bit_is_set(),
+            CfdOsc::Xosc32k => self.osc32kctrl().status.read().xosc32ksw().bit_is_set(),
+        }
+    }
+
+    /// Request a switch back from the safe clock to XOSC32K
+    ///
+    /// XOSC0 and XOSC1 switch back on their own if switch back was enabled
+    /// when the CFD was armed, so this does nothing for them.
+    pub fn switch_back(&self) {
+        if self.osc == CfdOsc::Xosc32k {
+            self.osc32kctrl()
+                .cfdctrl
+                .modify(|_, w| w.swback().set_bit());
+        }
+    }
+
+    /// Enable the clock failure interrupt
+    pub fn enable_interrupt(&self) {
+        match self.osc {
+            CfdOsc::Xosc0 => self.oscctrl().intenset.write(|w| w.xoscfail0().set_bit()),
+            CfdOsc::Xosc1 => self.oscctrl().intenset.write(|w| w.xoscfail1().set_bit()),
+            CfdOsc::Xosc32k => self
+                .osc32kctrl()
+                .intenset
+                .write(|w| w.xosc32kfail().set_bit()),
+        }
+    }
+
+    /// Disable the clock failure interrupt
+    pub fn disable_interrupt(&self) {
+        match self.osc {
+            CfdOsc::Xosc0 => self.oscctrl().intenclr.write(|w| w.xoscfail0().set_bit()),
+            CfdOsc::Xosc1 => self.oscctrl().intenclr.write(|w| w.xoscfail1().set_bit()),
+            CfdOsc::Xosc32k => self
+                .osc32kctrl()
+                .intenclr
+                .write(|w| w.xosc32kfail().set_bit()),
+        }
+    }
+
+    /// Returns `true` if the clock failure interrupt flag is set
+    pub fn is_interrupt_pending(&self) -> bool {
+        match self.osc {
+            CfdOsc::Xosc0 => self.oscctrl().intflag.read().xoscfail0().bit_is_set(),
+            CfdOsc::Xosc1 => self.oscctrl().intflag.read().xoscfail1().bit_is_set(),
+            CfdOsc::Xosc32k => self.osc32kctrl().intflag.read().xosc32kfail().bit_is_set(),
+        }
+    }
+
+    /// Clear the clock failure interrupt flag
+    pub fn clear_interrupt(&self) {
+        match self.osc {
+            CfdOsc::Xosc0 => self.oscctrl().intflag.write(|w| w.xoscfail0().set_bit()),
+            CfdOsc::Xosc1 => self.oscctrl().intflag.write(|w| w.xoscfail1().set_bit()),
+            CfdOsc::Xosc32k => self
+                .osc32kctrl()
+                .intflag
+                .write(|w| w.xosc32kfail().set_bit()),
+        }
+    }
+
+    /// Disarm the CFD
+    pub fn disable(self) {
+        match self.osc {
+            CfdOsc::Xosc0 => self.oscctrl().xoscctrl[0].modify(|_, w| w.cfden().clear_bit()),
+            CfdOsc::Xosc1 => self.oscctrl().xoscctrl[1].modify(|_, w| w.cfden().clear_bit()),
+            CfdOsc::Xosc32k => self
+                .osc32kctrl()
+                .cfdctrl
+                .modify(|_, w| w.cfden().clear_bit()),
+        }
+    }
+}
@@ -0,0 +1,849 @@
+//! Configurable clock tree
+//!
+//! [`GenericClockController::with_internal_32kosc`] and
+//! [`GenericClockController::with_external_32kosc`] always produce the same
+//! clock tree, with GCLK0 running at 120 MHz from DPLL0, which is in turn
+//! referenced to the DFLL. [`ClockConfig`] describes an arbitrary clock tree
+//! instead. It configures the two crystal oscillators, XOSC0 and XOSC1, both
+//! DPLLs, with any reference and any LDR/LDRFRAC ratio, and the source and
+//! divider of every GCLK.
+//!
+//! All of the builder methods are `const fn`, and so is
+//! [`ClockConfig::frequencies`], which validates the clock tree and calculates
+//! the resulting frequencies. A clock tree can therefore be checked at
+//! compile-time. [`ClockConfig::freeze`] performs the same validation at
+//! run-time before touching any registers.
+//!
+//! ```
+//! use atsamd_hal::clock::config::*;
+//! use atsamd_hal::target_device::gclk::genctrl::SRC_A::*;
+//! use atsamd_hal::target_device::gclk::pchctrl::GEN_A::*;
+//! use atsamd_hal::time::Hertz;
+//!
+//! const CLOCKS: ClockConfig = ClockConfig::new()
+//!     // 12 MHz crystal, divided to a 2 MHz reference and multiplied by 60
+//!     .xosc1(XoscConfig::crystal(Hertz(12_000_000)))
+//!     .dpll0(DpllConfig::new(DpllReference::Xosc1 { div: 2 }, 59, 0))
+//!     // 25 MHz crystal, divided to a 2.5 MHz reference and multiplied by 40
+//!     .xosc0(XoscConfig::crystal(Hertz(25_000_000)))
+//!     .dpll1(DpllConfig::new(DpllReference::Xosc0 { div: 4 }, 39, 0))
+//!     .gclk(GCLK0, GclkConfig::new(DPLL0, 1))
+//!     .gclk(GCLK2, GclkConfig::new(DPLL1, 2))
+//!     .gclk(GCLK3, GclkConfig::new(XOSC0, 1));
+//!
+//! // Reject an invalid clock tree at compile-time
+//! const FREQS: ClockFrequencies = match CLOCKS.frequencies() {
+//!     Ok(freqs) => freqs,
+//!     Err(_) => panic!("Invalid clock tree"),
+//! };
+//!
+//! let mut clocks = CLOCKS.freeze(
+//!     peripherals.GCLK,
+//!     &mut peripherals.MCLK,
+//!     &mut peripherals.OSC32KCTRL,
+//!     &mut peripherals.OSCCTRL,
+//!     &mut peripherals.NVMCTRL,
+//! )?;
+//! let gclk2 = clocks.get_gclk(GCLK2).unwrap();
+//! ```
+//!
+//! The `GCLKIN` source is not supported by [`ClockConfig`].
+
+use crate::target_device::gclk::genctrl::SRC_A::*;
+use crate::target_device::oscctrl::dpll::dpllctrlb::REFCLK_A;
+use crate::target_device::{GCLK, MCLK, NVMCTRL, OSC32KCTRL, OSCCTRL};
+use crate::time::Hertz;
+
+use super::*;
+
+/// Frequency of the DFLL in open-loop or USB recovery mode
+const DFLL_FREQ: u32 = OSC48M_FREQ.0;
+
+/// Frequency of the 32 kHz oscillators
+const OSC32K: u32 = OSC32K_FREQ.0;
+
+//==============================================================================
+// ClockError
+//==============================================================================
+
+/// Error `enum` for an invalid [`ClockConfig`]
+///
+/// Where relevant, the variant contains the number of the offending XOSC,
+/// DPLL or GCLK.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClockError {
+    /// The XOSC frequency is out of range for its [`XoscMode`]
+    InvalidXosc(u8),
+    /// The DPLL reference frequency is out of range, or its ratio or XOSC
+    /// divider doesn't fit in the corresponding register field
+    InvalidDpllReference(u8),
+    /// The DPLL output frequency is out of range
+    InvalidDpllOutput(u8),
+    /// The GCLK divider doesn't fit in the DIV field, or the GCLK uses itself
+    /// or an unsupported source
+    InvalidGclk(u8),
+    /// The GCLK output frequency is too high
+    InvalidGclkOutput(u8),
+    /// GCLK0 must always be enabled
+    Gclk0Disabled,
+    /// A DPLL or GCLK uses a source that is not enabled
+    SourceNotEnabled,
+    /// The DPLLs and GCLKs depend on each other in a loop
+    DependencyLoop,
+}
+
+//==============================================================================
+// XoscConfig
+//==============================================================================
+
+/// Operating mode of an external multipurpose oscillator (XOSC)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XoscMode {
+    /// A crystal is connected between XIN and XOUT
+    Crystal,
+    /// An external clock signal is connected to XIN
+    ExternalClock,
+}
+
+/// Configuration of XOSC0 or XOSC1
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct XoscConfig {
+    freq: Hertz,
+    mode: XoscMode,
+    startup: u8,
+    on_demand: bool,
+    run_standby: bool,
+}
+
+impl XoscConfig {
+    /// Create an [`XoscConfig`] for a crystal of the given frequency
+    ///
+    /// Crystals from 8 MHz to 48 MHz are supported.
+    #[inline]
+    pub const fn crystal(freq: Hertz) -> Self {
+        XoscConfig {
+            freq,
+            mode: XoscMode::Crystal,
+            startup: 0,
+            on_demand: false,
+            run_standby: false,
+        }
+    }
+
+    /// Create an [`XoscConfig`] for an external clock of the given frequency
+    ///
+    /// External clocks up to 48 MHz are supported.
+    #[inline]
+    pub const fn external_clock(freq: Hertz) -> Self {
+        XoscConfig {
+            freq,
+            mode: XoscMode::ExternalClock,
+            startup: 0,
+            on_demand: false,
+            run_standby: false,
+        }
+    }
+
+    /// Set the STARTUP field
+    ///
+    /// The start-up time is `2^startup` cycles of the 32 kHz oscillator.
+    /// Values greater than 15 saturate.
+    #[inline]
+    pub const fn startup(mut self, startup: u8) -> Self {
+        self.startup = if startup > 15 { 15 } else { startup };
+        self
+    }
+
+    /// Only run the oscillator when a peripheral requests it
+    #[inline]
+    pub const fn on_demand(mut self, on_demand: bool) -> Self {
+        self.on_demand = on_demand;
+        self
+    }
+
+    /// Keep running the oscillator in standby sleep mode
+    #[inline]
+    pub const fn run_standby(mut self, run_standby: bool) -> Self {
+        self.run_standby = run_standby;
+        self
+    }
+
+    /// Return the oscillator frequency
+    #[inline]
+    pub const fn freq(&self) -> Hertz {
+        self.freq
+    }
+
+    /// Return the [`XoscMode`]
+    #[inline]
+    pub const fn mode(&self) -> XoscMode {
+        self.mode
+    }
+
+    const fn is_valid(&self) -> bool {
+        match self.mode {
+            XoscMode::Crystal => self.freq.0 >= 8_000_000 && self.freq.0 <= 48_000_000,
+            XoscMode::ExternalClock => self.freq.0 > 0 && self.freq.0 <= 48_000_000,
+        }
+    }
+
+    /// Enable the oscillator
+    ///
+    /// The IMULT and IPTAT values are taken from the XOSC table in the
+    /// electrical characteristics section of the datasheet.
+    fn enable(&self, oscctrl: &mut OSCCTRL, index: usize) {
+        let crystal = self.mode == XoscMode::Crystal;
+        let (imult, iptat) = match self.freq.0 {
+            0..=8_000_000 => (3, 2),
+            8_000_001..=16_000_000 => (4, 3),
+            16_000_001..=24_000_000 => (5, 3),
+            _ => (6, 3),
+        };
+        oscctrl.xoscctrl[index].write(|w| {
+            unsafe {
+                w.imult().bits(imult);
+                w.iptat().bits(iptat);
+            }
+            w.startup().bits(self.startup);
+            w.xtalen().bit(crystal);
+            w.enalc().bit(crystal);
+            w.ondemand().bit(self.on_demand);
+            w.runstdby().bit(self.run_standby);
+            w.enable().set_bit()
+        });
+        if !self.on_demand {
+            let status = &oscctrl.status;
+            match index {
+                0 => while status.read().xoscrdy0().bit_is_clear() {},
+                _ => while status.read().xoscrdy1().bit_is_clear() {},
+            }
+        }
+    }
+}
+
+//==============================================================================
+// DpllConfig
+//==============================================================================
+
+/// Reference clock for a DPLL
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DpllReference {
+    /// Use the dedicated peripheral channel, fed by the given GCLK
+    Gclk(ClockGenId),
+    /// Use XOSC32K directly
+    Xosc32k,
+    /// Use XOSC0, divided by `2 * (div + 1)`
+    Xosc0 { div: u16 },
+    /// Use XOSC1, divided by `2 * (div + 1)`
+    Xosc1 { div: u16 },
+}
+
+/// Configuration of DPLL0 or DPLL1
+///
+/// The output frequency is `f_ref * (LDR + 1 + LDRFRAC / 32)`. The reference
+/// frequency must be between 32 kHz and 3.2 MHz, and the output frequency must
+/// be between 96 MHz and 200 MHz.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DpllConfig {
+    reference: DpllReference,
+    ldr: u16,
+    ldrfrac: u8,
+    on_demand: bool,
+    run_standby: bool,
+}
+
+impl DpllConfig {
+    /// Create a [`DpllConfig`] from the raw LDR and LDRFRAC values
+    #[inline]
+    pub const fn new(reference: DpllReference, ldr: u16, ldrfrac: u8) -> Self {
+        DpllConfig {
+            reference,
+            ldr,
+            ldrfrac,
+            on_demand: false,
+            run_standby: false,
+        }
+    }
+
+    /// Create a [`DpllConfig`] that multiplies the reference frequency as close
+    /// as possible to the requested output frequency
+    ///
+    /// `fref` is the frequency at the DPLL input, i.e. after the XOSC divider,
+    /// if any. The ratio is rounded down to the nearest 1/32.
+    #[inline]
+    pub const fn from_frequencies(reference: DpllReference, fref: Hertz, fout: Hertz) -> Self {
+        let ratio = if fref.0 == 0 {
+            0
+        } else {
+            fout.0 as u64 * 32 / fref.0 as u64
+        };
+        let ldr = if ratio >= 32 {
+            (ratio / 32 - 1) as u16
+        } else {
+            0
+        };
+        let ldrfrac = (ratio % 32) as u8;
+        Self::new(reference, ldr, ldrfrac)
+    }
+
+    /// Only run the DPLL when a peripheral requests it
+    #[inline]
+    pub const fn on_demand(mut self, on_demand: bool) -> Self {
+        self.on_demand = on_demand;
+        self
+    }
+
+    /// Keep running the DPLL in standby sleep mode
+    #[inline]
+    pub const fn run_standby(mut self, run_standby: bool) -> Self {
+        self.run_standby = run_standby;
+        self
+    }
+
+    /// Return the [`DpllReference`]
+    #[inline]
+    pub const fn reference(&self) -> DpllReference {
+        self.reference
+    }
+
+    /// Return the output frequency for a given reference frequency
+    #[inline]
+    pub const fn output(&self, fref: Hertz) -> Hertz {
+        let mult = (self.ldr as u64 + 1) * 32 + self.ldrfrac as u64;
+        Hertz((fref.0 as u64 * mult / 32) as u32)
+    }
+
+    /// Enable the DPLL
+    fn enable(&self, oscctrl: &mut OSCCTRL, gclk: &mut GCLK, index: usize) {
+        let dpll = &oscctrl.dpll[index];
+        dpll.dpllctrla.write(|w| w.enable().clear_bit());
+        while dpll.dpllsyncbusy.read().enable().bit_is_set() {}
+
+        let (refclk, div) = match self.reference {
+            DpllReference::Gclk(gen) => {
+                let channel = ClockId::FDPLL0 as usize + index;
+                gclk.pchctrl[channel].write(|w| unsafe {
+                    w.gen().bits(gen.into());
+                    w.chen().set_bit()
+                });
+                while gclk.pchctrl[channel].read().chen().bit_is_clear() {}
+                (REFCLK_A::GCLK, 0)
+            }
+            DpllReference::Xosc32k => (REFCLK_A::XOSC32, 0),
+            DpllReference::Xosc0 { div } => (REFCLK_A::XOSC0, div),
+            DpllReference::Xosc1 { div } => (REFCLK_A::XOSC1, div),
+        };
+        dpll.dpllratio.write(|w| unsafe {
+            w.ldr().bits(self.ldr);
+            w.ldrfrac().bits(self.ldrfrac)
+        });
+        while dpll.dpllsyncbusy.read().dpllratio().bit_is_set() {}
+        dpll.dpllctrlb.write(|w| unsafe {
+            w.refclk().variant(refclk);
+            w.div().bits(div)
+        });
+        dpll.dpllctrla.write(|w| {
+            w.ondemand().bit(self.on_demand);
+            w.runstdby().bit(self.run_standby);
+            w.enable().set_bit()
+        });
+        while dpll.dpllsyncbusy.read().enable().bit_is_set() {}
+        if !self.on_demand {
+            while dpll.dpllstatus.read().lock().bit_is_clear()
+                || dpll.dpllstatus.read().clkrdy().bit_is_clear()
+            {}
+        }
+    }
+}
+
+//==============================================================================
+// GclkConfig
+//==============================================================================
+
+/// Configuration of a single GCLK
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GclkConfig {
+    src: ClockSource,
+    div: u16,
+    improve_duty_cycle: bool,
+    output_enable: bool,
+    run_standby: bool,
+}
+
+impl GclkConfig {
+    /// Create a [`GclkConfig`] with the given source and linear divider
+    ///
+    /// A divider of zero is treated as one. GCLK1 accepts dividers up to
+    /// 65535, while all other GCLKs accept dividers up to 255.
+    #[inline]
+    pub const fn new(src: ClockSource, div: u16) -> Self {
+        GclkConfig {
+            src,
+            div,
+            improve_duty_cycle: false,
+            output_enable: false,
+            run_standby: false,
+        }
+    }
+
+    /// Produce a 50/50 duty cycle for odd divider values
+    #[inline]
+    pub const fn improve_duty_cycle(mut self, idc: bool) -> Self {
+        self.improve_duty_cycle = idc;
+        self
+    }
+
+    /// Drive the generator on its GCLK_IO pin, if it is configured
+    #[inline]
+    pub const fn output_enable(mut self, oe: bool) -> Self {
+        self.output_enable = oe;
+        self
+    }
+
+    /// Keep running the generator in standby sleep mode
+    #[inline]
+    pub const fn run_standby(mut self, run_standby: bool) -> Self {
+        self.run_standby = run_standby;
+        self
+    }
+
+    /// Return the [`ClockSource`]
+    #[inline]
+    pub const fn source(&self) -> ClockSource {
+        self.src
+    }
+
+    /// Return the effective divider
+    #[inline]
+    pub const fn divider(&self) -> u16 {
+        if self.div == 0 {
+            1
+        } else {
+            self.div
+        }
+    }
+
+    /// Write the GENCTRL register for the given generator
+    fn apply(&self, gclk: &mut GCLK, index: usize) {
+        gclk.genctrl[index].write(|w| unsafe {
+            w.src().variant(self.src);
+            w.div().bits(self.divider());
+            // divide directly by divider, rather than 2^(n+1)
+            w.divsel().clear_bit();
+            w.idc().bit(self.improve_duty_cycle);
+            w.oe().bit(self.output_enable);
+            w.runstdby().bit(self.run_standby);
+            w.genen().set_bit()
+        });
+        while gclk.syncbusy.read().bits() != 0 {}
+    }
+}
+
+//==============================================================================
+// ClockFrequencies
+//==============================================================================
+
+/// Frequencies produced by a valid [`ClockConfig`]
+///
+/// A frequency of zero indicates that the corresponding clock is disabled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClockFrequencies {
+    /// Frequencies of XOSC0 and XOSC1
+    pub xosc: [Hertz; 2],
+    /// Frequencies of DPLL0 and DPLL1
+    pub dpll: [Hertz; 2],
+    /// Frequencies of GCLK0 to GCLK11
+    pub gclk: [Hertz; NUM_GCLKS],
+}
+
+//==============================================================================
+// ClockConfig
+//==============================================================================
+
+/// Number of GCLK generators
+pub const NUM_GCLKS: usize = 12;
+
+/// Maximum frequency of GCLK0, which drives the CPU
+pub const MAX_GCLK0_FREQ: Hertz = OSC120M_FREQ;
+
+/// Maximum frequency of all other GCLKs
+pub const MAX_GCLK_FREQ: Hertz = Hertz(200_000_000);
+
+/// Description of a complete clock tree
+///
+/// See the [module-level](self) documentation for more details.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClockConfig {
+    external_32kosc: bool,
+    xosc: [Option<XoscConfig>; 2],
+    dpll: [Option<DpllConfig>; 2],
+    gclk: [Option<GclkConfig>; NUM_GCLKS],
+}
+
+impl ClockConfig {
+    /// Create a new [`ClockConfig`] matching the default clock tree
+    ///
+    /// This is the same clock tree produced by
+    /// [`GenericClockController::with_internal_32kosc`]. GCLK0 runs at
+    /// 120 MHz from DPLL0, which is referenced to GCLK5 at 2 MHz, which is in
+    /// turn fed by the DFLL. GCLK1 runs from the ultra low-power 32 kHz
+    /// oscillator.
+    pub const fn new() -> Self {
+        let mut gclk = [None; NUM_GCLKS];
+        gclk[0] = Some(GclkConfig::new(DPLL0, 1).output_enable(true));
+        gclk[1] = Some(GclkConfig::new(OSCULP32K, 1).output_enable(true));
+        gclk[5] = Some(GclkConfig::new(DFLL, 24));
+        ClockConfig {
+            external_32kosc: false,
+            xosc: [None; 2],
+            dpll: [
+                Some(DpllConfig::new(
+                    DpllReference::Gclk(ClockGenId::GCLK5),
+                    59,
+                    0,
+                )),
+                None,
+            ],
+            gclk,
+        }
+    }
+
+    /// Use the external 32 kHz crystal oscillator, XOSC32K
+    ///
+    /// When enabled, GCLK1 is fed by XOSC32K rather than OSCULP32K, as in
+    /// [`GenericClockController::with_external_32kosc`].
+    pub const fn external_32kosc(mut self, enable: bool) -> Self {
+        self.external_32kosc = enable;
+        let src = if enable { XOSC32K } else { OSCULP32K };
+        self.gclk[1] = Some(GclkConfig::new(src, 1).output_enable(true));
+        self
+    }
+
+    /// Enable XOSC0
+    #[inline]
+    pub const fn xosc0(mut self, config: XoscConfig) -> Self {
+        self.xosc[0] = Some(config);
+        self
+    }
+
+    /// Enable XOSC1
+    #[inline]
+    pub const fn xosc1(mut self, config: XoscConfig) -> Self {
+        self.xosc[1] = Some(config);
+        self
+    }
+
+    /// Enable DPLL0
+    #[inline]
+    pub const fn dpll0(mut self, config: DpllConfig) -> Self {
+        self.dpll[0] = Some(config);
+        self
+    }
+
+    /// Enable DPLL1
+    #[inline]
+    pub const fn dpll1(mut self, config: DpllConfig) -> Self {
+        self.dpll[1] = Some(config);
+        self
+    }
+
+    /// Disable DPLL0
+    ///
+    /// DPLL0 is enabled in the default clock tree.
+    #[inline]
+    pub const fn disable_dpll0(mut self) -> Self {
+        self.dpll[0] = None;
+        self
+    }
+
+    /// Configure a GCLK
+    #[inline]
+    pub const fn gclk(mut self, gen: ClockGenId, config: GclkConfig) -> Self {
+        self.gclk[gen as usize] = Some(config);
+        self
+    }
+
+    /// Disable a GCLK
+    ///
+    /// GCLK0 can't be disabled.
+    #[inline]
+    pub const fn disable_gclk(mut self, gen: ClockGenId) -> Self {
+        self.gclk[gen as usize] = None;
+        self
+    }
+
+    /// Validate the clock tree and calculate its frequencies
+    ///
+    /// This function can be evaluated at compile-time. See the
+    /// [module-level](self) documentation for an example.
+    pub const fn frequencies(&self) -> Result<ClockFrequencies, ClockError> {
+        let mut freqs = ClockFrequencies {
+            xosc: [Hertz(0); 2],
+            dpll: [Hertz(0); 2],
+            gclk: [Hertz(0); NUM_GCLKS],
+        };
+        if self.gclk[0].is_none() {
+            return Err(ClockError::Gclk0Disabled);
+        }
+
+        let mut i = 0;
+        while i < 2 {
+            if let Some(xosc) = self.xosc[i] {
+                if !xosc.is_valid() {
+                    return Err(ClockError::InvalidXosc(i as u8));
+                }
+                freqs.xosc[i] = xosc.freq;
+            }
+            i += 1;
+        }
+
+        // DPLLs and GCLKs can depend on each other, so resolve them
+        // iteratively. Each pass must resolve at least one clock.
+        let mut dpll_done = [false; 2];
+        let mut gclk_done = [false; NUM_GCLKS];
+        loop {
+            let mut progress = false;
+            let mut pending = false;
+
+            let mut i = 0;
+            while i < 2 {
+                if let (Some(dpll), false) = (self.dpll[i], dpll_done[i]) {
+                    let fref = match dpll.reference {
+                        DpllReference::Gclk(gen) => {
+                            let gen = gen as usize;
+                            if self.gclk[gen].is_none() {
+                                return Err(ClockError::SourceNotEnabled);
+                            }
+                            if gclk_done[gen] {
+                                Some(freqs.gclk[gen].0)
+                            } else {
+                                None
+                            }
+                        }
+                        DpllReference::Xosc32k => {
+                            if !self.external_32kosc {
+                                return Err(ClockError::SourceNotEnabled);
+                            }
+                            Some(OSC32K)
+                        }
+                        DpllReference::Xosc0 { div } | DpllReference::Xosc1 { div } => {
+                            let x = match dpll.reference {
+                                DpllReference::Xosc0 { .. } => 0,
+                                _ => 1,
+                            };
+                            if self.xosc[x].is_none() {
+                                return Err(ClockError::SourceNotEnabled);
+                            }
+                            if div > 0x7FF {
+                                return Err(ClockError::InvalidDpllReference(i as u8));
+                            }
+                            Some(freqs.xosc[x].0 / (2 * (div as u32 + 1)))
+                        }
+                    };
+                    if let Some(fref) = fref {
+                        if fref < 32_000
+                            || fref > 3_200_000
+                            || dpll.ldr > 0x1FFF
+                            || dpll.ldrfrac > 31
+                        {
+                            return Err(ClockError::InvalidDpllReference(i as u8));
+                        }
+                        let fout = dpll.output(Hertz(fref)).0;
+                        if fout < 96_000_000 || fout > 200_000_000 {
+                            return Err(ClockError::InvalidDpllOutput(i as u8));
+                        }
+                        freqs.dpll[i] = Hertz(fout);
+                        dpll_done[i] = true;
+                        progress = true;
+                    } else {
+                        pending = true;
+                    }
+                }
+                i += 1;
+            }
+
+            let mut i = 0;
+            while i < NUM_GCLKS {
+                if let (Some(gen), false) = (self.gclk[i], gclk_done[i]) {
+                    let fsrc = match gen.src {
+                        XOSC0 | XOSC1 => {
+                            let x = if matches!(gen.src, XOSC0) { 0 } else { 1 };
+                            if self.xosc[x].is_none() {
+                                return Err(ClockError::SourceNotEnabled);
+                            }
+                            Some(freqs.xosc[x].0)
+                        }
+                        GCLKIN => return Err(ClockError::InvalidGclk(i as u8)),
+                        GCLKGEN1 => {
+                            if i == 1 {
+                                return Err(ClockError::InvalidGclk(i as u8));
+                            }
+                            if self.gclk[1].is_none() {
+                                return Err(ClockError::SourceNotEnabled);
+                            }
+                            if gclk_done[1] {
+                                Some(freqs.gclk[1].0)
+                            } else {
+                                None
+                            }
+                        }
+                        OSCULP32K => Some(OSC32K),
+                        XOSC32K => {
+                            if !self.external_32kosc {
+                                return Err(ClockError::SourceNotEnabled);
+                            }
+                            Some(OSC32K)
+                        }
+                        DFLL => Some(DFLL_FREQ),
+                        DPLL0 | DPLL1 => {
+                            let d = if matches!(gen.src, DPLL0) { 0 } else { 1 };
+                            if self.dpll[d].is_none() {
+                                return Err(ClockError::SourceNotEnabled);
+                            }
+                            if dpll_done[d] {
+                                Some(freqs.dpll[d].0)
+                            } else {
+                                None
+                            }
+                        }
+                    };
+                    if let Some(fsrc) = fsrc {
+                        let max_div = if i == 1 { u16::MAX } else { u8::MAX as u16 };
+                        if gen.divider() > max_div {
+                            return Err(ClockError::InvalidGclk(i as u8));
+                        }
+                        let fout = fsrc / gen.divider() as u32;
+                        let max = if i == 0 {
+                            MAX_GCLK0_FREQ.0
+                        } else {
+                            MAX_GCLK_FREQ.0
+                        };
+                        if fout > max {
+                            return Err(ClockError::InvalidGclkOutput(i as u8));
+                        }
+                        freqs.gclk[i] = Hertz(fout);
+                        gclk_done[i] = true;
+                        progress = true;
+                    } else {
+                        pending = true;
+                    }
+                }
+                i += 1;
+            }
+
+            if !pending {
+                return Ok(freqs);
+            }
+            if !progress {
+                return Err(ClockError::DependencyLoop);
+            }
+        }
+    }
+
+    /// Validate the clock tree and apply it to the hardware
+    ///
+    /// Like [`GenericClockController::with_internal_32kosc`], this resets the
+    /// GCLK peripheral and takes ownership of it. The DPLLs and GCLKs are
+    /// enabled in dependency order, so each one is only enabled once its
+    /// source is running. For example, DPLL1 can use a GCLK fed by DPLL0 as
+    /// its reference.
+    ///
+    /// Returns an error, without touching any registers, if the clock tree is
+    /// invalid.
+    pub fn freeze(
+        self,
+        gclk: GCLK,
+        mclk: &mut MCLK,
+        osc32kctrl: &mut OSC32KCTRL,
+        oscctrl: &mut OSCCTRL,
+        nvmctrl: &mut NVMCTRL,
+    ) -> Result<GenericClockController, ClockError> {
+        let freqs = self.frequencies()?;
+        let mut state = State { gclk };
+
+        set_flash_to_half_auto_wait_state(nvmctrl);
+        enable_gclk_apb(mclk);
+
+        if self.external_32kosc {
+            enable_external_32kosc(osc32kctrl);
+        } else {
+            enable_internal_32kosc(osc32kctrl);
+        }
+        // After reset, GCLK0 runs from the DFLL
+        state.reset_gclk();
+
+        #[cfg(feature = "usb")]
+        configure_usb_correction(oscctrl);
+
+        for (i, xosc) in self.xosc.iter().enumerate() {
+            if let Some(xosc) = xosc {
+                xosc.enable(oscctrl, i);
+            }
+        }
+
+        // DPLLs and GCLKs can depend on each other, so enable each one once
+        // its source is running. `frequencies` has already rejected loops, so
+        // every pass enables at least one clock.
+        let mut dpll_done = [false; 2];
+        let mut gclk_done = [false; NUM_GCLKS];
+        let mut used_clocks = 0;
+        loop {
+            let mut pending = false;
+            for (i, dpll) in self.dpll.iter().enumerate() {
+                if let (Some(dpll), false) = (dpll, dpll_done[i]) {
+                    let ready = match dpll.reference {
+                        DpllReference::Gclk(gen) => gclk_done[gen as usize],
+                        _ => true,
+                    };
+                    if !ready {
+                        pending = true;
+                        continue;
+                    }
+                    dpll.enable(oscctrl, &mut state.gclk, i);
+                    if let DpllReference::Gclk(_) = dpll.reference {
+                        used_clocks |= 1u64 << (ClockId::FDPLL0 as u64 + i as u64);
+                    }
+                    dpll_done[i] = true;
+                }
+            }
+            for (i, gen) in self.gclk.iter().enumerate() {
+                if let (Some(gen), false) = (gen, gclk_done[i]) {
+                    let ready = match gen.src {
+                        DPLL0 => dpll_done[0],
+                        DPLL1 => dpll_done[1],
+                        GCLKGEN1 => gclk_done[1],
+                        _ => true,
+                    };
+                    if !ready {
+                        pending = true;
+                        continue;
+                    }
+                    gen.apply(&mut state.gclk, i);
+                    gclk_done[i] = true;
+                }
+            }
+            if !pending {
+                break;
+            }
+        }
+
+        mclk.cpudiv.write(|w| w.div().div1());
+
+        Ok(GenericClockController {
+            state,
+            gclks: freqs.gclk,
+            xoscs: freqs.xosc,
+            dplls: freqs.dpll,
+            gclkins: [Hertz(0); 8],
+            cpu_div: 1,
+            used_clocks,
+        })
+    }
+}
+
+impl Default for ClockConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
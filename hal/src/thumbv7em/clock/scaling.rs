@@ -0,0 +1,210 @@
+//! # Dynamic frequency scaling
+//!
+//! The CPU frequency can be changed at run-time, e.g. to drop from 120 MHz to
+//! 12 MHz while idle. There are two ways to do so:
+//!
+//! - [`GenericClockController::set_cpu_divider`] sets the MCLK CPUDIV
+//!   prescaler. Only the CPU and the AHB/APB buses are affected; peripherals
+//!   clocked by GCLK0 keep running at the same frequency.
+//! - [`GenericClockController::reconfigure_gclk0`] changes the divider and
+//!   source of GCLK0. The CPU and every peripheral clocked by GCLK0 are
+//!   affected.
+//!
+//! Both functions adjust the flash wait states to suit the new CPU frequency.
+//! Wait states are added before the CPU speeds up and removed after it slows
+//! down, so the flash is never accessed too quickly.
+//!
+//! Drivers that cache a clock frequency when they are constructed, like
+//! [`Delay`](crate::delay::Delay), the v1 UARTs and
+//! [`TimerCounter`](crate::timer::TimerCounter), implement [`Retime`]. Pass
+//! them to the frequency change functions, and the ones affected by the
+//! change are told about the new frequency. Peripheral drivers are only
+//! notified if their peripheral channel is currently fed by GCLK0.
+//!
+//! ```no_run
+//! # use atsamd_hal::clock::{scaling::Retime, GenericClockController};
+//! # use atsamd_hal::target_device::gclk::genctrl::SRC_A;
+//! # use atsamd_hal::delay::Delay;
+//! # fn f(
+//! #     clocks: &mut GenericClockController,
+//! #     nvmctrl: &mut atsamd_hal::target_device::NVMCTRL,
+//! #     delay: &mut Delay,
+//! # ) {
+//! // GCLK0 runs from DPLL0 at 120 MHz. Slow down to 12 MHz while idle.
+//! clocks.reconfigure_gclk0(10, SRC_A::DPLL0, nvmctrl, &mut [delay]);
+//! // ... and speed back up when there is work to do
+//! clocks.reconfigure_gclk0(1, SRC_A::DPLL0, nvmctrl, &mut [delay]);
+//! # }
+//! ```
+
+use crate::target_device::gclk::pchctrl::GEN_A::GCLK0;
+use crate::target_device::{self, MCLK, NVMCTRL};
+use crate::time::Hertz;
+
+use super::{ClockId, ClockSource, GenericClockController};
+
+/// CPU clock divider, applied by MCLK to GCLK0
+pub type CpuDivider = target_device::mclk::cpudiv::DIV_A;
+
+/// Maximum CPU frequency
+pub const MAX_CPU_FREQ: Hertz = Hertz(120_000_000);
+
+/// Maximum CPU frequency for each number of flash wait states
+///
+/// Taken from the NVM characteristics in the electrical characteristics
+/// section of the datasheet.
+const WAIT_STATE_LIMITS: [u32; 6] = [
+    24_000_000,
+    51_000_000,
+    77_000_000,
+    101_000_000,
+    119_000_000,
+    120_000_000,
+];
+
+/// Return the number of flash wait states required at the given CPU frequency
+fn wait_states(freq: Hertz) -> u8 {
+    WAIT_STATE_LIMITS
+        .iter()
+        .position(|&limit| freq.0 <= limit)
+        .unwrap_or(WAIT_STATE_LIMITS.len() - 1) as u8
+}
+
+//==============================================================================
+// Retime
+//==============================================================================
+
+/// The clock a [`Retime`] driver depends on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClockDomain {
+    /// The CPU clock, i.e. GCLK0 divided by the MCLK CPUDIV prescaler
+    Cpu,
+    /// The clock of a peripheral channel
+    Peripheral(ClockId),
+}
+
+/// Drivers that need to know when their clock frequency changes
+///
+/// Implementors cache a clock frequency, or a value derived from it, when they
+/// are constructed.
+pub trait Retime {
+    /// The clock the driver depends on
+    fn clock_domain(&self) -> ClockDomain;
+
+    /// Update the driver after its clock changed from `old` to `new`
+    fn retime(&mut self, old: Hertz, new: Hertz);
+}
+
+//==============================================================================
+// Frequency changes
+//==============================================================================
+
+impl GenericClockController {
+    /// Returns the frequency of the CPU clock
+    #[inline]
+    pub fn cpu_freq(&self) -> Hertz {
+        Hertz(self.gclks[0].0 / self.cpu_div as u32)
+    }
+
+    /// Set the MCLK CPUDIV prescaler
+    ///
+    /// The flash wait states are adjusted and the [`ClockDomain::Cpu`]
+    /// drivers in `drivers` are notified. Returns the new CPU frequency.
+    pub fn set_cpu_divider(
+        &mut self,
+        div: CpuDivider,
+        mclk: &mut MCLK,
+        nvmctrl: &mut NVMCTRL,
+        drivers: &mut [&mut dyn Retime],
+    ) -> Hertz {
+        let old_cpu = self.cpu_freq();
+        let old_div = self.cpu_div;
+        self.cpu_div = div.into();
+        let new_cpu = self.cpu_freq();
+
+        self.before_change(old_cpu, new_cpu, nvmctrl);
+        mclk.cpudiv.write(|w| w.div().variant(div));
+        self.after_change(old_cpu, new_cpu, nvmctrl);
+
+        if old_div != self.cpu_div {
+            for driver in drivers.iter_mut() {
+                if driver.clock_domain() == ClockDomain::Cpu {
+                    driver.retime(old_cpu, new_cpu);
+                }
+            }
+        }
+        new_cpu
+    }
+
+    /// Change the divider and source of GCLK0
+    ///
+    /// The flash wait states are adjusted and the affected drivers in
+    /// `drivers` are notified. Odd dividers use a 50/50 duty cycle. Returns
+    /// the new GCLK0 frequency, or `None` if the source is not running.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the divider is zero or if the resulting CPU frequency would
+    /// exceed [`MAX_CPU_FREQ`].
+    pub fn reconfigure_gclk0(
+        &mut self,
+        divider: u8,
+        src: ClockSource,
+        nvmctrl: &mut NVMCTRL,
+        drivers: &mut [&mut dyn Retime],
+    ) -> Option<Hertz> {
+        assert!(divider != 0, "GCLK0 divider must be non-zero");
+        let src_freq = self.source_freq(GCLK0, src);
+        if src_freq.0 == 0 {
+            return None;
+        }
+        let old_gclk0 = self.gclks[0];
+        let new_gclk0 = Hertz(src_freq.0 / divider as u32);
+        let old_cpu = self.cpu_freq();
+        let new_cpu = Hertz(new_gclk0.0 / self.cpu_div as u32);
+        assert!(new_cpu.0 <= MAX_CPU_FREQ.0, "CPU frequency out of range");
+
+        self.before_change(old_cpu, new_cpu, nvmctrl);
+        self.state
+            .set_gclk_divider_and_source(GCLK0, divider as u16, src, divider & 1 == 1);
+        self.gclks[0] = new_gclk0;
+        self.after_change(old_cpu, new_cpu, nvmctrl);
+
+        if old_gclk0 != new_gclk0 {
+            for driver in drivers.iter_mut() {
+                match driver.clock_domain() {
+                    ClockDomain::Cpu => driver.retime(old_cpu, new_cpu),
+                    ClockDomain::Peripheral(id) if self.fed_by_gclk0(id) => {
+                        driver.retime(old_gclk0, new_gclk0)
+                    }
+                    ClockDomain::Peripheral(_) => (),
+                }
+            }
+        }
+        Some(new_gclk0)
+    }
+
+    /// Add flash wait states before the CPU speeds up
+    fn before_change(&mut self, old: Hertz, new: Hertz, nvmctrl: &mut NVMCTRL) {
+        if new.0 > old.0 {
+            set_wait_states(nvmctrl, wait_states(new));
+        }
+    }
+
+    /// Remove flash wait states after the CPU slowed down
+    fn after_change(&mut self, old: Hertz, new: Hertz, nvmctrl: &mut NVMCTRL) {
+        if new.0 < old.0 {
+            set_wait_states(nvmctrl, wait_states(new));
+        }
+    }
+
+    /// Whether the peripheral channel is enabled and fed by GCLK0
+    fn fed_by_gclk0(&self, id: ClockId) -> bool {
+        let pchctrl = self.state.gclk.pchctrl[u8::from(id) as usize].read();
+        pchctrl.chen().bit_is_set() && pchctrl.gen().bits() == u8::from(GCLK0)
+    }
+}
+
+fn set_wait_states(nvmctrl: &mut NVMCTRL, rws: u8) {
+    nvmctrl.ctrla.modify(|_, w| unsafe { w.rws().bits(rws) });
+}
@@ -0,0 +1,529 @@
+//! # Clock tree introspection
+//!
+//! [`ClockTree::read`] reads back the live state of the oscillators, the GCLK
+//! generators and the peripheral channels, and works out the frequency of each
+//! clock from its source. Frequencies of external clocks can't be read from
+//! the hardware, so they must be supplied in an [`ExternalClocks`]. The
+//! [`GenericClockController::clock_tree`] function does so with the
+//! frequencies it already knows about.
+//!
+//! A [`ClockTree`] implements [`Display`](core::fmt::Display), which lists
+//! every running clock:
+//!
+//! ```text
+//! CPU: 120000000 Hz
+//! XOSC32K: enabled, ready, 32768 Hz
+//! DFLL: enabled, open loop, 48000000 Hz
+//! DPLL0: enabled, locked, reference GCLK5, 120000000 Hz
+//! GCLK0: DPLL0 / 1, 120000000 Hz
+//! GCLK1: XOSC32K / 1, 32768 Hz
+//! GCLK5: DFLL / 24, 2000000 Hz
+//! FDPLL0: GCLK5, 2000000 Hz
+//! SERCOM0_CORE: GCLK0, 120000000 Hz
+//! ```
+
+use core::fmt;
+
+use crate::target_device::gclk::genctrl::DIVSEL_A;
+use crate::target_device::gclk::genctrl::SRC_A;
+use crate::target_device::generic::Variant;
+use crate::target_device::oscctrl::dpll::dpllctrlb::REFCLK_A;
+use crate::target_device::{oscctrl, GCLK, MCLK, OSC32KCTRL, OSCCTRL};
+use crate::time::Hertz;
+
+use super::{ClockGenId, ClockId, ClockSource, GenericClockController, OSC32K_FREQ};
+
+/// Number of GCLK generators
+const NUM_GCLKS: usize = 12;
+
+/// Number of peripheral channels
+const NUM_CHANNELS: usize = 48;
+
+/// Peripheral channels, indexed by their PCHCTRL number
+const CHANNELS: [ClockId; NUM_CHANNELS] = [
+    ClockId::DFLL48,
+    ClockId::FDPLL0,
+    ClockId::FDPLL1,
+    ClockId::SLOW_32K,
+    ClockId::EIC,
+    ClockId::FREQM_MSR,
+    ClockId::FREQM_REF,
+    ClockId::SERCOM0_CORE,
+    ClockId::SERCOM1_CORE,
+    ClockId::TC0_TC1,
+    ClockId::USB,
+    ClockId::EVSYS0,
+    ClockId::EVSYS1,
+    ClockId::EVSYS2,
+    ClockId::EVSYS3,
+    ClockId::EVSYS4,
+    ClockId::EVSYS5,
+    ClockId::EVSYS6,
+    ClockId::EVSYS7,
+    ClockId::EVSYS8,
+    ClockId::EVSYS9,
+    ClockId::EVSYS10,
+    ClockId::EVSYS11,
+    ClockId::SERCOM2_CORE,
+    ClockId::SERCOM3_CORE,
+    ClockId::TCC0_TCC1,
+    ClockId::TC2_TC3,
+    ClockId::CAN0,
+    ClockId::CAN1,
+    ClockId::TCC2_TCC3,
+    ClockId::TC4_TC5,
+    ClockId::PDEC,
+    ClockId::AC,
+    ClockId::CCL,
+    ClockId::SERCOM4_CORE,
+    ClockId::SERCOM5_CORE,
+    ClockId::SERCOM6_CORE,
+    ClockId::SERCOM7_CORE,
+    ClockId::TCC4,
+    ClockId::TC6_TC7,
+    ClockId::ADC0,
+    ClockId::ADC1,
+    ClockId::DAC,
+    ClockId::I2S0,
+    ClockId::I2S1,
+    ClockId::SDHC0,
+    ClockId::SDHC1,
+    ClockId::CM4_TRACE,
+];
+
+/// Nominal frequency of the DFLL in open-loop mode
+const DFLL_OPEN_LOOP_FREQ: Hertz = Hertz(48_000_000);
+
+//==============================================================================
+// Descriptions
+//==============================================================================
+
+/// Frequencies of clocks outside the chip
+///
+/// A zero frequency means the frequency is unknown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExternalClocks {
+    /// Frequencies of the XOSC0 and XOSC1 crystals or clocks
+    pub xosc: [Hertz; 2],
+    /// Frequencies of the clocks on the GCLK_IO pins, per generator
+    pub gclkin: [Hertz; 8],
+}
+
+impl Default for ExternalClocks {
+    #[inline]
+    fn default() -> Self {
+        ExternalClocks {
+            xosc: [Hertz(0); 2],
+            gclkin: [Hertz(0); 8],
+        }
+    }
+}
+
+/// State of an oscillator
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OscState {
+    /// The oscillator is enabled
+    pub enabled: bool,
+    /// The oscillator is running and stable
+    pub ready: bool,
+    /// Frequency of the oscillator, if known
+    pub freq: Option<Hertz>,
+}
+
+/// State of the DFLL
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DfllState {
+    /// The DFLL is enabled
+    pub enabled: bool,
+    /// The DFLL is in closed-loop mode, locked to its peripheral channel
+    pub closed_loop: bool,
+    /// Frequency of the DFLL, if known
+    pub freq: Option<Hertz>,
+}
+
+/// Reference clock of a DPLL
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DpllReference {
+    /// The DPLL peripheral channel
+    Gclk,
+    Xosc32k,
+    Xosc0,
+    Xosc1,
+}
+
+/// State of a DPLL
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DpllState {
+    /// The DPLL is enabled
+    pub enabled: bool,
+    /// The DPLL is locked
+    pub locked: bool,
+    /// Reference clock of the DPLL, if valid
+    pub reference: Option<DpllReference>,
+    /// Frequency of the DPLL output, if known
+    pub freq: Option<Hertz>,
+}
+
+/// State of a GCLK generator
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GclkState {
+    /// Source of the generator, if valid
+    pub source: Option<ClockSource>,
+    /// Division factor applied to the source
+    pub divider: u32,
+    /// The generator drives its GCLK_IO pin
+    pub output_enable: bool,
+    /// The generator keeps running in standby sleep mode
+    pub run_standby: bool,
+    /// Frequency of the generator, if known
+    pub freq: Option<Hertz>,
+}
+
+/// State of an enabled peripheral channel
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChannelState {
+    /// The peripheral channel
+    pub id: ClockId,
+    /// Generator feeding the channel, if valid
+    pub generator: Option<ClockGenId>,
+    /// Frequency of the channel, if known
+    pub freq: Option<Hertz>,
+}
+
+/// A snapshot of the whole clock tree
+#[derive(Clone, Debug)]
+pub struct ClockTree {
+    /// CPU frequency, i.e. GCLK0 divided by the MCLK CPUDIV prescaler
+    pub cpu: Option<Hertz>,
+    /// XOSC0 and XOSC1
+    pub xosc: [OscState; 2],
+    pub xosc32k: OscState,
+    pub dfll: DfllState,
+    /// DPLL0 and DPLL1
+    pub dpll: [DpllState; 2],
+    /// GCLK0 to GCLK11, or `None` if a generator is disabled
+    pub gclk: [Option<GclkState>; NUM_GCLKS],
+    /// Peripheral channels, or `None` if a channel is disabled
+    pub channels: [Option<ChannelState>; NUM_CHANNELS],
+}
+
+//==============================================================================
+// Reading
+//==============================================================================
+
+impl ClockTree {
+    /// Read the live clock configuration
+    ///
+    /// This only reads registers, so it can be called at any time.
+    pub fn read(external: &ExternalClocks) -> Self {
+        // SAFETY: Registers are only read, and none of the reads has side
+        // effects
+        let (gclk, oscctrl, osc32kctrl, mclk) = unsafe {
+            (
+                &*GCLK::ptr(),
+                &*OSCCTRL::ptr(),
+                &*OSC32KCTRL::ptr(),
+                &*MCLK::ptr(),
+            )
+        };
+        let known = |freq: Hertz| Some(freq).filter(|f| f.0 != 0);
+
+        let status = oscctrl.status.read();
+        let ready = [
+            status.xoscrdy0().bit_is_set(),
+            status.xoscrdy1().bit_is_set(),
+        ];
+        let mut xosc = [OscState {
+            enabled: false,
+            ready: false,
+            freq: None,
+        }; 2];
+        for (i, osc) in xosc.iter_mut().enumerate() {
+            osc.enabled = oscctrl.xoscctrl[i].read().enable().bit_is_set();
+            osc.ready = ready[i];
+            osc.freq = known(external.xosc[i]).filter(|_| osc.enabled);
+        }
+
+        let xosc32k_enabled = osc32kctrl.xosc32k.read().enable().bit_is_set();
+        let xosc32k = OscState {
+            enabled: xosc32k_enabled,
+            ready: osc32kctrl.status.read().xosc32krdy().bit_is_set(),
+            freq: Some(OSC32K_FREQ).filter(|_| xosc32k_enabled),
+        };
+
+        let dfll_enabled = oscctrl.dfllctrla.read().enable().bit_is_set();
+        let dfllctrlb = oscctrl.dfllctrlb.read();
+        let dfll = DfllState {
+            enabled: dfll_enabled,
+            closed_loop: dfllctrlb.mode().bit_is_set() && dfllctrlb.usbcrm().bit_is_clear(),
+            freq: None,
+        };
+
+        let mut dpll = [DpllState {
+            enabled: false,
+            locked: false,
+            reference: None,
+            freq: None,
+        }; 2];
+        for (i, d) in dpll.iter_mut().enumerate() {
+            d.enabled = oscctrl.dpll[i].dpllctrla.read().enable().bit_is_set();
+            d.locked = oscctrl.dpll[i].dpllstatus.read().lock().bit_is_set();
+            d.reference = match oscctrl.dpll[i].dpllctrlb.read().refclk().variant() {
+                Variant::Val(REFCLK_A::GCLK) => Some(DpllReference::Gclk),
+                Variant::Val(REFCLK_A::XOSC32) => Some(DpllReference::Xosc32k),
+                Variant::Val(REFCLK_A::XOSC0) => Some(DpllReference::Xosc0),
+                Variant::Val(REFCLK_A::XOSC1) => Some(DpllReference::Xosc1),
+                _ => None,
+            };
+        }
+
+        let mut gclks = [None; NUM_GCLKS];
+        for (i, g) in gclks.iter_mut().enumerate() {
+            let genctrl = gclk.genctrl[i].read();
+            if genctrl.genen().bit_is_clear() {
+                continue;
+            }
+            let div = genctrl.div().bits() as u32;
+            let divider = match genctrl.divsel().variant() {
+                DIVSEL_A::DIV1 => div.max(1),
+                DIVSEL_A::DIV2 => 1 << (div.min(31) + 1),
+            };
+            *g = Some(GclkState {
+                source: match genctrl.src().variant() {
+                    Variant::Val(src) => Some(src),
+                    Variant::Res(_) => None,
+                },
+                divider,
+                output_enable: genctrl.oe().bit_is_set(),
+                run_standby: genctrl.runstdby().bit_is_set(),
+                freq: None,
+            });
+        }
+
+        let mut channels = [None; NUM_CHANNELS];
+        for (i, c) in channels.iter_mut().enumerate() {
+            let pchctrl = gclk.pchctrl[i].read();
+            if pchctrl.chen().bit_is_clear() {
+                continue;
+            }
+            *c = Some(ChannelState {
+                id: CHANNELS[i],
+                generator: match pchctrl.gen().variant() {
+                    Variant::Val(gen) => Some(gen),
+                    Variant::Res(_) => None,
+                },
+                freq: None,
+            });
+        }
+
+        // Sources and generators can feed each other in a chain, so resolve
+        // frequencies until nothing changes. Each pass resolves at least one
+        // more link, and the longest possible chain is bounded by the number
+        // of clocks.
+        let mut tree = ClockTree {
+            cpu: None,
+            xosc,
+            xosc32k,
+            dfll,
+            dpll,
+            gclk: gclks,
+            channels,
+        };
+        for _ in 0..NUM_GCLKS + 3 {
+            let before = (tree.dfll.freq, tree.dpll, tree.gclk);
+
+            let dfll_ref = tree.channel_freq(ClockId::DFLL48);
+            tree.dfll.freq = match (tree.dfll.enabled, tree.dfll.closed_loop) {
+                (false, _) => None,
+                (true, false) => Some(DFLL_OPEN_LOOP_FREQ),
+                (true, true) => {
+                    let mul = oscctrl.dfllmul.read().mul().bits() as u32;
+                    dfll_ref.map(|f| Hertz(f.0 * mul))
+                }
+            };
+
+            for i in 0..2 {
+                tree.dpll[i].freq = tree.dpll_freq(i, oscctrl);
+            }
+
+            for i in 0..NUM_GCLKS {
+                if let Some(mut g) = tree.gclk[i] {
+                    g.freq = g
+                        .source
+                        .and_then(|src| tree.source_freq(i, src, external))
+                        .map(|f| Hertz(f.0 / g.divider));
+                    tree.gclk[i] = Some(g);
+                }
+            }
+
+            if before == (tree.dfll.freq, tree.dpll, tree.gclk) {
+                break;
+            }
+        }
+
+        let gclks = tree.gclk;
+        for c in tree.channels.iter_mut().flatten() {
+            c.freq = c
+                .generator
+                .and_then(|gen| gclks[u8::from(gen) as usize])
+                .and_then(|g| g.freq);
+        }
+
+        let cpudiv = mclk.cpudiv.read().bits() as u32;
+        tree.cpu = tree.gclk[0]
+            .and_then(|g| g.freq)
+            .map(|f| Hertz(f.0 / cpudiv.max(1)));
+
+        tree
+    }
+
+    /// Frequency of a peripheral channel, if it is enabled and known
+    fn channel_freq(&self, id: ClockId) -> Option<Hertz> {
+        let gen = self.channels[u8::from(id) as usize]?.generator?;
+        self.gclk[u8::from(gen) as usize]?.freq
+    }
+
+    /// Frequency of a DPLL, if it is enabled and known
+    fn dpll_freq(&self, i: usize, oscctrl: &oscctrl::RegisterBlock) -> Option<Hertz> {
+        let dpll = &self.dpll[i];
+        if !dpll.enabled {
+            return None;
+        }
+        let id = if i == 0 {
+            ClockId::FDPLL0
+        } else {
+            ClockId::FDPLL1
+        };
+        let prediv = 2 * (oscctrl.dpll[i].dpllctrlb.read().div().bits() as u32 + 1);
+        let reference = match dpll.reference? {
+            DpllReference::Gclk => self.channel_freq(id)?,
+            DpllReference::Xosc32k => self.xosc32k.freq?,
+            DpllReference::Xosc0 => Hertz(self.xosc[0].freq?.0 / prediv),
+            DpllReference::Xosc1 => Hertz(self.xosc[1].freq?.0 / prediv),
+        };
+        let ratio = oscctrl.dpll[i].dpllratio.read();
+        let ldr = ratio.ldr().bits() as u64;
+        let ldrfrac = ratio.ldrfrac().bits() as u64;
+        // f = f_ref * (LDR + 1 + LDRFRAC / 32)
+        let freq = reference.0 as u64 * (32 * (ldr + 1) + ldrfrac) / 32;
+        Some(Hertz(freq as u32))
+    }
+
+    /// Frequency of a generator source, if it is running and known
+    fn source_freq(
+        &self,
+        gen: usize,
+        src: ClockSource,
+        external: &ExternalClocks,
+    ) -> Option<Hertz> {
+        match src {
+            SRC_A::XOSC0 => self.xosc[0].freq,
+            SRC_A::XOSC1 => self.xosc[1].freq,
+            SRC_A::GCLKIN => external.gclkin.get(gen).copied().filter(|f| f.0 != 0),
+            SRC_A::GCLKGEN1 => self.gclk[1]?.freq,
+            SRC_A::OSCULP32K => Some(OSC32K_FREQ),
+            SRC_A::XOSC32K => self.xosc32k.freq,
+            SRC_A::DFLL => self.dfll.freq,
+            SRC_A::DPLL0 => self.dpll[0].freq,
+            SRC_A::DPLL1 => self.dpll[1].freq,
+        }
+    }
+}
+
+impl GenericClockController {
+    /// Read the live clock configuration
+    ///
+    /// The frequencies of XOSC0, XOSC1 and the GCLK_IO inputs are filled in
+    /// from those given to the `GenericClockController`.
+    pub fn clock_tree(&self) -> ClockTree {
+        ClockTree::read(&ExternalClocks {
+            xosc: self.xoscs,
+            gclkin: self.gclkins,
+        })
+    }
+}
+
+//==============================================================================
+// Formatting
+//==============================================================================
+
+struct Freq(Option<Hertz>);
+
+impl fmt::Display for Freq {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Some(freq) => write!(f, "{} Hz", freq.0),
+            None => write!(f, "unknown frequency"),
+        }
+    }
+}
+
+fn write_osc(f: &mut fmt::Formatter, name: &str, osc: &OscState) -> fmt::Result {
+    if osc.enabled {
+        let ready = if osc.ready { "ready" } else { "not ready" };
+        writeln!(f, "{}: enabled, {}, {}", name, ready, Freq(osc.freq))
+    } else {
+        Ok(())
+    }
+}
+
+impl fmt::Display for ClockTree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "CPU: {}", Freq(self.cpu))?;
+        write_osc(f, "XOSC0", &self.xosc[0])?;
+        write_osc(f, "XOSC1", &self.xosc[1])?;
+        write_osc(f, "XOSC32K", &self.xosc32k)?;
+        if self.dfll.enabled {
+            let mode = if self.dfll.closed_loop {
+                "closed loop"
+            } else {
+                "open loop"
+            };
+            writeln!(f, "DFLL: enabled, {}, {}", mode, Freq(self.dfll.freq))?;
+        }
+        for (i, dpll) in self.dpll.iter().enumerate() {
+            if dpll.enabled {
+                let locked = if dpll.locked { "locked" } else { "not locked" };
+                write!(f, "DPLL{}: enabled, {}, reference ", i, locked)?;
+                match dpll.reference {
+                    Some(DpllReference::Gclk) => {
+                        let id = if i == 0 {
+                            ClockId::FDPLL0
+                        } else {
+                            ClockId::FDPLL1
+                        };
+                        match self.channels[u8::from(id) as usize].and_then(|c| c.generator) {
+                            Some(gen) => write!(f, "{:?}", gen)?,
+                            None => write!(f, "GCLK (disabled)")?,
+                        }
+                    }
+                    Some(reference) => write!(f, "{:?}", reference)?,
+                    None => write!(f, "invalid")?,
+                }
+                writeln!(f, ", {}", Freq(dpll.freq))?;
+            }
+        }
+        for (i, gclk) in self.gclk.iter().enumerate() {
+            if let Some(g) = gclk {
+                match g.source {
+                    Some(src) => write!(f, "GCLK{}: {:?} / {}", i, src, g.divider)?,
+                    None => write!(f, "GCLK{}: invalid source", i)?,
+                }
+                if g.output_enable {
+                    write!(f, ", output enabled")?;
+                }
+                if g.run_standby {
+                    write!(f, ", runs in standby")?;
+                }
+                writeln!(f, ", {}", Freq(g.freq))?;
+            }
+        }
+        for c in self.channels.iter().flatten() {
+            match c.generator {
+                Some(gen) => writeln!(f, "{:?}: {:?}, {}", c.id, gen, Freq(c.freq))?,
+                None => writeln!(f, "{:?}: invalid generator", c.id)?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,299 @@
+//! # Type-level clocking API
+//!
+//! [`GenericClockController`](super::GenericClockController) tracks the clock
+//! tree at run-time, but it can't stop a GCLK from being reconfigured while
+//! peripherals still depend on it. This module instead represents every
+//! oscillator, DPLL, generic clock generator (GCLK) and peripheral channel
+//! (PCLK) as a distinct type, and it tracks the users of each clock at the
+//! type level.
+//!
+//! ## Tokens
+//!
+//! Each clock starts life as a token, e.g. [`GclkToken`], [`PclkToken`] or
+//! [`XoscToken`]. Tokens are singletons that represent the exclusive right to
+//! configure the corresponding hardware. All of the tokens are provided by
+//! [`clock_system_at_reset`], which takes ownership of the clocking
+//! peripherals.
+//!
+//! ## Counted references
+//!
+//! When a clock is enabled, it is wrapped in the [`Enabled`] type, which
+//! carries a type-level count of its users, taken from the [`typenum`] crate.
+//! Using a clock as the source for another clock consumes the [`Enabled`]
+//! source and returns it with an incremented count. Freeing the consumer
+//! returns the source with a decremented count. Clocks can only be disabled
+//! when their count is [`U0`], so a generator can never be reconfigured or
+//! disabled while a peripheral depends on it.
+//!
+//! ```
+//! use atsamd_hal::clock::v2::*;
+//! use atsamd_hal::time::U32Ext;
+//!
+//! let (clocks, tokens) = clock_system_at_reset(
+//!     peripherals.OSCCTRL,
+//!     peripherals.OSC32KCTRL,
+//!     peripherals.GCLK,
+//!     &mut peripherals.MCLK,
+//!     &mut peripherals.NVMCTRL,
+//! );
+//!
+//! // Enable XOSC0 with a 12 MHz crystal connected to PA14 and PA15
+//! let xosc0 = Xosc::from_crystal(tokens.xosc0, pins.pa14, pins.pa15, 12.mhz())
+//!     .enable();
+//!
+//! // Feed XOSC0 into GCLK2, divided down to 4 MHz
+//! let (gclk2, xosc0) = Gclk::from_source(tokens.gclks.gclk2, xosc0);
+//! let gclk2 = gclk2.divider(3).enable();
+//!
+//! // Clock SERCOM0 from GCLK2
+//! let (pclk_sercom0, gclk2) = Pclk::enable(tokens.pclks.sercom0, gclk2);
+//! let freq = pclk_sercom0.freq();
+//!
+//! // `gclk2` now has the type `Enabled<Gclk<Gclk2Id, Xosc0Id>, U1>`, so it
+//! // can't be disabled until `pclk_sercom0` is disabled.
+//! let (token, gclk2) = pclk_sercom0.disable(gclk2);
+//! let gclk2 = gclk2.disable();
+//! ```
+//!
+//! At reset, GCLK0 is fed by the DFLL, which runs in open-loop mode at 48 MHz.
+//! GCLK0 drives the CPU, so it always has at least one user, and it can never
+//! be disabled. Instead, it can switch to a different source with
+//! [`Enabled::swap_sources`](Enabled#method.swap_sources).
+//!
+//! ## Interaction with existing drivers
+//!
+//! Most existing drivers require one of the typed clock tokens from the
+//! [`clock`](super) module, like
+//! [`Sercom0CoreClock`](super::Sercom0CoreClock). These can be created from a
+//! reference to the corresponding [`Pclk`]. The [`Pclk`] should be kept alive
+//! for as long as the peripheral is in use.
+//!
+//! This module is only available for the SAMD51/SAME5x family of chips. The
+//! `GCLKIN` source is not supported.
+
+use core::marker::PhantomData;
+use core::ops::{Add, Deref, Sub};
+
+use typenum::{Add1, Sub1, Unsigned, B1};
+
+use crate::target_device::{GCLK, MCLK, NVMCTRL, OSC32KCTRL, OSCCTRL};
+use crate::time::Hertz;
+
+pub mod dfll;
+pub mod dpll;
+pub mod gclk;
+pub mod osculp32k;
+pub mod pclk;
+pub mod xosc;
+pub mod xosc32k;
+
+pub use dfll::*;
+pub use dpll::*;
+pub use gclk::*;
+pub use osculp32k::*;
+pub use pclk::*;
+pub use xosc::*;
+pub use xosc32k::*;
+
+pub use typenum::{U0, U1, U2, U3, U4};
+
+//==============================================================================
+// Enabled
+//==============================================================================
+
+/// An enabled clock with a type-level count of its users
+///
+/// `N` is an [`Unsigned`] type from the [`typenum`] crate. Clocks can only be
+/// disabled when `N` is [`U0`].
+///
+/// [`Enabled`] dereferences to the underlying clock, so its configuration can
+/// be inspected, but not changed.
+pub struct Enabled<T, N: Unsigned = U0> {
+    clock: T,
+    count: PhantomData<N>,
+}
+
+impl<T, N: Unsigned> Enabled<T, N> {
+    #[inline]
+    pub(crate) fn new(clock: T) -> Self {
+        Enabled {
+            clock,
+            count: PhantomData,
+        }
+    }
+
+    /// Return the number of users of the clock
+    #[inline]
+    pub fn users(&self) -> usize {
+        N::USIZE
+    }
+}
+
+impl<T, N: Unsigned> Deref for Enabled<T, N> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.clock
+    }
+}
+
+mod private {
+    /// Token that restricts changes of the user count to this crate
+    ///
+    /// It can't be named or created outside of the crate, so [`Increment`]
+    /// and [`Decrement`] can't be implemented or called there either.
+    ///
+    /// [`Increment`]: super::Increment
+    /// [`Decrement`]: super::Decrement
+    pub struct Token(());
+
+    impl Token {
+        #[inline]
+        pub(crate) fn new() -> Self {
+            Token(())
+        }
+    }
+}
+
+pub(crate) use private::Token;
+
+/// Type-level increment of the user count of an [`Enabled`] clock
+///
+/// The count can only be changed by the HAL, when a clock gains a user.
+pub trait Increment {
+    /// The type with an incremented count
+    type Inc;
+    /// Increment the count
+    fn inc(self, token: Token) -> Self::Inc;
+}
+
+impl<T, N> Increment for Enabled<T, N>
+where
+    N: Unsigned + Add<B1>,
+    Add1<N>: Unsigned,
+{
+    type Inc = Enabled<T, Add1<N>>;
+
+    #[inline]
+    fn inc(self, _: Token) -> Self::Inc {
+        Enabled::new(self.clock)
+    }
+}
+
+/// Type-level decrement of the user count of an [`Enabled`] clock
+///
+/// This trait is not implemented for a count of [`U0`]. The count can only be
+/// changed by the HAL, when a clock loses a user.
+pub trait Decrement {
+    /// The type with a decremented count
+    type Dec;
+    /// Decrement the count
+    fn dec(self, token: Token) -> Self::Dec;
+}
+
+impl<T, N> Decrement for Enabled<T, N>
+where
+    N: Unsigned + Sub<B1>,
+    Sub1<N>: Unsigned,
+{
+    type Dec = Enabled<T, Sub1<N>>;
+
+    #[inline]
+    fn dec(self, _: Token) -> Self::Dec {
+        Enabled::new(self.clock)
+    }
+}
+
+//==============================================================================
+// Source
+//==============================================================================
+
+/// An [`Enabled`] clock that can feed other clocks
+///
+/// `Id` is a type-level identifier for the source, e.g. [`Xosc0Id`]. It
+/// appears as a type parameter of the consumer, e.g. [`Gclk<G, Xosc0Id>`], so
+/// that the consumer can only be freed with the same source.
+pub trait Source {
+    /// Type-level identifier of the source
+    type Id;
+    /// Return the frequency of the source
+    fn freq(&self) -> Hertz;
+}
+
+//==============================================================================
+// clock_system_at_reset
+//==============================================================================
+
+/// Clocks that are enabled at power-on reset
+pub struct Clocks {
+    /// The DFLL, running at 48 MHz in open-loop mode, with GCLK0 as its only
+    /// user
+    pub dfll: Enabled<Dfll, U1>,
+    /// GCLK0, fed by the DFLL, with the CPU as its only user
+    pub gclk0: Enabled<Gclk0<DfllId>, U1>,
+    /// The always-on ultra low-power 32 kHz oscillator
+    pub osculp32k: Enabled<OscUlp32k>,
+}
+
+/// Tokens for all clocks that are disabled at power-on reset
+pub struct Tokens {
+    /// Tokens for GCLK1 to GCLK11
+    pub gclks: GclkTokens,
+    /// Tokens for every peripheral channel
+    pub pclks: PclkTokens,
+    /// Token for XOSC0
+    pub xosc0: XoscToken<Xosc0Id>,
+    /// Token for XOSC1
+    pub xosc1: XoscToken<Xosc1Id>,
+    /// Token for XOSC32K
+    pub xosc32k: Xosc32kToken,
+    /// Token for DPLL0
+    pub dpll0: DpllToken<Dpll0Id>,
+    /// Token for DPLL1
+    pub dpll1: DpllToken<Dpll1Id>,
+}
+
+/// Take ownership of the clocking peripherals and return the clock tree in
+/// its reset state
+///
+/// The `GCLK`, `OSCCTRL` and `OSC32KCTRL` peripherals are consumed, so this
+/// module can't be used together with
+/// [`GenericClockController`](super::GenericClockController). The GCLK
+/// peripheral is reset, in case a bootloader has changed it, which returns
+/// GCLK0 to the DFLL. The flash wait states are set for the maximum CPU
+/// frequency, and the CPU clock divider is reset to one.
+pub fn clock_system_at_reset(
+    oscctrl: OSCCTRL,
+    osc32kctrl: OSC32KCTRL,
+    gclk: GCLK,
+    mclk: &mut MCLK,
+    nvmctrl: &mut NVMCTRL,
+) -> (Clocks, Tokens) {
+    // The peripherals are only consumed to prove that this function is called
+    // once. From here on, each token accesses its own registers.
+    let _ = (oscctrl, osc32kctrl);
+    super::set_flash_to_half_auto_wait_state(nvmctrl);
+    super::State { gclk }.reset_gclk();
+    super::enable_gclk_apb(mclk);
+    mclk.cpudiv.write(|w| w.div().div1());
+    // SAFETY: Each token is a singleton, and this function can only be called
+    // once, because it consumes the PAC structs
+    unsafe {
+        let clocks = Clocks {
+            dfll: Enabled::new(Dfll::new()),
+            gclk0: Enabled::new(Gclk::new(GclkToken::new(), Hertz(dfll::DFLL_FREQ))),
+            osculp32k: Enabled::new(OscUlp32k::new()),
+        };
+        let tokens = Tokens {
+            gclks: GclkTokens::new(),
+            pclks: PclkTokens::new(),
+            xosc0: XoscToken::new(),
+            xosc1: XoscToken::new(),
+            xosc32k: Xosc32kToken::new(),
+            dpll0: DpllToken::new(),
+            dpll1: DpllToken::new(),
+        };
+        (clocks, tokens)
+    }
+}
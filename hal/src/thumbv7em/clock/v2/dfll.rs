@@ -0,0 +1,113 @@
+//! # Digital frequency locked loop
+//!
+//! The DFLL is enabled at reset and runs in open-loop mode at 48 MHz. It feeds
+//! GCLK0, so it starts with one user. Once GCLK0 has been switched to a
+//! different source, the DFLL can be disabled.
+
+use typenum::{Unsigned, U0};
+
+use crate::target_device::oscctrl::RegisterBlock;
+use crate::target_device::OSCCTRL;
+use crate::time::Hertz;
+use crate::typelevel::Sealed;
+
+use super::{Enabled, GclkId, GclkSource, GclkSourceId, Source};
+use crate::target_device::gclk::genctrl::SRC_A;
+
+/// Frequency of the DFLL in open-loop mode
+pub(super) const DFLL_FREQ: u32 = 48_000_000;
+
+/// Type-level identifier of the DFLL
+pub enum DfllId {}
+
+impl Sealed for DfllId {}
+
+impl GclkSourceId for DfllId {
+    const SRC: SRC_A = SRC_A::DFLL;
+}
+
+/// The DFLL in open-loop mode
+pub struct Dfll {
+    on_demand: bool,
+    run_standby: bool,
+}
+
+impl Dfll {
+    /// Create the [`Dfll`] in its reset state
+    ///
+    /// # Safety
+    ///
+    /// The [`Dfll`] is a singleton, so this function must only be called once.
+    #[inline]
+    pub(super) unsafe fn new() -> Self {
+        Dfll {
+            on_demand: true,
+            run_standby: false,
+        }
+    }
+
+    #[inline]
+    fn oscctrl(&self) -> &RegisterBlock {
+        // SAFETY: The DFLL only accesses its own registers
+        unsafe { &*OSCCTRL::ptr() }
+    }
+
+    /// Only run the DFLL when a peripheral requests it
+    #[inline]
+    pub fn on_demand(mut self, on_demand: bool) -> Self {
+        self.on_demand = on_demand;
+        self
+    }
+
+    /// Keep running the DFLL in standby sleep mode
+    #[inline]
+    pub fn run_standby(mut self, run_standby: bool) -> Self {
+        self.run_standby = run_standby;
+        self
+    }
+
+    /// Return the frequency of the DFLL
+    #[inline]
+    pub fn freq(&self) -> Hertz {
+        Hertz(DFLL_FREQ)
+    }
+
+    /// Enable the DFLL
+    pub fn enable(self) -> Enabled<Self, U0> {
+        let oscctrl = self.oscctrl();
+        oscctrl.dfllctrla.write(|w| {
+            w.ondemand().bit(self.on_demand);
+            w.runstdby().bit(self.run_standby);
+            w.enable().set_bit()
+        });
+        while oscctrl.dfllsync.read().enable().bit_is_set() {}
+        if !self.on_demand {
+            while oscctrl.status.read().dfllrdy().bit_is_clear() {}
+        }
+        Enabled::new(self)
+    }
+}
+
+impl Enabled<Dfll, U0> {
+    /// Disable the DFLL
+    ///
+    /// The DFLL can only be disabled when it has no users.
+    #[inline]
+    pub fn disable(self) -> Dfll {
+        let oscctrl = self.clock.oscctrl();
+        oscctrl.dfllctrla.modify(|_, w| w.enable().clear_bit());
+        while oscctrl.dfllsync.read().enable().bit_is_set() {}
+        self.clock
+    }
+}
+
+impl<N: Unsigned> Source for Enabled<Dfll, N> {
+    type Id = DfllId;
+
+    #[inline]
+    fn freq(&self) -> Hertz {
+        self.clock.freq()
+    }
+}
+
+impl<G: GclkId, N: Unsigned> GclkSource<G> for Enabled<Dfll, N> {}
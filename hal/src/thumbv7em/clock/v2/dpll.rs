@@ -0,0 +1,387 @@
+//! # Digital phase locked loops
+//!
+//! A [`Dpll`] is parameterized by its [`DpllId`] and the type-level identifier
+//! of its reference clock. The reference can be XOSC0, XOSC1, XOSC32K or a
+//! GCLK. A GCLK reference is provided through the dedicated peripheral
+//! channel of the DPLL, so a [`Dpll`] with a GCLK reference is created from a
+//! [`Pclk`], and it holds the [`Pclk`] until it is freed.
+//!
+//! The output frequency is `f_ref * (LDR + 1 + LDRFRAC / 32)`. The reference
+//! frequency must be between 32 kHz and 3.2 MHz, and the output frequency must
+//! be between 96 MHz and 200 MHz.
+//!
+//! ```
+//! // 12 MHz XOSC0, divided to a 2 MHz reference and multiplied by 60
+//! let (dpll0, xosc0) = Dpll::from_xosc(tokens.dpll0, xosc0);
+//! let dpll0 = dpll0.prediv(6).loop_div(60, 0).enable();
+//! ```
+
+use core::marker::PhantomData;
+
+use typenum::{Unsigned, U0};
+
+use crate::target_device::gclk::genctrl::SRC_A;
+use crate::target_device::oscctrl::dpll::dpllctrlb::REFCLK_A;
+use crate::target_device::oscctrl::DPLL;
+use crate::target_device::OSCCTRL;
+use crate::time::Hertz;
+use crate::typelevel::Sealed;
+
+use super::{
+    Decrement, Enabled, GclkId, GclkSource, GclkSourceId, Increment, Pclk, PclkId, PclkToken,
+    Source, Token, Xosc0Id, Xosc1Id, Xosc32k, Xosc32kId, XoscId,
+};
+
+//==============================================================================
+// Ids
+//==============================================================================
+
+/// Type-level identifier of DPLL0 or DPLL1
+pub trait DpllId: Sealed {
+    /// Index of the DPLL
+    const NUM: usize;
+    /// Peripheral channel that provides the GCLK reference
+    type Pclk: PclkId;
+}
+
+/// Type-level identifier of DPLL0
+pub enum Dpll0Id {}
+
+impl Sealed for Dpll0Id {}
+
+impl DpllId for Dpll0Id {
+    const NUM: usize = 0;
+    type Pclk = super::pclk::Dpll0;
+}
+
+impl GclkSourceId for Dpll0Id {
+    const SRC: SRC_A = SRC_A::DPLL0;
+}
+
+/// Type-level identifier of DPLL1
+pub enum Dpll1Id {}
+
+impl Sealed for Dpll1Id {}
+
+impl DpllId for Dpll1Id {
+    const NUM: usize = 1;
+    type Pclk = super::pclk::Dpll1;
+}
+
+impl GclkSourceId for Dpll1Id {
+    const SRC: SRC_A = SRC_A::DPLL1;
+}
+
+/// Type-level identifier of a DPLL reference clock
+///
+/// This is implemented for [`Xosc0Id`], [`Xosc1Id`], [`Xosc32kId`] and every
+/// [`GclkId`].
+pub trait DpllSourceId: Sealed {}
+
+impl DpllSourceId for Xosc0Id {}
+
+impl DpllSourceId for Xosc1Id {}
+
+impl DpllSourceId for Xosc32kId {}
+
+macro_rules! gclk_dpll_source {
+    ( $($Id:ident),+ ) => {
+        $( impl DpllSourceId for super::$Id {} )+
+    };
+}
+
+gclk_dpll_source!(
+    Gclk0Id, Gclk1Id, Gclk2Id, Gclk3Id, Gclk4Id, Gclk5Id, Gclk6Id, Gclk7Id, Gclk8Id, Gclk9Id,
+    Gclk10Id, Gclk11Id
+);
+
+//==============================================================================
+// DpllToken
+//==============================================================================
+
+/// Singleton token for DPLL0 or DPLL1
+pub struct DpllToken<D: DpllId> {
+    id: PhantomData<D>,
+}
+
+impl<D: DpllId> DpllToken<D> {
+    /// Create a new token
+    ///
+    /// # Safety
+    ///
+    /// Each token is a singleton. There must never be more than one instance
+    /// of a token for a given [`DpllId`].
+    #[inline]
+    pub(super) unsafe fn new() -> Self {
+        DpllToken { id: PhantomData }
+    }
+
+    #[inline]
+    fn dpll(&self) -> &DPLL {
+        // SAFETY: Each token only accesses its own DPLL registers
+        unsafe { &(*OSCCTRL::ptr()).dpll[D::NUM] }
+    }
+
+    fn disable(&self) {
+        let dpll = self.dpll();
+        dpll.dpllctrla.modify(|_, w| w.enable().clear_bit());
+        while dpll.dpllsyncbusy.read().enable().bit_is_set() {}
+    }
+}
+
+//==============================================================================
+// Dpll
+//==============================================================================
+
+/// A digital phase locked loop
+///
+/// `D` identifies the DPLL and `I` identifies its reference clock.
+pub struct Dpll<D: DpllId, I: DpllSourceId> {
+    token: DpllToken<D>,
+    src: PhantomData<I>,
+    /// Peripheral channel token, only present for a GCLK reference
+    pclk: Option<PclkToken<D::Pclk>>,
+    refclk: REFCLK_A,
+    src_freq: Hertz,
+    prediv: u16,
+    ldr: u16,
+    ldrfrac: u8,
+    on_demand: bool,
+    run_standby: bool,
+}
+
+impl<D: DpllId, I: DpllSourceId> Dpll<D, I> {
+    #[inline]
+    fn new(
+        token: DpllToken<D>,
+        pclk: Option<PclkToken<D::Pclk>>,
+        refclk: REFCLK_A,
+        src_freq: Hertz,
+    ) -> Self {
+        Dpll {
+            token,
+            src: PhantomData,
+            pclk,
+            refclk,
+            src_freq,
+            // The XOSC pre-divider is always active and divides by at least 2
+            prediv: match refclk {
+                REFCLK_A::XOSC0 | REFCLK_A::XOSC1 => 2,
+                _ => 1,
+            },
+            ldr: 0,
+            ldrfrac: 0,
+            on_demand: false,
+            run_standby: false,
+        }
+    }
+
+    /// Set the loop divider, i.e. the multiplication factor `int + frac / 32`
+    ///
+    /// `int` must be at least one.
+    #[inline]
+    pub fn loop_div(mut self, int: u16, frac: u8) -> Self {
+        self.ldr = int.saturating_sub(1);
+        self.ldrfrac = frac;
+        self
+    }
+
+    /// Only run the DPLL when a peripheral requests it
+    #[inline]
+    pub fn on_demand(mut self, on_demand: bool) -> Self {
+        self.on_demand = on_demand;
+        self
+    }
+
+    /// Keep running the DPLL in standby sleep mode
+    #[inline]
+    pub fn run_standby(mut self, run_standby: bool) -> Self {
+        self.run_standby = run_standby;
+        self
+    }
+
+    /// Return the reference frequency, after the XOSC pre-divider, if any
+    #[inline]
+    pub fn ref_freq(&self) -> Hertz {
+        Hertz(self.src_freq.0 / self.prediv as u32)
+    }
+
+    /// Return the output frequency
+    #[inline]
+    pub fn freq(&self) -> Hertz {
+        let mult = (self.ldr as u64 + 1) * 32 + self.ldrfrac as u64;
+        Hertz((self.ref_freq().0 as u64 * mult / 32) as u32)
+    }
+
+    /// Enable the DPLL and wait for it to lock
+    ///
+    /// # Panics
+    ///
+    /// Panics if the reference or output frequency is out of range, or if the
+    /// loop divider doesn't fit in the DPLLRATIO register.
+    pub fn enable(self) -> Enabled<Self, U0> {
+        let fref = self.ref_freq().0;
+        let fout = self.freq().0;
+        assert!(
+            (32_000..=3_200_000).contains(&fref),
+            "DPLL reference frequency out of range"
+        );
+        assert!(
+            self.ldr <= 0x1FFF && self.ldrfrac <= 31,
+            "DPLL loop divider out of range"
+        );
+        assert!(
+            (96_000_000..=200_000_000).contains(&fout),
+            "DPLL output frequency out of range"
+        );
+
+        let dpll = self.token.dpll();
+        dpll.dpllratio.write(|w| unsafe {
+            w.ldr().bits(self.ldr);
+            w.ldrfrac().bits(self.ldrfrac)
+        });
+        while dpll.dpllsyncbusy.read().dpllratio().bit_is_set() {}
+        // The XOSC is divided by 2 * (DIV + 1)
+        let div = (self.prediv / 2).saturating_sub(1);
+        dpll.dpllctrlb.write(|w| unsafe {
+            w.refclk().variant(self.refclk);
+            w.div().bits(div)
+        });
+        dpll.dpllctrla.write(|w| {
+            w.ondemand().bit(self.on_demand);
+            w.runstdby().bit(self.run_standby);
+            w.enable().set_bit()
+        });
+        while dpll.dpllsyncbusy.read().enable().bit_is_set() {}
+        if !self.on_demand {
+            while dpll.dpllstatus.read().lock().bit_is_clear()
+                || dpll.dpllstatus.read().clkrdy().bit_is_clear()
+            {}
+        }
+        Enabled::new(self)
+    }
+}
+
+impl<D: DpllId, G: GclkId + DpllSourceId> Dpll<D, G> {
+    /// Create a [`Dpll`] referenced to a GCLK through its peripheral channel
+    #[inline]
+    pub fn from_pclk(token: DpllToken<D>, pclk: Pclk<D::Pclk, G>) -> Self {
+        let freq = pclk.freq();
+        let (pclk, _) = pclk.into_parts();
+        Dpll::new(token, Some(pclk), REFCLK_A::GCLK, freq)
+    }
+
+    /// Free the [`Dpll`] and return its token and peripheral channel
+    #[inline]
+    pub fn free_pclk(self) -> (DpllToken<D>, Pclk<D::Pclk, G>) {
+        // A `Dpll` with a GCLK reference is always created with a `PclkToken`
+        let pclk = self.pclk.unwrap();
+        (self.token, Pclk::from_parts(pclk, self.src_freq))
+    }
+}
+
+impl<D: DpllId, X: XoscId + DpllSourceId> Dpll<D, X> {
+    /// Create a [`Dpll`] referenced to XOSC0 or XOSC1
+    ///
+    /// The user count of the XOSC is incremented.
+    #[inline]
+    pub fn from_xosc<S>(token: DpllToken<D>, xosc: S) -> (Self, S::Inc)
+    where
+        S: Source<Id = X> + Increment,
+    {
+        let refclk = match X::NUM {
+            0 => REFCLK_A::XOSC0,
+            _ => REFCLK_A::XOSC1,
+        };
+        let dpll = Dpll::new(token, None, refclk, xosc.freq());
+        (dpll, xosc.inc(Token::new()))
+    }
+
+    /// Set the XOSC pre-divider
+    ///
+    /// The XOSC frequency is divided by `prediv`, which is rounded down to an
+    /// even number between 2 and 4096. The default is 2.
+    #[inline]
+    pub fn prediv(mut self, prediv: u16) -> Self {
+        self.prediv = (prediv & !1).clamp(2, 4096);
+        self
+    }
+
+    /// Free the [`Dpll`] and return its token
+    ///
+    /// The user count of the XOSC is decremented.
+    #[inline]
+    pub fn free_xosc<S>(self, xosc: S) -> (DpllToken<D>, S::Dec)
+    where
+        S: Source<Id = X> + Decrement,
+    {
+        (self.token, xosc.dec(Token::new()))
+    }
+}
+
+impl<D: DpllId> Dpll<D, Xosc32kId> {
+    /// Create a [`Dpll`] referenced to XOSC32K
+    ///
+    /// The user count of XOSC32K is incremented.
+    #[inline]
+    pub fn from_xosc32k<N>(
+        token: DpllToken<D>,
+        xosc32k: Enabled<Xosc32k, N>,
+    ) -> (Self, <Enabled<Xosc32k, N> as Increment>::Inc)
+    where
+        N: Unsigned,
+        Enabled<Xosc32k, N>: Increment,
+    {
+        let dpll = Dpll::new(token, None, REFCLK_A::XOSC32, xosc32k.freq());
+        (dpll, xosc32k.inc(Token::new()))
+    }
+
+    /// Free the [`Dpll`] and return its token
+    ///
+    /// The user count of XOSC32K is decremented.
+    #[inline]
+    pub fn free_xosc32k<N>(
+        self,
+        xosc32k: Enabled<Xosc32k, N>,
+    ) -> (DpllToken<D>, <Enabled<Xosc32k, N> as Decrement>::Dec)
+    where
+        N: Unsigned,
+        Enabled<Xosc32k, N>: Decrement,
+    {
+        (self.token, xosc32k.dec(Token::new()))
+    }
+}
+
+impl<D: DpllId, I: DpllSourceId> Enabled<Dpll<D, I>, U0> {
+    /// Disable the DPLL
+    ///
+    /// The DPLL can only be disabled when it has no users.
+    #[inline]
+    pub fn disable(self) -> Dpll<D, I> {
+        self.clock.token.disable();
+        self.clock
+    }
+}
+
+impl<D, I, N> Source for Enabled<Dpll<D, I>, N>
+where
+    D: DpllId + GclkSourceId,
+    I: DpllSourceId,
+    N: Unsigned,
+{
+    type Id = D;
+
+    #[inline]
+    fn freq(&self) -> Hertz {
+        self.clock.freq()
+    }
+}
+
+impl<G, D, I, N> GclkSource<G> for Enabled<Dpll<D, I>, N>
+where
+    G: GclkId,
+    D: DpllId + GclkSourceId,
+    I: DpllSourceId,
+    N: Unsigned,
+{
+}
@@ -0,0 +1,406 @@
+//! # Generic clock generators
+//!
+//! Each of the twelve generic clock generators, GCLK0 to GCLK11, is
+//! represented by a [`Gclk`], parameterized by its [`GclkId`] and the
+//! [`GclkSourceId`] of its source. A [`Gclk`] is created from a [`GclkToken`]
+//! and an [`Enabled`] source, whose user count is incremented. The divider
+//! and other settings can only be changed while the [`Gclk`] is disabled,
+//! which requires that it has no users.
+//!
+//! GCLK0 drives the CPU. It is enabled at reset and has no token, so it can't
+//! be disabled. See [`Enabled::swap_sources`](Enabled#method.swap_sources)
+//! and [`Enabled::set_div`](Enabled#method.set_div) instead.
+
+use core::marker::PhantomData;
+
+use typenum::U0;
+
+use crate::target_device::gclk::genctrl::SRC_A;
+use crate::target_device::gclk::GENCTRL;
+use crate::target_device::GCLK;
+use crate::time::Hertz;
+use crate::typelevel::Sealed;
+
+use super::{Decrement, Enabled, Increment, Source, Token, U1};
+
+/// Maximum frequency of GCLK0, which drives the CPU
+const MAX_GCLK0_FREQ: u32 = 120_000_000;
+
+/// Maximum frequency of all other GCLKs
+const MAX_GCLK_FREQ: u32 = 200_000_000;
+
+//==============================================================================
+// GclkId
+//==============================================================================
+
+/// Type-level identifier of a GCLK
+pub trait GclkId: Sealed {
+    /// Index of the GCLK
+    const NUM: usize;
+    /// Type of the GCLK divider
+    ///
+    /// GCLK1 has a 16-bit divider, while all other GCLKs have an 8-bit
+    /// divider.
+    type Divider: Copy + Into<u16>;
+}
+
+/// Type-level identifier of a [`Gclk`] source
+pub trait GclkSourceId: Sealed {
+    /// Value of the GENCTRL.SRC field
+    const SRC: SRC_A;
+}
+
+macro_rules! gclk_ids {
+    ( $( ($Id:ident, $Alias:ident, $num:literal, $Div:ty) ),+ $(,)? ) => {
+        $(
+            /// Type-level identifier of a GCLK
+            pub enum $Id {}
+            impl Sealed for $Id {}
+            impl GclkId for $Id {
+                const NUM: usize = $num;
+                type Divider = $Div;
+            }
+
+            /// Type alias for a [`Gclk`] with a specific [`GclkId`]
+            pub type $Alias<I> = Gclk<$Id, I>;
+        )+
+    };
+}
+
+gclk_ids!(
+    (Gclk0Id, Gclk0, 0, u8),
+    (Gclk1Id, Gclk1, 1, u16),
+    (Gclk2Id, Gclk2, 2, u8),
+    (Gclk3Id, Gclk3, 3, u8),
+    (Gclk4Id, Gclk4, 4, u8),
+    (Gclk5Id, Gclk5, 5, u8),
+    (Gclk6Id, Gclk6, 6, u8),
+    (Gclk7Id, Gclk7, 7, u8),
+    (Gclk8Id, Gclk8, 8, u8),
+    (Gclk9Id, Gclk9, 9, u8),
+    (Gclk10Id, Gclk10, 10, u8),
+    (Gclk11Id, Gclk11, 11, u8),
+);
+
+/// GCLK1 can act as the source of all other GCLKs
+impl GclkSourceId for Gclk1Id {
+    const SRC: SRC_A = SRC_A::GCLKGEN1;
+}
+
+//==============================================================================
+// GclkToken
+//==============================================================================
+
+/// Singleton token for a GCLK
+///
+/// The token represents the exclusive right to configure the GENCTRL register
+/// of the corresponding GCLK.
+pub struct GclkToken<G: GclkId> {
+    gen: PhantomData<G>,
+}
+
+impl<G: GclkId> GclkToken<G> {
+    /// Create a new token
+    ///
+    /// # Safety
+    ///
+    /// Each token is a singleton. There must never be more than one instance
+    /// of a token for a given [`GclkId`].
+    #[inline]
+    pub(super) unsafe fn new() -> Self {
+        GclkToken { gen: PhantomData }
+    }
+
+    #[inline]
+    fn gclk(&self) -> &crate::target_device::gclk::RegisterBlock {
+        // SAFETY: Each token only accesses its own GENCTRL register and the
+        // read-only SYNCBUSY register
+        unsafe { &*GCLK::ptr() }
+    }
+
+    #[inline]
+    fn genctrl(&self) -> &GENCTRL {
+        &self.gclk().genctrl[G::NUM]
+    }
+
+    #[inline]
+    fn wait_for_sync(&self) {
+        let mask = 1 << (G::NUM + 2);
+        while self.gclk().syncbusy.read().bits() & mask != 0 {}
+    }
+
+    fn enable(&self, src: SRC_A, div: u16, improve_duty_cycle: bool, run_standby: bool) {
+        self.genctrl().write(|w| unsafe {
+            w.src().variant(src);
+            w.div().bits(div);
+            // divide directly by divider, rather than 2^(n+1)
+            w.divsel().clear_bit();
+            w.idc().bit(improve_duty_cycle);
+            w.runstdby().bit(run_standby);
+            w.genen().set_bit()
+        });
+        self.wait_for_sync();
+    }
+
+    fn disable(&self) {
+        self.genctrl().modify(|_, w| w.genen().clear_bit());
+        self.wait_for_sync();
+    }
+}
+
+/// Tokens for GCLK1 to GCLK11
+///
+/// GCLK0 is enabled at reset, so it has no token. See
+/// [`Clocks`](super::Clocks).
+#[allow(missing_docs)]
+pub struct GclkTokens {
+    pub gclk1: GclkToken<Gclk1Id>,
+    pub gclk2: GclkToken<Gclk2Id>,
+    pub gclk3: GclkToken<Gclk3Id>,
+    pub gclk4: GclkToken<Gclk4Id>,
+    pub gclk5: GclkToken<Gclk5Id>,
+    pub gclk6: GclkToken<Gclk6Id>,
+    pub gclk7: GclkToken<Gclk7Id>,
+    pub gclk8: GclkToken<Gclk8Id>,
+    pub gclk9: GclkToken<Gclk9Id>,
+    pub gclk10: GclkToken<Gclk10Id>,
+    pub gclk11: GclkToken<Gclk11Id>,
+}
+
+impl GclkTokens {
+    /// Create the set of tokens
+    ///
+    /// # Safety
+    ///
+    /// All of the tokens are singletons, so this function must only be called
+    /// once.
+    #[inline]
+    pub(super) unsafe fn new() -> Self {
+        GclkTokens {
+            gclk1: GclkToken::new(),
+            gclk2: GclkToken::new(),
+            gclk3: GclkToken::new(),
+            gclk4: GclkToken::new(),
+            gclk5: GclkToken::new(),
+            gclk6: GclkToken::new(),
+            gclk7: GclkToken::new(),
+            gclk8: GclkToken::new(),
+            gclk9: GclkToken::new(),
+            gclk10: GclkToken::new(),
+            gclk11: GclkToken::new(),
+        }
+    }
+}
+
+//==============================================================================
+// GclkSource
+//==============================================================================
+
+/// Marker trait for [`Enabled`] clocks that can feed the GCLK `G`
+///
+/// All oscillators and DPLLs can feed any GCLK. GCLK1 can feed any GCLK
+/// except itself.
+pub trait GclkSource<G: GclkId>: Source {}
+
+//==============================================================================
+// Gclk
+//==============================================================================
+
+/// A generic clock generator
+///
+/// `G` identifies the generator and `I` identifies its source.
+pub struct Gclk<G: GclkId, I: GclkSourceId> {
+    token: GclkToken<G>,
+    src: PhantomData<I>,
+    src_freq: Hertz,
+    div: u16,
+    improve_duty_cycle: bool,
+    run_standby: bool,
+}
+
+impl<G: GclkId, I: GclkSourceId> Gclk<G, I> {
+    #[inline]
+    pub(super) fn new(token: GclkToken<G>, src_freq: Hertz) -> Self {
+        Gclk {
+            token,
+            src: PhantomData,
+            src_freq,
+            div: 1,
+            improve_duty_cycle: false,
+            run_standby: false,
+        }
+    }
+
+    /// Create a disabled [`Gclk`] from a token and an [`Enabled`] source
+    ///
+    /// The user count of the source is incremented.
+    #[inline]
+    pub fn from_source<S>(token: GclkToken<G>, source: S) -> (Self, S::Inc)
+    where
+        S: GclkSource<G, Id = I> + Increment,
+    {
+        let gclk = Gclk::new(token, source.freq());
+        (gclk, source.inc(Token::new()))
+    }
+
+    /// Free the [`Gclk`] and return its token
+    ///
+    /// The user count of the source is decremented.
+    #[inline]
+    pub fn free<S>(self, source: S) -> (GclkToken<G>, S::Dec)
+    where
+        S: GclkSource<G, Id = I> + Decrement,
+    {
+        (self.token, source.dec(Token::new()))
+    }
+
+    /// Set the linear divider
+    ///
+    /// A divider of zero is treated as one.
+    #[inline]
+    pub fn divider(mut self, div: G::Divider) -> Self {
+        self.div = div.into();
+        self
+    }
+
+    /// Produce a 50/50 duty cycle for odd divider values
+    #[inline]
+    pub fn improve_duty_cycle(mut self, idc: bool) -> Self {
+        self.improve_duty_cycle = idc;
+        self
+    }
+
+    /// Keep running the generator in standby sleep mode
+    #[inline]
+    pub fn run_standby(mut self, run_standby: bool) -> Self {
+        self.run_standby = run_standby;
+        self
+    }
+
+    /// Return the effective divider
+    #[inline]
+    pub fn get_div(&self) -> u16 {
+        self.div.max(1)
+    }
+
+    /// Return the output frequency
+    #[inline]
+    pub fn freq(&self) -> Hertz {
+        Hertz(self.src_freq.0 / self.get_div() as u32)
+    }
+
+    /// Enable the generator
+    ///
+    /// # Panics
+    ///
+    /// Panics if the output frequency exceeds 200 MHz.
+    #[inline]
+    pub fn enable(self) -> Enabled<Self, U0> {
+        assert!(self.freq().0 <= MAX_GCLK_FREQ, "GCLK frequency too high");
+        self.apply();
+        Enabled::new(self)
+    }
+
+    #[inline]
+    fn apply(&self) {
+        self.token.enable(
+            I::SRC,
+            self.get_div(),
+            self.improve_duty_cycle,
+            self.run_standby,
+        );
+    }
+}
+
+impl<G: GclkId, I: GclkSourceId> Enabled<Gclk<G, I>, U0> {
+    /// Disable the generator
+    ///
+    /// Only generators without users can be disabled.
+    #[inline]
+    pub fn disable(self) -> Gclk<G, I> {
+        self.clock.token.disable();
+        self.clock
+    }
+}
+
+/// Value returned by [`swap_sources`](Enabled::swap_sources)
+///
+/// Contains the reconfigured GCLK0 and the updated old and new sources.
+pub type Gclk0Swap<O, N> = (
+    Enabled<Gclk0<<N as Source>::Id>, U1>,
+    <O as Decrement>::Dec,
+    <N as Increment>::Inc,
+);
+
+impl<I: GclkSourceId> Enabled<Gclk0<I>, U1> {
+    /// Switch GCLK0 to a different source
+    ///
+    /// GCLK0 always drives the CPU, so its sole user is the CPU. The source
+    /// can only be changed when no peripheral channel depends on GCLK0.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new frequency of GCLK0 would exceed 120 MHz.
+    pub fn swap_sources<O, N>(self, old: O, new: N) -> Gclk0Swap<O, N>
+    where
+        O: GclkSource<Gclk0Id, Id = I> + Decrement,
+        N: GclkSource<Gclk0Id> + Increment,
+        N::Id: GclkSourceId,
+    {
+        let mut gclk0: Gclk0<N::Id> = Gclk::new(self.clock.token, new.freq());
+        gclk0.div = self.clock.div;
+        gclk0.improve_duty_cycle = self.clock.improve_duty_cycle;
+        gclk0.run_standby = self.clock.run_standby;
+        assert!(gclk0.freq().0 <= MAX_GCLK0_FREQ, "CPU frequency too high");
+        gclk0.apply();
+        (
+            Enabled::new(gclk0),
+            old.dec(Token::new()),
+            new.inc(Token::new()),
+        )
+    }
+
+    /// Change the GCLK0 divider
+    ///
+    /// Like [`swap_sources`](Self::swap_sources), this is only possible when
+    /// the CPU is the sole user of GCLK0.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new frequency of GCLK0 would exceed 120 MHz.
+    pub fn set_div(&mut self, div: u8) {
+        let gclk0 = &mut self.clock;
+        let old = gclk0.div;
+        gclk0.div = div.into();
+        if gclk0.freq().0 > MAX_GCLK0_FREQ {
+            gclk0.div = old;
+            panic!("CPU frequency too high");
+        }
+        gclk0.apply();
+    }
+}
+
+impl<G: GclkId, I: GclkSourceId, N: typenum::Unsigned> Source for Enabled<Gclk<G, I>, N> {
+    type Id = G;
+
+    #[inline]
+    fn freq(&self) -> Hertz {
+        self.clock.freq()
+    }
+}
+
+macro_rules! gclk1_source {
+    ( $($Id:ident),+ ) => {
+        $(
+            impl<I: GclkSourceId, N: typenum::Unsigned> GclkSource<$Id>
+                for Enabled<Gclk1<I>, N>
+            {
+            }
+        )+
+    };
+}
+
+gclk1_source!(
+    Gclk0Id, Gclk2Id, Gclk3Id, Gclk4Id, Gclk5Id, Gclk6Id, Gclk7Id, Gclk8Id, Gclk9Id, Gclk10Id,
+    Gclk11Id
+);
@@ -0,0 +1,57 @@
+//! # Ultra low-power 32 kHz oscillator
+//!
+//! OSCULP32K is always running and can't be disabled. Its [`Enabled`] wrapper
+//! still counts its users, like every other clock.
+
+use typenum::Unsigned;
+
+use crate::target_device::gclk::genctrl::SRC_A;
+use crate::time::Hertz;
+use crate::typelevel::Sealed;
+
+use super::{Enabled, GclkId, GclkSource, GclkSourceId, Source};
+use crate::clock::OSC32K_FREQ;
+
+/// Type-level identifier of OSCULP32K
+pub enum OscUlp32kId {}
+
+impl Sealed for OscUlp32kId {}
+
+impl GclkSourceId for OscUlp32kId {
+    const SRC: SRC_A = SRC_A::OSCULP32K;
+}
+
+/// The ultra low-power 32 kHz oscillator
+pub struct OscUlp32k {
+    _private: (),
+}
+
+impl OscUlp32k {
+    /// Create the [`OscUlp32k`]
+    ///
+    /// # Safety
+    ///
+    /// The [`OscUlp32k`] is a singleton, so this function must only be called
+    /// once.
+    #[inline]
+    pub(super) unsafe fn new() -> Self {
+        OscUlp32k { _private: () }
+    }
+
+    /// Return the frequency of the oscillator
+    #[inline]
+    pub fn freq(&self) -> Hertz {
+        OSC32K_FREQ
+    }
+}
+
+impl<N: Unsigned> Source for Enabled<OscUlp32k, N> {
+    type Id = OscUlp32kId;
+
+    #[inline]
+    fn freq(&self) -> Hertz {
+        self.clock.freq()
+    }
+}
+
+impl<G: GclkId, N: Unsigned> GclkSource<G> for Enabled<OscUlp32k, N> {}
@@ -0,0 +1,345 @@
+//! # Peripheral channels
+//!
+//! Each peripheral channel connects one or more peripherals to a GCLK. A
+//! [`Pclk`] is parameterized by its [`PclkId`] and by the [`GclkId`] of the
+//! generator that feeds it. Enabling a [`Pclk`] increments the user count of
+//! its [`Gclk`](super::Gclk), and disabling it decrements the count again.
+//!
+//! Existing drivers expect the clock tokens of the [`clock`](super::super)
+//! module. These can be created by converting the corresponding [`Pclk`]. The
+//! conversion consumes the [`Pclk`], so the peripheral channel stays enabled
+//! and can't be reconfigured while the clock token is in use. The user count
+//! of the [`Gclk`](super::Gclk) is never decremented again.
+//!
+//! ```
+//! let (pclk, gclk0) = Pclk::enable(tokens.pclks.sercom0, clocks.gclk0);
+//! let sercom0_clock: Sercom0CoreClock = pclk.into();
+//! ```
+
+use core::marker::PhantomData;
+
+use crate::target_device::gclk::PCHCTRL;
+use crate::target_device::GCLK;
+use crate::time::Hertz;
+use crate::typelevel::Sealed;
+
+use super::{Decrement, Enabled, GclkId, GclkSourceId, Increment, Source, Token};
+use crate::clock::ClockId;
+
+//==============================================================================
+// PclkId
+//==============================================================================
+
+/// Type-level identifier of a peripheral channel
+pub trait PclkId: Sealed {
+    /// Index of the PCHCTRL register
+    const ID: usize;
+}
+
+//==============================================================================
+// PclkToken
+//==============================================================================
+
+/// Singleton token for a peripheral channel
+///
+/// The token represents the exclusive right to configure the PCHCTRL register
+/// of the corresponding channel.
+pub struct PclkToken<P: PclkId> {
+    id: PhantomData<P>,
+}
+
+impl<P: PclkId> PclkToken<P> {
+    /// Create a new token
+    ///
+    /// # Safety
+    ///
+    /// Each token is a singleton. There must never be more than one instance
+    /// of a token for a given [`PclkId`].
+    #[inline]
+    pub(super) unsafe fn new() -> Self {
+        PclkToken { id: PhantomData }
+    }
+
+    #[inline]
+    fn pchctrl(&self) -> &PCHCTRL {
+        // SAFETY: Each token only accesses its own PCHCTRL register
+        unsafe { &(*GCLK::ptr()).pchctrl[P::ID] }
+    }
+
+    fn enable(&self, gen: u8) {
+        self.pchctrl().write(|w| unsafe {
+            w.gen().bits(gen);
+            w.chen().set_bit()
+        });
+        while self.pchctrl().read().chen().bit_is_clear() {}
+    }
+
+    fn disable(&self) {
+        self.pchctrl().modify(|_, w| w.chen().clear_bit());
+        while self.pchctrl().read().chen().bit_is_set() {}
+    }
+}
+
+//==============================================================================
+// Pclk
+//==============================================================================
+
+/// Marker trait for [`Enabled`] clocks that can feed a peripheral channel
+///
+/// Only GCLKs can feed peripheral channels.
+pub trait PclkSource: Source {}
+
+impl<G: GclkId, I: GclkSourceId, N: typenum::Unsigned> PclkSource
+    for Enabled<super::Gclk<G, I>, N>
+{
+}
+
+/// An enabled peripheral channel
+///
+/// `P` identifies the channel and `G` identifies the GCLK that feeds it.
+pub struct Pclk<P: PclkId, G: GclkId> {
+    token: PclkToken<P>,
+    gen: PhantomData<G>,
+    freq: Hertz,
+}
+
+impl<P: PclkId, G: GclkId> Pclk<P, G> {
+    /// Enable the peripheral channel, fed by the given GCLK
+    ///
+    /// The user count of the GCLK is incremented.
+    #[inline]
+    pub fn enable<S>(token: PclkToken<P>, gclk: S) -> (Self, S::Inc)
+    where
+        S: PclkSource<Id = G> + Increment,
+    {
+        token.enable(G::NUM as u8);
+        let pclk = Pclk {
+            token,
+            gen: PhantomData,
+            freq: gclk.freq(),
+        };
+        (pclk, gclk.inc(Token::new()))
+    }
+
+    /// Disable the peripheral channel and return its token
+    ///
+    /// The user count of the GCLK is decremented.
+    #[inline]
+    pub fn disable<S>(self, gclk: S) -> (PclkToken<P>, S::Dec)
+    where
+        S: PclkSource<Id = G> + Decrement,
+    {
+        self.token.disable();
+        (self.token, gclk.dec(Token::new()))
+    }
+
+    /// Return the frequency of the peripheral channel
+    #[inline]
+    pub fn freq(&self) -> Hertz {
+        self.freq
+    }
+
+    /// Split an enabled [`Pclk`] into its token and frequency, without
+    /// disabling it
+    #[inline]
+    pub(super) fn into_parts(self) -> (PclkToken<P>, Hertz) {
+        (self.token, self.freq)
+    }
+
+    /// Recreate an enabled [`Pclk`] from the parts returned by
+    /// [`into_parts`](Self::into_parts)
+    #[inline]
+    pub(super) fn from_parts(token: PclkToken<P>, freq: Hertz) -> Self {
+        Pclk {
+            token,
+            gen: PhantomData,
+            freq,
+        }
+    }
+}
+
+impl<P: PclkId, G: GclkId> From<&Pclk<P, G>> for Hertz {
+    #[inline]
+    fn from(pclk: &Pclk<P, G>) -> Hertz {
+        pclk.freq
+    }
+}
+
+//==============================================================================
+// Peripheral channel IDs
+//==============================================================================
+
+macro_rules! pclk_ids {
+    (
+        $(
+            $(#[$attr:meta])*
+            ($Id:ident, $field:ident, $clock:ident),
+        )+
+    ) => {
+        $(
+            $(#[$attr])*
+            /// Type-level identifier of a peripheral channel
+            pub enum $Id {}
+
+            $(#[$attr])*
+            impl Sealed for $Id {}
+
+            $(#[$attr])*
+            impl PclkId for $Id {
+                const ID: usize = ClockId::$clock as usize;
+            }
+        )+
+
+        /// Tokens for every peripheral channel
+        #[allow(missing_docs)]
+        pub struct PclkTokens {
+            $(
+                $(#[$attr])*
+                pub $field: PclkToken<$Id>,
+            )+
+        }
+
+        impl PclkTokens {
+            /// Create the set of tokens
+            ///
+            /// # Safety
+            ///
+            /// All of the tokens are singletons, so this function must only be
+            /// called once.
+            #[inline]
+            pub(super) unsafe fn new() -> Self {
+                PclkTokens {
+                    $(
+                        $(#[$attr])*
+                        $field: PclkToken::new(),
+                    )+
+                }
+            }
+        }
+    };
+}
+
+pclk_ids!(
+    (Dfll48, dfll48, DFLL48),
+    (Dpll0, dpll0, FDPLL0),
+    (Dpll1, dpll1, FDPLL1),
+    (Slow32k, slow32k, SLOW_32K),
+    (Eic, eic, EIC),
+    (FreqmMsr, freqm_msr, FREQM_MSR),
+    (FreqmRef, freqm_ref, FREQM_REF),
+    (Sercom0, sercom0, SERCOM0_CORE),
+    (Sercom1, sercom1, SERCOM1_CORE),
+    (Tc0Tc1, tc0_tc1, TC0_TC1),
+    (Usb, usb, USB),
+    (EvSys0, evsys0, EVSYS0),
+    (EvSys1, evsys1, EVSYS1),
+    (EvSys2, evsys2, EVSYS2),
+    (EvSys3, evsys3, EVSYS3),
+    (EvSys4, evsys4, EVSYS4),
+    (EvSys5, evsys5, EVSYS5),
+    (EvSys6, evsys6, EVSYS6),
+    (EvSys7, evsys7, EVSYS7),
+    (EvSys8, evsys8, EVSYS8),
+    (EvSys9, evsys9, EVSYS9),
+    (EvSys10, evsys10, EVSYS10),
+    (EvSys11, evsys11, EVSYS11),
+    (Sercom2, sercom2, SERCOM2_CORE),
+    (Sercom3, sercom3, SERCOM3_CORE),
+    (Tcc0Tcc1, tcc0_tcc1, TCC0_TCC1),
+    (Tc2Tc3, tc2_tc3, TC2_TC3),
+    (Can0, can0, CAN0),
+    (Can1, can1, CAN1),
+    (Tcc2Tcc3, tcc2_tcc3, TCC2_TCC3),
+    (Tc4Tc5, tc4_tc5, TC4_TC5),
+    (PDec, pdec, PDEC),
+    (Ac, ac, AC),
+    (Ccl, ccl, CCL),
+    (Sercom4, sercom4, SERCOM4_CORE),
+    (Sercom5, sercom5, SERCOM5_CORE),
+    #[cfg(feature = "min-samd51n")]
+    (Sercom6, sercom6, SERCOM6_CORE),
+    #[cfg(feature = "min-samd51n")]
+    (Sercom7, sercom7, SERCOM7_CORE),
+    (Tcc4, tcc4, TCC4),
+    (Tc6Tc7, tc6_tc7, TC6_TC7),
+    (Adc0, adc0, ADC0),
+    (Adc1, adc1, ADC1),
+    (Dac, dac, DAC),
+    (I2S0, i2s0, I2S0),
+    (I2S1, i2s1, I2S1),
+    (Sdhc0, sdhc0, SDHC0),
+    (Sdhc1, sdhc1, SDHC1),
+    (Cm4Trace, cm4_trace, CM4_TRACE),
+);
+
+//==============================================================================
+// Conversion to existing clock tokens
+//==============================================================================
+
+macro_rules! pclk_legacy {
+    (
+        $(
+            $(#[$attr:meta])*
+            ($Id:ident, $Legacy:ident),
+        )+
+    ) => {
+        $(
+            $(#[$attr])*
+            impl<G: GclkId> From<Pclk<$Id, G>> for crate::clock::$Legacy {
+                #[inline]
+                fn from(pclk: Pclk<$Id, G>) -> Self {
+                    crate::clock::$Legacy { freq: pclk.freq }
+                }
+            }
+        )+
+    };
+}
+
+pclk_legacy!(
+    (Eic, EicClock),
+    (FreqmMsr, FreqmMsrClock),
+    (FreqmRef, FreqmRefClock),
+    (Sercom0, Sercom0CoreClock),
+    (Sercom1, Sercom1CoreClock),
+    (Tc0Tc1, Tc0Tc1Clock),
+    (Usb, UsbClock),
+    (EvSys0, Evsys0Clock),
+    (EvSys1, Evsys1Clock),
+    (EvSys2, Evsys2Clock),
+    (EvSys3, Evsys3Clock),
+    (EvSys4, Evsys4Clock),
+    (EvSys5, Evsys5Clock),
+    (EvSys6, Evsys6Clock),
+    (EvSys7, Evsys7Clock),
+    (EvSys8, Evsys8Clock),
+    (EvSys9, Evsys9Clock),
+    (EvSys10, Evsys10Clock),
+    (EvSys11, Evsys11Clock),
+    (Sercom2, Sercom2CoreClock),
+    (Sercom3, Sercom3CoreClock),
+    (Tcc0Tcc1, Tcc0Tcc1Clock),
+    (Tc2Tc3, Tc2Tc3Clock),
+    (Can0, Can0Clock),
+    (Can1, Can1Clock),
+    (Tcc2Tcc3, Tcc2Tcc3Clock),
+    (Tc4Tc5, Tc4Tc5Clock),
+    (PDec, PdecClock),
+    (Ac, AcClock),
+    (Ccl, CclClock),
+    (Sercom4, Sercom4CoreClock),
+    (Sercom5, Sercom5CoreClock),
+    #[cfg(feature = "min-samd51n")]
+    (Sercom6, Sercom6CoreClock),
+    #[cfg(feature = "min-samd51n")]
+    (Sercom7, Sercom7CoreClock),
+    (Tcc4, Tcc4Clock),
+    (Tc6Tc7, Tc6Tc7Clock),
+    (Adc0, Adc0Clock),
+    (Adc1, Adc1Clock),
+    (Dac, DacClock),
+    (I2S0, I2S0Clock),
+    (I2S1, I2S1Clock),
+    (Sdhc0, Sdhc0Clock),
+    (Sdhc1, Sdhc1Clock),
+    (Cm4Trace, Cm4TraceClock),
+);
@@ -0,0 +1,305 @@
+//! # External multipurpose crystal oscillators
+//!
+//! XOSC0 uses the XIN0 and XOUT0 pins, PA14 and PA15, while XOSC1 uses the
+//! XIN1 and XOUT1 pins, PB22 and PB23. A crystal uses both pins, while an
+//! external clock only uses XIN. The pins are held by the [`Xosc`] until it
+//! is freed.
+
+use core::marker::PhantomData;
+
+use typenum::{Unsigned, U0};
+
+use crate::gpio::v2::{FloatingDisabled, Pin, PinId, PA14, PA15, PB22, PB23};
+use crate::target_device::gclk::genctrl::SRC_A;
+use crate::target_device::oscctrl::RegisterBlock;
+use crate::target_device::OSCCTRL;
+use crate::time::Hertz;
+use crate::typelevel::Sealed;
+
+use super::{Enabled, GclkId, GclkSource, GclkSourceId, Source};
+use crate::clock::cfd::{arm_xosc, Cfd, CfdOsc};
+
+//==============================================================================
+// XoscId
+//==============================================================================
+
+/// Type-level identifier of XOSC0 or XOSC1
+pub trait XoscId: Sealed {
+    /// Index of the XOSC
+    const NUM: usize;
+    /// XIN pin
+    type XIn: PinId;
+    /// XOUT pin
+    type XOut: PinId;
+}
+
+/// Type-level identifier of XOSC0
+pub enum Xosc0Id {}
+
+impl Sealed for Xosc0Id {}
+
+impl XoscId for Xosc0Id {
+    const NUM: usize = 0;
+    type XIn = PA14;
+    type XOut = PA15;
+}
+
+impl GclkSourceId for Xosc0Id {
+    const SRC: SRC_A = SRC_A::XOSC0;
+}
+
+/// Type-level identifier of XOSC1
+pub enum Xosc1Id {}
+
+impl Sealed for Xosc1Id {}
+
+impl XoscId for Xosc1Id {
+    const NUM: usize = 1;
+    type XIn = PB22;
+    type XOut = PB23;
+}
+
+impl GclkSourceId for Xosc1Id {
+    const SRC: SRC_A = SRC_A::XOSC1;
+}
+
+//==============================================================================
+// XoscToken
+//==============================================================================
+
+/// Singleton token for XOSC0 or XOSC1
+pub struct XoscToken<X: XoscId> {
+    id: PhantomData<X>,
+}
+
+impl<X: XoscId> XoscToken<X> {
+    /// Create a new token
+    ///
+    /// # Safety
+    ///
+    /// Each token is a singleton. There must never be more than one instance
+    /// of a token for a given [`XoscId`].
+    #[inline]
+    pub(super) unsafe fn new() -> Self {
+        XoscToken { id: PhantomData }
+    }
+
+    #[inline]
+    fn oscctrl(&self) -> &RegisterBlock {
+        // SAFETY: Each token only accesses its own XOSCCTRL register and the
+        // read-only STATUS register
+        unsafe { &*OSCCTRL::ptr() }
+    }
+
+    #[inline]
+    fn is_ready(&self) -> bool {
+        let status = self.oscctrl().status.read();
+        match X::NUM {
+            0 => status.xoscrdy0().bit_is_set(),
+            _ => status.xoscrdy1().bit_is_set(),
+        }
+    }
+}
+
+//==============================================================================
+// Xosc
+//==============================================================================
+
+/// An external multipurpose crystal oscillator
+pub struct Xosc<X: XoscId> {
+    token: XoscToken<X>,
+    xin: Pin<X::XIn, FloatingDisabled>,
+    xout: Option<Pin<X::XOut, FloatingDisabled>>,
+    freq: Hertz,
+    startup: u8,
+    on_demand: bool,
+    run_standby: bool,
+}
+
+/// Type alias for XOSC0
+pub type Xosc0 = Xosc<Xosc0Id>;
+
+/// Type alias for XOSC1
+pub type Xosc1 = Xosc<Xosc1Id>;
+
+/// Token and pins returned by [`Xosc::free`]
+pub type XoscParts<X> = (
+    XoscToken<X>,
+    Pin<<X as XoscId>::XIn, FloatingDisabled>,
+    Option<Pin<<X as XoscId>::XOut, FloatingDisabled>>,
+);
+
+impl<X: XoscId> Xosc<X> {
+    /// Create an [`Xosc`] for a crystal of the given frequency
+    ///
+    /// Crystals from 8 MHz to 48 MHz are supported.
+    #[inline]
+    pub fn from_crystal(
+        token: XoscToken<X>,
+        xin: impl Into<Pin<X::XIn, FloatingDisabled>>,
+        xout: impl Into<Pin<X::XOut, FloatingDisabled>>,
+        freq: impl Into<Hertz>,
+    ) -> Self {
+        Xosc {
+            token,
+            xin: xin.into(),
+            xout: Some(xout.into()),
+            freq: freq.into(),
+            startup: 0,
+            on_demand: false,
+            run_standby: false,
+        }
+    }
+
+    /// Create an [`Xosc`] for an external clock of the given frequency on XIN
+    ///
+    /// External clocks up to 48 MHz are supported.
+    #[inline]
+    pub fn from_clock(
+        token: XoscToken<X>,
+        xin: impl Into<Pin<X::XIn, FloatingDisabled>>,
+        freq: impl Into<Hertz>,
+    ) -> Self {
+        Xosc {
+            token,
+            xin: xin.into(),
+            xout: None,
+            freq: freq.into(),
+            startup: 0,
+            on_demand: false,
+            run_standby: false,
+        }
+    }
+
+    /// Set the STARTUP field
+    ///
+    /// The start-up time is `2^startup` cycles of the 32 kHz oscillator.
+    /// Values greater than 15 saturate.
+    #[inline]
+    pub fn startup(mut self, startup: u8) -> Self {
+        self.startup = startup.min(15);
+        self
+    }
+
+    /// Only run the oscillator when a peripheral requests it
+    #[inline]
+    pub fn on_demand(mut self, on_demand: bool) -> Self {
+        self.on_demand = on_demand;
+        self
+    }
+
+    /// Keep running the oscillator in standby sleep mode
+    #[inline]
+    pub fn run_standby(mut self, run_standby: bool) -> Self {
+        self.run_standby = run_standby;
+        self
+    }
+
+    /// Return the frequency of the oscillator
+    #[inline]
+    pub fn freq(&self) -> Hertz {
+        self.freq
+    }
+
+    /// Free the [`Xosc`] and return its token and pins
+    #[inline]
+    pub fn free(self) -> XoscParts<X> {
+        (self.token, self.xin, self.xout)
+    }
+
+    /// Enable the oscillator and wait for it to stabilize
+    ///
+    /// The IMULT and IPTAT values are taken from the XOSC table in the
+    /// electrical characteristics section of the datasheet.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the frequency is out of range.
+    pub fn enable(self) -> Enabled<Self, U0> {
+        let crystal = self.xout.is_some();
+        let freq = self.freq.0;
+        if crystal {
+            assert!(
+                (8_000_000..=48_000_000).contains(&freq),
+                "XOSC crystal frequency out of range"
+            );
+        } else {
+            assert!(freq <= 48_000_000, "XOSC clock frequency out of range");
+        }
+        let (imult, iptat) = match freq {
+            0..=8_000_000 => (3, 2),
+            8_000_001..=16_000_000 => (4, 3),
+            16_000_001..=24_000_000 => (5, 3),
+            _ => (6, 3),
+        };
+        self.token.oscctrl().xoscctrl[X::NUM].write(|w| {
+            unsafe {
+                w.imult().bits(imult);
+                w.iptat().bits(iptat);
+            }
+            w.startup().bits(self.startup);
+            w.xtalen().bit(crystal);
+            w.enalc().bit(crystal);
+            w.ondemand().bit(self.on_demand);
+            w.runstdby().bit(self.run_standby);
+            w.enable().set_bit()
+        });
+        if !self.on_demand {
+            while !self.token.is_ready() {}
+        }
+        Enabled::new(self)
+    }
+}
+
+impl<X: XoscId> Enabled<Xosc<X>, U0> {
+    /// Disable the oscillator
+    ///
+    /// The oscillator can only be disabled when it has no users.
+    #[inline]
+    pub fn disable(self) -> Xosc<X> {
+        let oscctrl = self.clock.token.oscctrl();
+        oscctrl.xoscctrl[X::NUM].modify(|_, w| w.enable().clear_bit());
+        self.clock
+    }
+}
+
+impl<X: XoscId, N: Unsigned> Enabled<Xosc<X>, N> {
+    /// Arm the clock failure detector
+    ///
+    /// The safe clock is the DFLL divided by `2^prescaler`. If `switch_back`
+    /// is set, the oscillator output returns to the crystal once it recovers.
+    /// See the [`cfd`](crate::clock::cfd) module for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `prescaler` is greater than 15.
+    #[inline]
+    pub fn enable_failure_detection(&mut self, prescaler: u8, switch_back: bool) -> Cfd {
+        arm_xosc(self.clock.token.oscctrl(), X::NUM, prescaler, switch_back);
+        match X::NUM {
+            0 => Cfd::new(CfdOsc::Xosc0),
+            _ => Cfd::new(CfdOsc::Xosc1),
+        }
+    }
+}
+
+impl<X, N> Source for Enabled<Xosc<X>, N>
+where
+    X: XoscId + GclkSourceId,
+    N: Unsigned,
+{
+    type Id = X;
+
+    #[inline]
+    fn freq(&self) -> Hertz {
+        self.clock.freq()
+    }
+}
+
+impl<G, X, N> GclkSource<G> for Enabled<Xosc<X>, N>
+where
+    G: GclkId,
+    X: XoscId + GclkSourceId,
+    N: Unsigned,
+{
+}
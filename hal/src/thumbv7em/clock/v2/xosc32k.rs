@@ -0,0 +1,174 @@
+//! # External 32 kHz crystal oscillator
+//!
+//! XOSC32K uses the XIN32 and XOUT32 pins, PA00 and PA01. A crystal uses both
+//! pins, while an external clock only uses XIN32. The pins are held by the
+//! [`Xosc32k`] until it is freed.
+
+use typenum::{Unsigned, U0};
+
+use crate::gpio::v2::{FloatingDisabled, Pin, PA00, PA01};
+use crate::target_device::gclk::genctrl::SRC_A;
+use crate::target_device::osc32kctrl::RegisterBlock;
+use crate::target_device::OSC32KCTRL;
+use crate::time::Hertz;
+use crate::typelevel::Sealed;
+
+use super::{Enabled, GclkId, GclkSource, GclkSourceId, Source};
+use crate::clock::cfd::{arm_xosc32k, Cfd, CfdOsc};
+use crate::clock::OSC32K_FREQ;
+
+/// Type-level identifier of XOSC32K
+pub enum Xosc32kId {}
+
+impl Sealed for Xosc32kId {}
+
+impl GclkSourceId for Xosc32kId {
+    const SRC: SRC_A = SRC_A::XOSC32K;
+}
+
+/// Singleton token for XOSC32K
+pub struct Xosc32kToken {
+    _private: (),
+}
+
+impl Xosc32kToken {
+    /// Create a new token
+    ///
+    /// # Safety
+    ///
+    /// The token is a singleton, so this function must only be called once.
+    #[inline]
+    pub(super) unsafe fn new() -> Self {
+        Xosc32kToken { _private: () }
+    }
+
+    #[inline]
+    fn osc32kctrl(&self) -> &RegisterBlock {
+        // SAFETY: The token only accesses the XOSC32K registers
+        unsafe { &*OSC32KCTRL::ptr() }
+    }
+}
+
+/// The external 32 kHz crystal oscillator
+pub struct Xosc32k {
+    token: Xosc32kToken,
+    xin32: Pin<PA00, FloatingDisabled>,
+    xout32: Option<Pin<PA01, FloatingDisabled>>,
+    on_demand: bool,
+    run_standby: bool,
+}
+
+impl Xosc32k {
+    /// Create an [`Xosc32k`] for a 32.768 kHz crystal
+    #[inline]
+    pub fn from_crystal(
+        token: Xosc32kToken,
+        xin32: impl Into<Pin<PA00, FloatingDisabled>>,
+        xout32: impl Into<Pin<PA01, FloatingDisabled>>,
+    ) -> Self {
+        Xosc32k {
+            token,
+            xin32: xin32.into(),
+            xout32: Some(xout32.into()),
+            on_demand: false,
+            run_standby: false,
+        }
+    }
+
+    /// Create an [`Xosc32k`] for an external 32.768 kHz clock on XIN32
+    #[inline]
+    pub fn from_clock(token: Xosc32kToken, xin32: impl Into<Pin<PA00, FloatingDisabled>>) -> Self {
+        Xosc32k {
+            token,
+            xin32: xin32.into(),
+            xout32: None,
+            on_demand: false,
+            run_standby: false,
+        }
+    }
+
+    /// Only run the oscillator when a peripheral requests it
+    #[inline]
+    pub fn on_demand(mut self, on_demand: bool) -> Self {
+        self.on_demand = on_demand;
+        self
+    }
+
+    /// Keep running the oscillator in standby sleep mode
+    #[inline]
+    pub fn run_standby(mut self, run_standby: bool) -> Self {
+        self.run_standby = run_standby;
+        self
+    }
+
+    /// Return the frequency of the oscillator
+    #[inline]
+    pub fn freq(&self) -> Hertz {
+        OSC32K_FREQ
+    }
+
+    /// Free the [`Xosc32k`] and return its token and pins
+    #[inline]
+    pub fn free(
+        self,
+    ) -> (
+        Xosc32kToken,
+        Pin<PA00, FloatingDisabled>,
+        Option<Pin<PA01, FloatingDisabled>>,
+    ) {
+        (self.token, self.xin32, self.xout32)
+    }
+
+    /// Enable the oscillator and wait for it to stabilize
+    pub fn enable(self) -> Enabled<Self, U0> {
+        let osc32kctrl = self.token.osc32kctrl();
+        osc32kctrl.xosc32k.write(|w| {
+            w.ondemand().bit(self.on_demand);
+            w.runstdby().bit(self.run_standby);
+            w.en32k().set_bit();
+            w.en1k().set_bit();
+            w.xtalen().bit(self.xout32.is_some());
+            w.cgm().xt();
+            w.enable().set_bit()
+        });
+        if !self.on_demand {
+            while osc32kctrl.status.read().xosc32krdy().bit_is_clear() {}
+        }
+        Enabled::new(self)
+    }
+}
+
+impl Enabled<Xosc32k, U0> {
+    /// Disable the oscillator
+    ///
+    /// The oscillator can only be disabled when it has no users.
+    #[inline]
+    pub fn disable(self) -> Xosc32k {
+        let osc32kctrl = self.clock.token.osc32kctrl();
+        osc32kctrl.xosc32k.modify(|_, w| w.enable().clear_bit());
+        self.clock
+    }
+}
+
+impl<N: Unsigned> Enabled<Xosc32k, N> {
+    /// Arm the clock failure detector
+    ///
+    /// The safe clock is OSCULP32K, divided by two if `divide_by_2` is set.
+    /// See the [`cfd`](crate::clock::cfd) module for details.
+    #[inline]
+    pub fn enable_failure_detection(&mut self, divide_by_2: bool) -> Cfd {
+        arm_xosc32k(self.clock.token.osc32kctrl(), divide_by_2);
+        Cfd::new(CfdOsc::Xosc32k)
+    }
+}
+
+impl<N: Unsigned> Source for Enabled<Xosc32k, N> {
+    type Id = Xosc32kId;
+
+    #[inline]
+    fn freq(&self) -> Hertz {
+        self.clock.freq()
+    }
+}
+
+impl<G: GclkId, N: Unsigned> GclkSource<G> for Enabled<Xosc32k, N> {}
@@ -0,0 +1,179 @@
+//! # Event system
+//!
+//! The EVSYS routes events from one peripheral, the generator, to another, the
+//! user, without involving the CPU. Each of the [`NUM_CHANNELS`] channels
+//! carries the events of one generator, and any number of users can listen to
+//! a channel.
+//!
+//! Generators and users are identified by the IDs listed in the EVSYS chapter
+//! of the datasheet. The ones used by this HAL are in the [`generator`] and
+//! [`user`] modules.
+//!
+//! Channels are configured with the asynchronous path, so events reach the
+//! user directly, without a GCLK_EVSYS clock, and also in Standby.
+//!
+//! ```no_run
+//! # use atsamd_hal::evsys::{generator, user, Evsys};
+//! # fn f(
+//! #     evsys: atsamd_hal::target_device::EVSYS,
+//! #     mclk: &mut atsamd_hal::target_device::MCLK,
+//! # ) {
+//! let mut evsys = Evsys::new(evsys, mclk);
+//! // Route EXTINT 4 to the TC2 event input on channel 0
+//! evsys.connect(0, generator::eic_extint(4), user::TC2);
+//! # }
+//! ```
+
+use crate::clock::bus::{self, ApbClk};
+use crate::eic::pin::ExternalInterruptID;
+use crate::target_device::{EVSYS, MCLK};
+
+/// Number of EVSYS channels
+pub const NUM_CHANNELS: u8 = 32;
+
+/// Event generator ID
+pub type GeneratorId = u8;
+
+/// Event user ID
+pub type UserId = u8;
+
+/// Event generator IDs
+pub mod generator {
+    use super::{ExternalInterruptID, GeneratorId};
+
+    /// EIC external interrupt 0
+    pub const EIC_EXTINT_0: GeneratorId = 0x12;
+
+    /// EIC external interrupt `n`
+    #[inline]
+    pub const fn eic_extint(n: ExternalInterruptID) -> GeneratorId {
+        EIC_EXTINT_0 + n as GeneratorId
+    }
+}
+
+/// Event user IDs
+pub mod user {
+    use super::UserId;
+
+    /// TCC0 event input 0
+    pub const TCC0_EV0: UserId = 0x11;
+    /// TCC0 event input 1
+    pub const TCC0_EV1: UserId = 0x12;
+    /// TCC0 match/capture 0 event input
+    pub const TCC0_MC0: UserId = 0x13;
+    /// TCC0 match/capture 1 event input
+    pub const TCC0_MC1: UserId = 0x14;
+    /// TCC0 match/capture 2 event input
+    pub const TCC0_MC2: UserId = 0x15;
+    /// TCC0 match/capture 3 event input
+    pub const TCC0_MC3: UserId = 0x16;
+    /// TCC0 match/capture 4 event input
+    pub const TCC0_MC4: UserId = 0x17;
+    /// TCC0 match/capture 5 event input
+    pub const TCC0_MC5: UserId = 0x18;
+    /// TCC1 event input 0
+    pub const TCC1_EV0: UserId = 0x19;
+    /// TCC1 event input 1
+    pub const TCC1_EV1: UserId = 0x1A;
+    /// TCC1 match/capture 0 event input
+    pub const TCC1_MC0: UserId = 0x1B;
+    /// TCC1 match/capture 1 event input
+    pub const TCC1_MC1: UserId = 0x1C;
+    /// TCC1 match/capture 2 event input
+    pub const TCC1_MC2: UserId = 0x1D;
+    /// TCC1 match/capture 3 event input
+    pub const TCC1_MC3: UserId = 0x1E;
+    /// TCC2 event input 0
+    pub const TCC2_EV0: UserId = 0x1F;
+    /// TCC2 event input 1
+    pub const TCC2_EV1: UserId = 0x20;
+    /// TCC2 match/capture 0 event input
+    pub const TCC2_MC0: UserId = 0x21;
+    /// TCC2 match/capture 1 event input
+    pub const TCC2_MC1: UserId = 0x22;
+    /// TCC2 match/capture 2 event input
+    pub const TCC2_MC2: UserId = 0x23;
+    /// TCC3 event input 0
+    pub const TCC3_EV0: UserId = 0x24;
+    /// TCC3 event input 1
+    pub const TCC3_EV1: UserId = 0x25;
+    /// TCC3 match/capture 0 event input
+    pub const TCC3_MC0: UserId = 0x26;
+    /// TCC3 match/capture 1 event input
+    pub const TCC3_MC1: UserId = 0x27;
+    /// TCC4 event input 0
+    pub const TCC4_EV0: UserId = 0x28;
+    /// TCC4 event input 1
+    pub const TCC4_EV1: UserId = 0x29;
+    /// TCC4 match/capture 0 event input
+    pub const TCC4_MC0: UserId = 0x2A;
+    /// TCC4 match/capture 1 event input
+    pub const TCC4_MC1: UserId = 0x2B;
+    /// TC0 event input
+    pub const TC0: UserId = 0x2C;
+    /// TC1 event input
+    pub const TC1: UserId = 0x2D;
+    /// TC2 event input
+    pub const TC2: UserId = 0x2E;
+    /// TC3 event input
+    pub const TC3: UserId = 0x2F;
+    /// TC4 event input
+    pub const TC4: UserId = 0x30;
+    /// TC5 event input
+    pub const TC5: UserId = 0x31;
+    /// TC6 event input
+    pub const TC6: UserId = 0x32;
+    /// TC7 event input
+    pub const TC7: UserId = 0x33;
+}
+
+/// The event system
+pub struct Evsys {
+    evsys: EVSYS,
+    apb_clk: ApbClk<bus::Evsys>,
+}
+
+impl Evsys {
+    /// Power on and reset the EVSYS
+    pub fn new(evsys: EVSYS, mclk: &mut MCLK) -> Self {
+        // SAFETY: We own the EVSYS, so no other token exists for its clock
+        let apb_clk = unsafe { ApbClk::enable(mclk) };
+        evsys.ctrla.write(|w| w.swrst().set_bit());
+        while evsys.ctrla.read().swrst().bit_is_set() {}
+        Self { evsys, apb_clk }
+    }
+
+    /// Route the events of `generator` to `user` through `channel`
+    ///
+    /// Any user previously connected to the channel stays connected.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel` is not less than [`NUM_CHANNELS`].
+    pub fn connect(&mut self, channel: u8, generator: GeneratorId, user: UserId) {
+        assert!(channel < NUM_CHANNELS, "EVSYS channel out of range");
+        self.evsys.channel[channel as usize].channel.write(|w| {
+            // SAFETY: Unknown generator IDs don't generate any event
+            unsafe { w.evgen().bits(generator) };
+            w.path().asynchronous();
+            w.edgsel().no_evt_output()
+        });
+        // The user is connected to the channel number plus one, zero
+        // disconnects it
+        self.evsys.user[user as usize].write(|w| unsafe { w.channel().bits(channel + 1) });
+    }
+
+    /// Disconnect `user` from its channel
+    #[inline]
+    pub fn disconnect(&mut self, user: UserId) {
+        self.evsys.user[user as usize].reset();
+    }
+
+    /// Reset the EVSYS, gate off its APB clock and return it
+    pub fn free(self) -> EVSYS {
+        self.evsys.ctrla.write(|w| w.swrst().set_bit());
+        while self.evsys.ctrla.read().swrst().bit_is_set() {}
+        self.apb_clk.disable();
+        self.evsys
+    }
+}
@@ -0,0 +1,179 @@
+//! # Frequency meter
+//!
+//! The FREQM peripheral counts the periods of a measured clock, GCLK_FREQM_MSR,
+//! during a fixed number of periods of a reference clock, GCLK_FREQM_REF. The
+//! measured frequency is then
+//!
+//! ```text
+//! f_msr = VALUE / REFNUM * f_ref
+//! ```
+//!
+//! [`Freqm`] owns both peripheral channel tokens, so it can route any GCLK
+//! generator to the measurement channel. To measure an oscillator, or an
+//! external signal on a GCLK_IO pin, configure a GCLK generator with it as
+//! the source and measure the generator.
+//!
+//! The reference frequency is taken from the [`FreqmRefClock`] token, so the
+//! result is only as accurate as the reference. A 32.768 kHz crystal is a good
+//! choice. The resolution of a measurement is `f_ref / REFNUM`.
+//!
+//! ```no_run
+//! # use atsamd_hal::clock::{GenericClockController, ClockGenId};
+//! # use atsamd_hal::freqm::Freqm;
+//! # fn f(
+//! #     clocks: &mut GenericClockController,
+//! #     freqm: atsamd_hal::target_device::FREQM,
+//! #     mclk: &mut atsamd_hal::target_device::MCLK,
+//! # ) {
+//! let gclk0 = clocks.gclk0();
+//! let gclk1 = clocks.gclk1();
+//! let msr = clocks.freq_m_msr(&gclk0).unwrap();
+//! let reference = clocks.freq_m_ref(&gclk1).unwrap();
+//! let mut freqm = Freqm::new(freqm, mclk, msr, reference);
+//! let freq = freqm.measure(ClockGenId::GCLK0).unwrap();
+//! # }
+//! ```
+
+use crate::clock::bus::{self, ApbClk};
+use crate::clock::{ClockGenId, ClockId, FreqmMsrClock, FreqmRefClock};
+use crate::target_device::{FREQM, GCLK, MCLK};
+use crate::time::Hertz;
+
+/// Errors that can occur during a measurement
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The 24-bit counter overflowed
+    ///
+    /// The measured clock is too fast for the number of reference periods.
+    /// Use a smaller REFNUM or a faster reference clock.
+    Overflow,
+}
+
+/// The frequency meter
+pub struct Freqm {
+    freqm: FREQM,
+    msr: FreqmMsrClock,
+    reference: FreqmRefClock,
+    apb_clk: ApbClk<bus::Freqm>,
+}
+
+impl Freqm {
+    /// Power on and enable the FREQM
+    ///
+    /// REFNUM defaults to its maximum value, 255.
+    pub fn new(
+        freqm: FREQM,
+        mclk: &mut MCLK,
+        msr: FreqmMsrClock,
+        reference: FreqmRefClock,
+    ) -> Self {
+        // SAFETY: We own the FREQM, so no other token exists for its clock
+        let apb_clk = unsafe { ApbClk::enable(mclk) };
+
+        freqm.ctrla.write(|w| w.swrst().set_bit());
+        while freqm.syncbusy.read().swrst().bit_is_set() {}
+
+        let mut freqm = Self {
+            freqm,
+            msr,
+            reference,
+            apb_clk,
+        };
+        freqm.set_refnum(255);
+        freqm
+    }
+
+    /// Set the number of reference clock periods of a measurement
+    ///
+    /// # Panics
+    ///
+    /// Panics if `refnum` is zero.
+    pub fn set_refnum(&mut self, refnum: u8) {
+        assert!(refnum != 0, "REFNUM must be non-zero");
+        self.enable(false);
+        self.freqm
+            .cfga
+            .write(|w| unsafe { w.refnum().bits(refnum) });
+        self.enable(true);
+    }
+
+    /// Returns the number of reference clock periods of a measurement
+    #[inline]
+    pub fn refnum(&self) -> u8 {
+        self.freqm.cfga.read().refnum().bits()
+    }
+
+    fn enable(&mut self, enable: bool) {
+        self.freqm.ctrla.write(|w| w.enable().bit(enable));
+        while self.freqm.syncbusy.read().enable().bit_is_set() {}
+    }
+
+    /// Route a GCLK generator to the measurement channel
+    fn select(&mut self, gclk: ClockGenId) {
+        // SAFETY: We own the FREQM_MSR token, so nothing else uses this
+        // peripheral channel
+        let gclk_regs = unsafe { &*GCLK::ptr() };
+        let pchctrl = &gclk_regs.pchctrl[u8::from(ClockId::FREQM_MSR) as usize];
+        pchctrl.modify(|_, w| w.chen().clear_bit());
+        while pchctrl.read().chen().bit_is_set() {}
+        pchctrl.write(|w| {
+            w.gen().variant(gclk);
+            w.chen().set_bit()
+        });
+        while pchctrl.read().chen().bit_is_clear() {}
+    }
+
+    /// Start measuring the given GCLK generator
+    ///
+    /// Any measurement in progress is restarted. Use [`Freqm::read`] to get
+    /// the result.
+    pub fn start(&mut self, gclk: ClockGenId) {
+        self.select(gclk);
+        self.freqm.status.write(|w| w.ovf().set_bit());
+        self.freqm.intflag.write(|w| w.done().set_bit());
+        self.freqm.ctrlb.write(|w| w.start().set_bit());
+    }
+
+    /// Read the result of the last measurement
+    pub fn read(&mut self) -> nb::Result<Hertz, Error> {
+        if self.freqm.intflag.read().done().bit_is_clear() {
+            return Err(nb::Error::WouldBlock);
+        }
+        if self.freqm.status.read().ovf().bit_is_set() {
+            return Err(nb::Error::Other(Error::Overflow));
+        }
+        let value = self.freqm.value.read().value().bits() as u64;
+        let freq = value * self.reference.freq().0 as u64 / self.refnum() as u64;
+        Ok(Hertz(freq as u32))
+    }
+
+    /// Measure the frequency of the given GCLK generator
+    ///
+    /// Blocks for `REFNUM` periods of the reference clock.
+    pub fn measure(&mut self, gclk: ClockGenId) -> Result<Hertz, Error> {
+        self.start(gclk);
+        nb::block!(self.read())
+    }
+
+    /// Enable the DONE interrupt
+    #[inline]
+    pub fn enable_interrupt(&mut self) {
+        self.freqm.intenset.write(|w| w.done().set_bit());
+    }
+
+    /// Disable the DONE interrupt
+    #[inline]
+    pub fn disable_interrupt(&mut self) {
+        self.freqm.intenclr.write(|w| w.done().set_bit());
+    }
+
+    /// Disable the FREQM, gate off its APB clock and return its resources
+    ///
+    /// The measurement channel is left connected to the last measured GCLK
+    /// generator.
+    pub fn free(mut self) -> (FREQM, FreqmMsrClock, FreqmRefClock) {
+        self.enable(false);
+        self.apb_clk.disable();
+        (self.freqm, self.msr, self.reference)
+    }
+}
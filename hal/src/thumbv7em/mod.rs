@@ -1,8 +1,13 @@
+pub mod backup;
 pub mod calibration;
 pub mod clock;
 pub mod eic;
+pub mod evsys;
+pub mod freqm;
 pub mod qspi;
 pub(crate) mod sercom;
+pub mod sleep;
+pub mod supc;
 pub mod timer;
 pub mod trng;
 
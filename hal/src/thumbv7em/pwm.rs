@@ -4,6 +4,7 @@ use crate::hal::{Pwm, PwmPin};
 use crate::time::Hertz;
 use crate::timer_params::TimerParams;
 
+use crate::target_device::tcc0;
 use crate::target_device::{MCLK, TC0, TC1, TC2, TC3, TCC0, TCC1, TCC2};
 #[cfg(feature = "min-samd51j")]
 use crate::target_device::{TC4, TC5, TCC3, TCC4};
@@ -209,6 +210,10 @@ pwm! {
 
 // Timer/Counter for Control Applications (TCCx)
 
+/// A compare channel of a TCC
+///
+/// TCC0 has 6 compare channels, TCC1 has 4, TCC2 has 3, and TCC3 and TCC4
+/// have 2.
 #[derive(Copy, Clone)]
 pub enum Channel {
     _0,
@@ -221,6 +226,235 @@ pub enum Channel {
     _7,
 }
 
+// TCC waveform extensions
+
+/// Waveform generation mode of a TCC
+///
+/// In the dual-slope modes, the counter counts up to PER and back down to
+/// zero, so the PWM frequency is half the single-slope frequency and the
+/// outputs are center-aligned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Waveform {
+    /// Single-slope PWM, the default
+    SingleSlope,
+    /// Dual-slope PWM, with the compare values updated at TOP
+    DualSlopeTop,
+    /// Dual-slope PWM, with the compare values updated at ZERO
+    DualSlopeBottom,
+    /// Dual-slope PWM, with the compare values updated at TOP and ZERO
+    DualSlopeBoth,
+    /// Dual-slope PWM, with CC0 for the up-count and CC1 for the down-count
+    /// of each output pair
+    DualSlopeCritical,
+}
+
+impl From<Waveform> for tcc0::wave::WAVEGEN_A {
+    fn from(waveform: Waveform) -> Self {
+        use tcc0::wave::WAVEGEN_A;
+        match waveform {
+            Waveform::SingleSlope => WAVEGEN_A::NPWM,
+            Waveform::DualSlopeTop => WAVEGEN_A::DSTOP,
+            Waveform::DualSlopeBottom => WAVEGEN_A::DSBOTTOM,
+            Waveform::DualSlopeBoth => WAVEGEN_A::DSBOTH,
+            Waveform::DualSlopeCritical => WAVEGEN_A::DSCRITICAL,
+        }
+    }
+}
+
+/// Routing of the compare channels to the waveform outputs, OTMX
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputMatrix {
+    /// WO\[n\] is driven by CC\[n % CC_NUM\], the default
+    Default,
+    /// WO\[n\] is driven by CC\[n % 2\]
+    Modulo2,
+    /// All outputs are driven by CC0
+    Cc0,
+    /// WO\[0\] is driven by CC0, the other outputs by CC1
+    Cc0Cc1,
+}
+
+/// A fault input of a TCC
+///
+/// The recoverable faults A and B are the match/capture event inputs MC0 and
+/// MC1. The non-recoverable faults 0 and 1 are the event inputs EV0 and EV1.
+/// Route an event to them with the EVSYS, using the `TCCx_MCx` and `TCCx_EVx`
+/// user IDs in [`evsys::user`](crate::evsys::user).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fault {
+    /// Recoverable fault A
+    A,
+    /// Recoverable fault B
+    B,
+    /// Non-recoverable fault 0
+    NonRecoverable0,
+    /// Non-recoverable fault 1
+    NonRecoverable1,
+}
+
+/// Source of a recoverable fault
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FaultSource {
+    /// The fault is active while the event is high
+    Normal,
+    /// The fault is active while the event is low
+    Inverted,
+    /// The fault is taken from the other recoverable fault input
+    Alternate,
+}
+
+/// Start of the blanking period of a recoverable fault
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FaultBlanking {
+    /// Blank from the start of the cycle
+    Start,
+    /// Blank from the rising edge of the output
+    Rise,
+    /// Blank from the falling edge of the output
+    Fall,
+    /// Blank from both edges of the output
+    Both,
+}
+
+/// Action taken on a recoverable fault
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FaultHalt {
+    /// Don't halt the outputs
+    None,
+    /// Halt the outputs while the fault is active
+    Hardware,
+    /// Halt the outputs until the fault is cleared with
+    /// [`clear_fault`](Tcc0Pwm::clear_fault)
+    Software,
+    /// Treat the fault as a non-recoverable fault
+    NonRecoverable,
+}
+
+/// Recoverable fault configuration
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecoverableFaultConfig {
+    source: FaultSource,
+    keep: bool,
+    qualify: bool,
+    blanking: FaultBlanking,
+    blank_prescaler: bool,
+    blank_cycles: u8,
+    restart: bool,
+    halt: FaultHalt,
+    filter: u8,
+}
+
+impl RecoverableFaultConfig {
+    /// Create a new configuration with the given source
+    ///
+    /// By default, the outputs are halted while the fault is active, without
+    /// blanking, qualification or filtering.
+    #[inline]
+    pub const fn new(source: FaultSource) -> Self {
+        Self {
+            source,
+            keep: false,
+            qualify: false,
+            blanking: FaultBlanking::Start,
+            blank_prescaler: false,
+            blank_cycles: 0,
+            restart: false,
+            halt: FaultHalt::Hardware,
+            filter: 0,
+        }
+    }
+
+    /// Keep the fault active until the end of the cycle
+    #[inline]
+    pub const fn keep(mut self, keep: bool) -> Self {
+        self.keep = keep;
+        self
+    }
+
+    /// Ignore the fault while the output of the matching channel is inactive
+    #[inline]
+    pub const fn qualify(mut self, qualify: bool) -> Self {
+        self.qualify = qualify;
+        self
+    }
+
+    /// Ignore the fault for `cycles` TCC clock cycles after `start`
+    ///
+    /// When `prescaler` is set, the blanking cycles are counted in units of
+    /// 64 TCC clock cycles.
+    #[inline]
+    pub const fn blanking(mut self, start: FaultBlanking, cycles: u8, prescaler: bool) -> Self {
+        self.blanking = start;
+        self.blank_cycles = cycles;
+        self.blank_prescaler = prescaler;
+        self
+    }
+
+    /// Restart the counter on a fault
+    #[inline]
+    pub const fn restart(mut self, restart: bool) -> Self {
+        self.restart = restart;
+        self
+    }
+
+    /// Set the action taken on a fault
+    #[inline]
+    pub const fn halt(mut self, halt: FaultHalt) -> Self {
+        self.halt = halt;
+        self
+    }
+
+    /// Only take a fault into account after it was active for `filter`
+    /// consecutive samples, from 0 to 15
+    ///
+    /// # Panics
+    ///
+    /// Panics if `filter` is greater than 15.
+    #[inline]
+    pub const fn filter(mut self, filter: u8) -> Self {
+        assert!(filter <= 0xF, "TCC fault filter out of range");
+        self.filter = filter;
+        self
+    }
+
+    /// Returns the value of the FCTRLA and FCTRLB registers
+    const fn bits(&self) -> u32 {
+        let source = match self.source {
+            FaultSource::Normal => 1,
+            FaultSource::Inverted => 2,
+            FaultSource::Alternate => 3,
+        };
+        let blank = match self.blanking {
+            FaultBlanking::Start => 0,
+            FaultBlanking::Rise => 1,
+            FaultBlanking::Fall => 2,
+            FaultBlanking::Both => 3,
+        };
+        let halt = match self.halt {
+            FaultHalt::None => 0,
+            FaultHalt::Hardware => 1,
+            FaultHalt::Software => 2,
+            FaultHalt::NonRecoverable => 3,
+        };
+        source
+            | (self.keep as u32) << 3
+            | (self.qualify as u32) << 4
+            | blank << 5
+            | (self.restart as u32) << 7
+            | halt << 8
+            | (self.blank_prescaler as u32) << 15
+            | (self.blank_cycles as u32) << 16
+            | (self.filter as u32) << 24
+    }
+}
+
+/// Errors that can occur when reading a TCC capture channel
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaptureError {
+    /// A new value was captured before the previous one was read
+    Overrun,
+}
+
 pub enum TCC0Pinout {
     Pa8(Pa8<PfF>),
     Pa9(Pa9<PfF>),
@@ -375,7 +609,7 @@ pub enum TCC4Pinout {
 }
 
 macro_rules! pwm_tcc {
-    ($($TYPE:ident: ($TCC:ident, $pinout:ident, $clock:ident, $apmask:ident, $apbits:ident, $wrapper:ident),)+) => {
+    ($($TYPE:ident: ($TCC:ident, $pinout:ident, $clock:ident, $apmask:ident, $apbits:ident, $wrapper:ident, $cc_num:literal),)+) => {
         $(
 
 pub struct $TYPE {
@@ -432,6 +666,311 @@ impl $TYPE {
     }
 }
 
+impl $TYPE {
+    /// Disable the TCC while `f` writes its enable-protected registers
+    fn reconfigure<R>(&mut self, f: impl FnOnce(&$TCC) -> R) -> R {
+        self.tcc.ctrla.modify(|_, w| w.enable().clear_bit());
+        while self.tcc.syncbusy.read().enable().bit_is_set() {}
+        let result = f(&self.tcc);
+        self.tcc.ctrla.modify(|_, w| w.enable().set_bit());
+        while self.tcc.syncbusy.read().enable().bit_is_set() {}
+        result
+    }
+
+    /// Select the waveform generation mode
+    pub fn set_waveform(&mut self, waveform: Waveform) {
+        self.reconfigure(|tcc| {
+            tcc.wave.modify(|_, w| w.wavegen().variant(waveform.into()));
+            while tcc.syncbusy.read().wave().bit_is_set() {}
+        });
+    }
+
+    /// Invert the polarity of the compare channels in `channels`, a bit mask
+    /// of the compare channels of the TCC
+    pub fn set_polarity(&mut self, channels: u8) {
+        let mask = (1 << $cc_num) - 1;
+        self.reconfigure(|tcc| {
+            // SAFETY: Only the POLx bits of existing channels are modified
+            tcc.wave.modify(|r, w| unsafe {
+                w.bits(r.bits() & !(mask << 16) | ((channels as u32) & mask) << 16)
+            });
+            while tcc.syncbusy.read().wave().bit_is_set() {}
+        });
+    }
+
+    /// Invert the waveform outputs in `outputs`, a bit mask of WO\[0\] to
+    /// WO\[7\]
+    pub fn set_output_inversion(&mut self, outputs: u8) {
+        self.reconfigure(|tcc| {
+            // SAFETY: Only the INVENx bits are modified
+            tcc.drvctrl.modify(|r, w| unsafe {
+                w.bits(r.bits() & !(0xFF << 16) | (outputs as u32) << 16)
+            });
+        });
+    }
+
+    /// Configure the recoverable fault `A` or `B`, and enable its event input
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fault` is not a recoverable fault.
+    pub fn configure_recoverable_fault(&mut self, fault: Fault, config: RecoverableFaultConfig) {
+        self.reconfigure(|tcc| match fault {
+            Fault::A => {
+                // SAFETY: The configuration only produces valid field values
+                tcc.fctrla.write(|w| unsafe { w.bits(config.bits()) });
+                tcc.evctrl.modify(|_, w| w.mcei0().set_bit());
+            }
+            Fault::B => {
+                // SAFETY: The configuration only produces valid field values
+                tcc.fctrlb.write(|w| unsafe { w.bits(config.bits()) });
+                tcc.evctrl.modify(|_, w| w.mcei1().set_bit());
+            }
+            _ => panic!("not a recoverable TCC fault"),
+        });
+    }
+
+    /// Enable the non-recoverable fault `0` or `1` on its event input
+    ///
+    /// When `invert` is set, the fault is active while the event is low. The
+    /// fault is only taken into account after it was active for `filter`
+    /// consecutive samples, from 0 to 15. On a fault, the outputs are driven
+    /// as set by [`set_fault_outputs`](Self::set_fault_outputs).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fault` is not a non-recoverable fault, or if `filter` is
+    /// greater than 15.
+    pub fn configure_non_recoverable_fault(&mut self, fault: Fault, invert: bool, filter: u8) {
+        assert!(filter <= 0xF, "TCC fault filter out of range");
+        self.reconfigure(|tcc| match fault {
+            Fault::NonRecoverable0 => {
+                tcc.evctrl.modify(|_, w| {
+                    w.evact0().fault();
+                    w.tcinv0().bit(invert);
+                    w.tcei0().set_bit()
+                });
+                // SAFETY: The filter value was checked
+                tcc.drvctrl.modify(|_, w| unsafe { w.filterval0().bits(filter) });
+            }
+            Fault::NonRecoverable1 => {
+                tcc.evctrl.modify(|_, w| {
+                    w.evact1().fault();
+                    w.tcinv1().bit(invert);
+                    w.tcei1().set_bit()
+                });
+                // SAFETY: The filter value was checked
+                tcc.drvctrl.modify(|_, w| unsafe { w.filterval1().bits(filter) });
+            }
+            _ => panic!("not a non-recoverable TCC fault"),
+        });
+    }
+
+    /// Set the state of the waveform outputs on a non-recoverable fault
+    ///
+    /// The outputs in `enable`, a bit mask of WO\[0\] to WO\[7\], are driven to
+    /// the matching bit of `value`. The other outputs are tri-stated.
+    pub fn set_fault_outputs(&mut self, enable: u8, value: u8) {
+        self.reconfigure(|tcc| {
+            // SAFETY: Only the NREx and NRVx bits are modified
+            tcc.drvctrl.modify(|r, w| unsafe {
+                w.bits(r.bits() & !0xFFFF | enable as u32 | (value as u32) << 8)
+            });
+        });
+    }
+
+    /// Returns `true` if `fault` is active or was latched
+    pub fn is_faulted(&self, fault: Fault) -> bool {
+        let status = self.tcc.status.read();
+        match fault {
+            Fault::A => status.faulta().bit_is_set(),
+            Fault::B => status.faultb().bit_is_set(),
+            Fault::NonRecoverable0 => status.fault0().bit_is_set(),
+            Fault::NonRecoverable1 => status.fault1().bit_is_set(),
+        }
+    }
+
+    /// Clear the state and interrupt flag of `fault`
+    ///
+    /// The outputs only resume once the fault input is inactive.
+    pub fn clear_fault(&mut self, fault: Fault) {
+        match fault {
+            Fault::A => {
+                self.tcc.status.write(|w| w.faulta().set_bit());
+                self.tcc.intflag.write(|w| w.faulta().set_bit());
+            }
+            Fault::B => {
+                self.tcc.status.write(|w| w.faultb().set_bit());
+                self.tcc.intflag.write(|w| w.faultb().set_bit());
+            }
+            Fault::NonRecoverable0 => {
+                self.tcc.status.write(|w| w.fault0().set_bit());
+                self.tcc.intflag.write(|w| w.fault0().set_bit());
+            }
+            Fault::NonRecoverable1 => {
+                self.tcc.status.write(|w| w.fault1().set_bit());
+                self.tcc.intflag.write(|w| w.fault1().set_bit());
+            }
+        }
+    }
+
+    /// Enable the interrupt of `fault`
+    pub fn enable_fault_interrupt(&mut self, fault: Fault) {
+        self.tcc.intenset.write(|w| match fault {
+            Fault::A => w.faulta().set_bit(),
+            Fault::B => w.faultb().set_bit(),
+            Fault::NonRecoverable0 => w.fault0().set_bit(),
+            Fault::NonRecoverable1 => w.fault1().set_bit(),
+        });
+    }
+
+    /// Disable the interrupt of `fault`
+    pub fn disable_fault_interrupt(&mut self, fault: Fault) {
+        self.tcc.intenclr.write(|w| match fault {
+            Fault::A => w.faulta().set_bit(),
+            Fault::B => w.faultb().set_bit(),
+            Fault::NonRecoverable0 => w.fault0().set_bit(),
+            Fault::NonRecoverable1 => w.fault1().set_bit(),
+        });
+    }
+}
+
+impl $TYPE {
+    /// Write the duty cycle of `channel` into its buffer, CCBUF
+    ///
+    /// The buffer is copied into the compare register at the next update
+    /// condition, so the current PWM cycle isn't disturbed. Use
+    /// [`lock_update`](Self::lock_update) to update several channels at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the TCC has no compare channel `channel`.
+    pub fn set_duty_buffered(&mut self, channel: Channel, duty: u32) {
+        let ccbuf = self.tcc.ccbuf();
+        ccbuf[Self::cc_index(channel)].write(|w| unsafe { w.ccbuf().bits(duty) });
+    }
+
+    /// Write the TOP value, and thus the maximum duty cycle, into the period
+    /// buffer, PERBUF
+    ///
+    /// Like [`set_duty_buffered`](Self::set_duty_buffered), the period changes
+    /// at the next update condition.
+    pub fn set_max_duty_buffered(&mut self, top: u32) {
+        self.tcc.perbuf().write(|w| unsafe { w.perbuf().bits(top) });
+    }
+
+    /// Lock the update of the compare and period registers from their
+    /// buffers
+    ///
+    /// The buffers can then be written one by one, and are all applied at the
+    /// first update condition after [`unlock_update`](Self::unlock_update).
+    pub fn lock_update(&mut self) {
+        self.tcc.ctrlbset.write(|w| w.lupd().set_bit());
+        while self.tcc.syncbusy.read().ctrlb().bit_is_set() {}
+    }
+
+    /// Unlock the update of the compare and period registers from their
+    /// buffers
+    pub fn unlock_update(&mut self) {
+        self.tcc.ctrlbclr.write(|w| w.lupd().set_bit());
+        while self.tcc.syncbusy.read().ctrlb().bit_is_set() {}
+    }
+
+    /// Copy the buffers into the compare and period registers now, without
+    /// waiting for the update condition
+    pub fn force_update(&mut self) {
+        self.tcc.ctrlbset.write(|w| w.cmd().update());
+        while self.tcc.syncbusy.read().ctrlb().bit_is_set() {}
+    }
+
+    /// Enable the circular buffers
+    ///
+    /// With a circular buffer, the register and its buffer are swapped at each
+    /// update condition instead of copying the buffer, so the output
+    /// alternates between two values without CPU intervention. `period`
+    /// enables it for PER, and `channels` for CC0 to CC3, as a bit mask.
+    pub fn set_circular_buffer(&mut self, period: bool, channels: u8) {
+        self.reconfigure(|tcc| {
+            tcc.wave.modify(|_, w| {
+                w.ciperen().bit(period);
+                w.ciccen0().bit(channels & 0b0001 != 0);
+                w.ciccen1().bit(channels & 0b0010 != 0);
+                w.ciccen2().bit(channels & 0b0100 != 0);
+                w.ciccen3().bit(channels & 0b1000 != 0)
+            });
+            while tcc.syncbusy.read().wave().bit_is_set() {}
+        });
+    }
+
+    /// Turn `channel` into a capture channel
+    ///
+    /// The counter value is captured into the compare register of the
+    /// channel on each event of the match/capture event input of the
+    /// channel. Route an event to it with the EVSYS, using the `TCCx_MCx`
+    /// user IDs in [`evsys::user`](crate::evsys::user).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the TCC has no compare channel `channel`.
+    pub fn enable_capture(&mut self, channel: Channel) {
+        let bit = Self::capture_bit(channel);
+        self.reconfigure(|tcc| {
+            // SAFETY: Only the CPTENx and MCEIx bits of the channel are set
+            tcc.ctrla.modify(|r, w| unsafe { w.bits(r.bits() | bit << 24) });
+            tcc.evctrl.modify(|r, w| unsafe { w.bits(r.bits() | bit << 16) });
+        });
+    }
+
+    /// Turn `channel` back into a compare channel
+    ///
+    /// # Panics
+    ///
+    /// Panics if the TCC has no compare channel `channel`.
+    pub fn disable_capture(&mut self, channel: Channel) {
+        let bit = Self::capture_bit(channel);
+        self.reconfigure(|tcc| {
+            // SAFETY: Only the CPTENx and MCEIx bits of the channel are cleared
+            tcc.ctrla.modify(|r, w| unsafe { w.bits(r.bits() & !(bit << 24)) });
+            tcc.evctrl.modify(|r, w| unsafe { w.bits(r.bits() & !(bit << 16)) });
+        });
+    }
+
+    /// Read the last value captured by `channel`
+    ///
+    /// Returns `WouldBlock` until a new value was captured.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the TCC has no compare channel `channel`.
+    pub fn read_capture(&mut self, channel: Channel) -> nb::Result<u32, CaptureError> {
+        let bit = Self::capture_bit(channel);
+        let flags = self.tcc.intflag.read().bits();
+        if flags & 1 << 3 != 0 {
+            self.tcc.intflag.write(|w| w.err().set_bit());
+            return Err(nb::Error::Other(CaptureError::Overrun));
+        }
+        if flags & bit << 16 == 0 {
+            return Err(nb::Error::WouldBlock);
+        }
+        let value = self.tcc.cc()[Self::cc_index(channel)].read().cc().bits();
+        // SAFETY: Only the MCx flag of the channel is cleared
+        self.tcc.intflag.write(|w| unsafe { w.bits(bit << 16) });
+        Ok(value)
+    }
+
+    /// Returns the bit of `channel` in the CPTENx, MCEIx and MCx fields
+    fn capture_bit(channel: Channel) -> u32 {
+        1 << Self::cc_index(channel)
+    }
+
+    /// Returns the index of `channel` in the CC and CCBUF registers
+    fn cc_index(channel: Channel) -> usize {
+        let channel = channel as usize;
+        assert!(channel < $cc_num, "TCC has no such compare channel");
+        channel
+    }
+}
+
 impl Pwm for $TYPE {
     type Channel = Channel;
     type Time = Hertz;
@@ -455,7 +994,7 @@ impl Pwm for $TYPE {
 
     fn get_duty(&self, channel: Self::Channel) -> Self::Duty {
         let cc = self.tcc.cc();
-        let duty = cc[channel as usize].read().cc().bits();
+        let duty = cc[Self::cc_index(channel)].read().cc().bits();
         duty
     }
 
@@ -466,7 +1005,7 @@ impl Pwm for $TYPE {
 
     fn set_duty(&mut self, channel: Self::Channel, duty: Self::Duty) {
         let cc = self.tcc.cc();
-        cc[channel as usize].write(|w| unsafe { w.cc().bits(duty) });
+        cc[Self::cc_index(channel)].write(|w| unsafe { w.cc().bits(duty) });
     }
 
     fn set_period<P>(&mut self, period: P)
@@ -502,13 +1041,93 @@ impl Pwm for $TYPE {
 }
 
 pwm_tcc! {
-    Tcc0Pwm: (TCC0, TCC0Pinout, Tcc0Tcc1Clock, apbbmask, tcc0_, TccPwm0Wrapper),
-    Tcc1Pwm: (TCC1, TCC1Pinout, Tcc0Tcc1Clock, apbbmask, tcc1_, TccPwm1Wrapper),
-    Tcc2Pwm: (TCC2, TCC2Pinout, Tcc2Tcc3Clock, apbcmask, tcc2_, TccPwm2Wrapper),
+    Tcc0Pwm: (TCC0, TCC0Pinout, Tcc0Tcc1Clock, apbbmask, tcc0_, TccPwm0Wrapper, 6),
+    Tcc1Pwm: (TCC1, TCC1Pinout, Tcc0Tcc1Clock, apbbmask, tcc1_, TccPwm1Wrapper, 4),
+    Tcc2Pwm: (TCC2, TCC2Pinout, Tcc2Tcc3Clock, apbcmask, tcc2_, TccPwm2Wrapper, 3),
 }
 
 #[cfg(feature = "min-samd51j")]
 pwm_tcc! {
-    Tcc3Pwm: (TCC3, TCC3Pinout, Tcc2Tcc3Clock, apbcmask, tcc3_, TccPwm3Wrapper),
-    Tcc4Pwm: (TCC4, TCC4Pinout, Tcc4Clock,     apbdmask, tcc4_, TccPwm4Wrapper),
+    Tcc3Pwm: (TCC3, TCC3Pinout, Tcc2Tcc3Clock, apbcmask, tcc3_, TccPwm3Wrapper, 2),
+    Tcc4Pwm: (TCC4, TCC4Pinout, Tcc4Clock,     apbdmask, tcc4_, TccPwm4Wrapper, 2),
+}
+
+/// Implements the waveform extensions that only TCC0 and TCC1 have: the
+/// dead-time generators, output swapping, the output matrix and the pattern
+/// generator
+macro_rules! pwm_tcc_wex {
+    ($($TYPE:ident,)+) => {
+        $(
+
+impl $TYPE {
+    /// Swap the low-side and high-side outputs of the dead-time generators in
+    /// `dti`, a bit mask of DTI0 to DTI3
+    pub fn set_swap(&mut self, dti: u8) {
+        self.reconfigure(|tcc| {
+            // SAFETY: Only the SWAPx bits are modified
+            tcc.wave.modify(|r, w| unsafe {
+                w.bits(r.bits() & !(0xF << 24) | ((dti & 0xF) as u32) << 24)
+            });
+            while tcc.syncbusy.read().wave().bit_is_set() {}
+        });
+    }
+
+    /// Insert dead time on the dead-time generators in `dti`, a bit mask of
+    /// DTI0 to DTI3
+    ///
+    /// DTIx turns WO\[x\] and WO\[x + 4\] into a complementary pair driven by
+    /// the same compare channel. `low` and `high` are the dead times, in TCC
+    /// clock cycles, inserted before the low-side and high-side outputs turn
+    /// on. With no dead time, the outputs are simply complementary.
+    pub fn set_dead_time(&mut self, dti: u8, low: u8, high: u8) {
+        self.reconfigure(|tcc| {
+            tcc.wexctrl.modify(|_, w| {
+                w.dtien0().bit(dti & 0b0001 != 0);
+                w.dtien1().bit(dti & 0b0010 != 0);
+                w.dtien2().bit(dti & 0b0100 != 0);
+                w.dtien3().bit(dti & 0b1000 != 0);
+                // SAFETY: Any dead time is valid
+                unsafe {
+                    w.dtls().bits(low);
+                    w.dths().bits(high)
+                }
+            });
+        });
+    }
+
+    /// Select the routing of the compare channels to the waveform outputs
+    pub fn set_output_matrix(&mut self, matrix: OutputMatrix) {
+        let otmx = match matrix {
+            OutputMatrix::Default => 0,
+            OutputMatrix::Modulo2 => 1,
+            OutputMatrix::Cc0 => 2,
+            OutputMatrix::Cc0Cc1 => 3,
+        };
+        self.reconfigure(|tcc| {
+            // SAFETY: All values of OTMX are valid
+            tcc.wexctrl.modify(|_, w| unsafe { w.otmx().bits(otmx) });
+        });
+    }
+
+    /// Set the waveform output pattern
+    ///
+    /// The outputs in `enable`, a bit mask of WO\[0\] to WO\[7\], are driven to
+    /// the matching bit of `value` instead of their waveform. The pattern is
+    /// buffered and takes effect at the start of the next cycle, which makes
+    /// it suitable for stepper and BLDC commutation.
+    pub fn set_pattern(&mut self, enable: u8, value: u8) {
+        // SAFETY: Any pattern is valid
+        self.tcc
+            .pattbuf
+            .write(|w| unsafe { w.bits(enable as u16 | (value as u16) << 8) });
+    }
+}
+
+        )+
+    };
+}
+
+pwm_tcc_wex! {
+    Tcc0Pwm,
+    Tcc1Pwm,
 }
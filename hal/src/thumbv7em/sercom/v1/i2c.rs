@@ -1,6 +1,7 @@
 // Note: section 7.2.3 shows which pins support I2C Hs mode
 
 use crate::clock;
+use crate::clock::bus::{self, ApbClk};
 use crate::hal::blocking::i2c::{Read, Write, WriteRead};
 use crate::target_device::sercom0::I2CM;
 use crate::target_device::{MCLK, SERCOM0, SERCOM1, SERCOM2, SERCOM3, SERCOM4, SERCOM5};
@@ -22,9 +23,8 @@ macro_rules! i2c {
                 $pad0:ident,
                 $pad1:ident,
                 $SERCOM:ident,
-                $powermask:ident,
-                $clock:ident,
-                $apmask:ident
+                $Sercom:ident,
+                $clock:ident
             ),
         )+
     ]) => {
@@ -37,6 +37,7 @@ pub struct $Type<$pad0, $pad1> {
     sda: $pad0,
     scl: $pad1,
     sercom: $SERCOM,
+    apb_clk: ApbClk<bus::$Sercom>,
 }
 
 impl<$pad0, $pad1> $Type<$pad0, $pad1> {
@@ -70,8 +71,8 @@ impl<$pad0, $pad1> $Type<$pad0, $pad1> {
         scl: $pad1,
     ) -> Self {
         // Power up the peripheral bus clock.
-        // safe because we're exclusively owning SERCOM
-        mclk.$apmask.modify(|_, w| w.$powermask().set_bit());
+        // SAFETY: We own the SERCOM, so no other token exists for its clock
+        let apb_clk = unsafe { ApbClk::enable(mclk) };
 
         unsafe {
             // reset the sercom instance
@@ -104,15 +105,39 @@ impl<$pad0, $pad1> $Type<$pad0, $pad1> {
             while sercom.i2cm().syncbusy.read().sysop().bit_is_set() {}
         }
 
-        Self { sda, scl, sercom }
+        Self {
+            sda,
+            scl,
+            sercom,
+            apb_clk,
+        }
     }
 
     /// Breaks the sercom device up into its constituent pins and the SERCOM
-    /// instance.  Does not make any changes to power management.
+    /// instance, and gates off the APB clock of the SERCOM.
     pub fn free(self) -> ($pad0, $pad1, $SERCOM) {
+        self.apb_clk.disable();
         (self.sda, self.scl, self.sercom)
     }
 
+    /// Run in standby mode
+    ///
+    /// When set, the I2C master keeps running while the chip is in standby.
+    /// This is disabled by default.
+    pub fn run_in_standby(&mut self, set: bool) {
+        let i2cm = self.i2cm();
+        i2cm.ctrla.modify(|_, w| w.enable().clear_bit());
+        while i2cm.syncbusy.read().enable().bit_is_set() {}
+        i2cm.ctrla.modify(|_, w| w.runstdby().bit(set));
+        i2cm.ctrla.modify(|_, w| w.enable().set_bit());
+        while i2cm.syncbusy.read().enable().bit_is_set() {}
+        // The bus state is unknown after enabling, so force it back to idle
+        unsafe {
+            i2cm.status.modify(|_, w| w.busstate().bits(BUS_STATE_IDLE));
+        }
+        while i2cm.syncbusy.read().sysop().bit_is_set() {}
+    }
+
     fn start_tx_write(&mut self, addr: u8) -> Result<(), I2CError> {
         loop {
             match self.i2cm().status.read().busstate().bits() {
@@ -322,54 +347,48 @@ i2c!([
             Sercom0Pad0,
             Sercom0Pad1,
             SERCOM0,
-            sercom0_,
-            Sercom0CoreClock,
-            apbamask
+            Sercom0,
+            Sercom0CoreClock
         ),
     I2CMaster1:
         (
             Sercom1Pad0,
             Sercom1Pad1,
             SERCOM1,
-            sercom1_,
-            Sercom1CoreClock,
-            apbamask
+            Sercom1,
+            Sercom1CoreClock
         ),
     I2CMaster2:
         (
             Sercom2Pad0,
             Sercom2Pad1,
             SERCOM2,
-            sercom2_,
-            Sercom2CoreClock,
-            apbbmask
+            Sercom2,
+            Sercom2CoreClock
         ),
     I2CMaster3:
         (
             Sercom3Pad0,
             Sercom3Pad1,
             SERCOM3,
-            sercom3_,
-            Sercom3CoreClock,
-            apbbmask
+            Sercom3,
+            Sercom3CoreClock
         ),
     I2CMaster4:
         (
             Sercom4Pad0,
             Sercom4Pad1,
             SERCOM4,
-            sercom4_,
-            Sercom4CoreClock,
-            apbdmask
+            Sercom4,
+            Sercom4CoreClock
         ),
     I2CMaster5:
         (
             Sercom5Pad0,
             Sercom5Pad1,
             SERCOM5,
-            sercom5_,
-            Sercom5CoreClock,
-            apbdmask
+            Sercom5,
+            Sercom5CoreClock
         ),
 ]);
 
@@ -380,18 +399,16 @@ i2c!([
             Sercom6Pad0,
             Sercom6Pad1,
             SERCOM6,
-            sercom6_,
-            Sercom6CoreClock,
-            apbdmask
+            Sercom6,
+            Sercom6CoreClock
         ),
     I2CMaster7:
         (
             Sercom7Pad0,
             Sercom7Pad1,
             SERCOM7,
-            sercom7_,
-            Sercom7CoreClock,
-            apbdmask
+            Sercom7,
+            Sercom7CoreClock
         ),
 ]);
 
@@ -1,4 +1,7 @@
 use crate::clock;
+use crate::clock::bus::{self, ApbClk};
+use crate::clock::scaling::{ClockDomain, Retime};
+use crate::clock::ClockId;
 use crate::hal::blocking::serial::{write::Default, Write};
 use crate::hal::serial;
 use crate::sercom::pads::*;
@@ -18,6 +21,27 @@ pub trait RxpoTxpo {
     fn rxpo_txpo(&self) -> (u8, u8);
 }
 
+/// Direction of the XCK clock signal for a USART in synchronous mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XckMode {
+    /// XCK is an output, generated by the internal baud rate generator
+    Master,
+    /// XCK is an input, driven by the remote device
+    Slave,
+}
+
+/// XCK clock polarity for a USART in synchronous mode
+///
+/// Selects the XCK edge on which TxD changes. RxD is always sampled on the
+/// opposite edge.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XckPolarity {
+    /// TxD changes on the rising XCK edge, RxD is sampled on the falling edge
+    RisingEdgeTx,
+    /// TxD changes on the falling XCK edge, RxD is sampled on the rising edge
+    FallingEdgeTx,
+}
+
 /// Define a UARTX type for the given Sercom.
 ///
 /// Also defines the valid "pad to uart function" mappings for this instance so
@@ -26,9 +50,7 @@ macro_rules! uart {
     ($Type:ident: (
         $Sercom:ident,
         $SERCOM:ident,
-        $powermask:ident,
         $clock:ident,
-        $apmask:ident,
         $int0: ident,
         $int1: ident,
         $int2: ident)
@@ -59,6 +81,26 @@ macro_rules! uart {
                 rts: RTS,
             }
 
+            /// A pad mapping configuration for the SERCOM in synchronous
+            /// (USRT) mode.
+            ///
+            /// This type can only be constructed using the From implementations
+            /// in this module, which are restricted to valid configurations.
+            ///
+            /// Defines which sercom pad is mapped to the RX, TX and XCK functions.
+            pub struct [<$Type SyncPadout>]<RX, TX, XCK> {
+                rx: RX,
+                tx: TX,
+                xck: XCK,
+            }
+
+            impl<RX, TX, XCK> [<$Type SyncPadout>]<RX, TX, XCK> {
+                /// Consumes the padout and returns the RX, TX and XCK pads
+                pub fn free(self) -> (RX, TX, XCK) {
+                    (self.rx, self.tx, self.xck)
+                }
+            }
+
             impl<RX, TX, RTS, CTS> [<$Type Padout>]<RX, TX, RTS, CTS> {
                 /// Splits the padout into transmit and receive halves
                 pub fn split(self) -> ([<$Type TxPadout>]<TX, RTS>, [<$Type RxPadout>]<RX, CTS>) {
@@ -167,6 +209,46 @@ macro_rules! uart {
         // todo we could support an RTS without a CTS
         // padout!((3, 3) => Pad3, Pad0, Pad2);
 
+
+        /// Define a From instance for a tuple of three SercomXPadX instances
+        /// that converts them into an UARTXSyncPadout instance.
+        ///
+        /// Also defines a RxpoTxpo instance for the constructed padout
+        /// instance.
+        macro_rules! sync_padout {
+            ($rxpo_txpo:expr => $pad0:ident, $pad1:ident, $pad2:ident) => {
+                $crate::paste::item! {
+                    /// Convert from a tuple of (RX, TX, XCK) to UARTXSyncPadout
+                    impl<PIN0, PIN1, PIN2> From<([<$Sercom $pad0>]<PIN0>, [<$Sercom $pad1>]<PIN1>, [<$Sercom $pad2>]<PIN2>)> for [<$Type SyncPadout>]<[<$Sercom $pad0>]<PIN0>, [<$Sercom $pad1>]<PIN1>, [<$Sercom $pad2>]<PIN2>>
+                    where
+                        PIN0: Map<$Sercom, $pad0>,
+                        PIN1: Map<$Sercom, $pad1>,
+                        PIN2: Map<$Sercom, $pad2>,
+                    {
+                        fn from(pads: ([<$Sercom $pad0>]<PIN0>, [<$Sercom $pad1>]<PIN1>, [<$Sercom $pad2>]<PIN2>)) -> [<$Type SyncPadout>]<[<$Sercom $pad0>]<PIN0>, [<$Sercom $pad1>]<PIN1>, [<$Sercom $pad2>]<PIN2>> {
+                            [<$Type SyncPadout>] { rx: pads.0, tx: pads.1, xck: pads.2 }
+                        }
+                    }
+
+                    impl<PIN0, PIN1, PIN2> RxpoTxpo for [<$Type SyncPadout>]<[<$Sercom $pad0>]<PIN0>, [<$Sercom $pad1>]<PIN1>, [<$Sercom $pad2>]<PIN2>>
+                    where
+                        PIN0: Map<$Sercom, $pad0>,
+                        PIN1: Map<$Sercom, $pad1>,
+                        PIN2: Map<$Sercom, $pad2>,
+                    {
+                        fn rxpo_txpo(&self) -> (u8, u8) {
+                            $rxpo_txpo
+                        }
+                    }
+                }
+            };
+        }
+
+        // In synchronous mode, XCK is always on PAD 1 and TX on PAD 0
+        // (rxpo_txpo) => (RX, TX, XCK)
+        sync_padout!((2, 0) => Pad2, Pad0, Pad1);
+        sync_padout!((3, 0) => Pad3, Pad0, Pad1);
+
         $crate::paste::item! {
             /// UARTX represents the corresponding SERCOMX instance
             /// configured to act in the role of a UART Master.
@@ -178,6 +260,7 @@ macro_rules! uart {
             pub struct $Type<RX, TX, RTS, CTS> {
                 padout: [<$Type Padout>]<RX, TX, RTS, CTS>,
                 sercom: $SERCOM,
+                apb_clk: ApbClk<bus::$Sercom>,
             }
 
             impl<RX, TX, RTS, CTS> $Type<RX, TX, RTS, CTS> {
@@ -191,7 +274,8 @@ macro_rules! uart {
                     [<$Type Padout>]<RX, TX, RTS, CTS>: RxpoTxpo {
                     let padout = padout.into();
 
-                    mclk.$apmask.modify(|_, w| w.$powermask().set_bit());
+                    // SAFETY: We own the SERCOM, so no other token exists for its clock
+                    let apb_clk = unsafe { ApbClk::enable(mclk) };
 
                     // Lots of union fields which require unsafe access
                     unsafe {
@@ -263,16 +347,23 @@ macro_rules! uart {
                     Self {
                         padout,
                         sercom,
+                        apb_clk,
                     }
                 }
 
                 pub fn free(self) -> ([<$Type Padout>]<RX, TX, RTS, CTS>, $SERCOM) {
+                    self.apb_clk.disable();
                     (self.padout, self.sercom)
                 }
 
                 /// Splits the UART into transmit and receive halves
+                ///
+                /// Either half can be dropped while the other one is still in
+                /// use, so the APB clock stays enabled until the halves are
+                /// joined again and the UART is freed.
                 pub fn split(self) -> ([<$Type Tx>]<TX, RTS>, [<$Type Rx>]<RX, CTS>) {
                     let (tx_pads, rx_pads) = self.padout.split();
+                    self.apb_clk.leak();
                     (
                         [<$Type Tx>] {
                             padout: tx_pads,
@@ -290,6 +381,9 @@ macro_rules! uart {
                     Self {
                         padout: [<$Type Padout>]::join(tx.padout, rx.padout),
                         sercom: tx.sercom,
+                        // SAFETY: The token was leaked by `split`, and both
+                        // halves are needed to recreate it
+                        apb_clk: unsafe { ApbClk::assume_enabled() },
                     }
                 }
 
@@ -318,6 +412,20 @@ macro_rules! uart {
                 pub fn flags(&self) -> crate::target_device::sercom0::usart_int::status::R {
                     self.usart().status.read()
                 }
+
+                /// Run in standby mode
+                ///
+                /// When set, the UART keeps running while the chip is in
+                /// standby, so received data can wake up the CPU. This is
+                /// enabled by default.
+                pub fn run_in_standby(&mut self, set: bool) {
+                    let usart = self.usart();
+                    usart.ctrla.modify(|_, w| w.enable().clear_bit());
+                    while usart.syncbusy.read().enable().bit_is_set() {}
+                    usart.ctrla.modify(|_, w| w.runstdby().bit(set));
+                    usart.ctrla.modify(|_, w| w.enable().set_bit());
+                    while usart.syncbusy.read().enable().bit_is_set() {}
+                }
             }
 
             /// The transmitting half of the corresponding UARTX instance (as returned by `UARTX::split`)
@@ -372,6 +480,20 @@ macro_rules! uart {
                 }
             }
 
+            /// The baud rate is preserved. The USART is briefly disabled while
+            /// the BAUD register is rewritten, so no transfer should be in
+            /// progress.
+            impl<RX, TX, RTS, CTS> Retime for $Type<RX, TX, RTS, CTS> {
+                fn clock_domain(&self) -> ClockDomain {
+                    ClockDomain::Peripheral(ClockId::[<$SERCOM _CORE>])
+                }
+
+                fn retime(&mut self, old: Hertz, new: Hertz) {
+                    let baud = self.usart().baud().read().baud().bits();
+                    write_baud(self.usart(), rescale_baud_value(baud, old.0, new.0));
+                }
+            }
+
             impl<RX, TX, RTS, CTS> serial::Write<u8> for $Type<RX, TX, RTS, CTS> {
                 type Error = ();
 
@@ -446,6 +568,215 @@ macro_rules! uart {
                     self.bwrite_all(s.as_bytes()).map_err(|_| fmt::Error)
                 }
             }
+
+            /// UARTXSync represents the corresponding SERCOMX instance
+            /// configured to act as a synchronous USART (USRT), with the
+            /// serial clock on the XCK pad.
+            /// Objects of this type implement the HAL `serial::Read`,
+            /// `serial::Write` traits.
+            ///
+            /// In [`XckMode::Master`] mode, the baud rate generator drives the
+            /// XCK pad. In [`XckMode::Slave`] mode, XCK is an input and the
+            /// baud rate is set by the remote device.
+            pub struct [<$Type Sync>]<RX, TX, XCK> {
+                padout: [<$Type SyncPadout>]<RX, TX, XCK>,
+                sercom: $SERCOM,
+                apb_clk: ApbClk<bus::$Sercom>,
+            }
+
+            impl<RX, TX, XCK> [<$Type Sync>]<RX, TX, XCK> {
+                /// Power on and configure SERCOMX to work as a synchronous
+                /// USART with the XCK pad as a clock output, running at the
+                /// specified frequency.
+                ///
+                /// You can use any tuple of three SercomXPadY instances for
+                /// which there exists a From implementation for
+                /// UARTXSyncPadout.
+                pub fn master<F: Into<Hertz>, T: Into<[<$Type SyncPadout>]<RX, TX, XCK>>>(
+                    clock: &clock::$clock,
+                    freq: F,
+                    sercom: $SERCOM,
+                    mclk: &mut MCLK,
+                    padout: T,
+                    polarity: XckPolarity,
+                ) -> Self where
+                    [<$Type SyncPadout>]<RX, TX, XCK>: RxpoTxpo {
+                    let baud = calculate_sync_baud_value(freq.into().0, clock.freq().0);
+                    Self::create(sercom, mclk, padout.into(), XckMode::Master, polarity, baud)
+                }
+
+                /// Power on and configure SERCOMX to work as a synchronous
+                /// USART clocked by an external signal on the XCK pad.
+                ///
+                /// The maximum XCK frequency is one quarter of the SERCOM core
+                /// clock frequency.
+                pub fn slave<T: Into<[<$Type SyncPadout>]<RX, TX, XCK>>>(
+                    sercom: $SERCOM,
+                    mclk: &mut MCLK,
+                    padout: T,
+                    polarity: XckPolarity,
+                ) -> Self where
+                    [<$Type SyncPadout>]<RX, TX, XCK>: RxpoTxpo {
+                    Self::create(sercom, mclk, padout.into(), XckMode::Slave, polarity, 0)
+                }
+
+                fn create(
+                    sercom: $SERCOM,
+                    mclk: &mut MCLK,
+                    padout: [<$Type SyncPadout>]<RX, TX, XCK>,
+                    mode: XckMode,
+                    polarity: XckPolarity,
+                    baud: u16,
+                ) -> Self where
+                    [<$Type SyncPadout>]<RX, TX, XCK>: RxpoTxpo {
+                    // SAFETY: We own the SERCOM, so no other token exists for its clock
+                    let apb_clk = unsafe { ApbClk::enable(mclk) };
+
+                    // Lots of union fields which require unsafe access
+                    unsafe {
+                        // Reset
+                        sercom.usart_int().ctrla.modify(|_, w| w.swrst().set_bit());
+                        while sercom.usart_int().syncbusy.read().swrst().bit_is_set()
+                            || sercom.usart_int().ctrla.read().swrst().bit_is_set() {
+                            // wait for sync of CTRLA.SWRST
+                        }
+
+                        // Unsafe b/c of direct call to bits on rxpo/txpo
+                        sercom.usart_int().ctrla.modify(|_, w| {
+                            w.dord().set_bit();
+
+                            let (rxpo, txpo) = padout.rxpo_txpo();
+                            w.rxpo().bits(rxpo);
+                            w.txpo().bits(txpo);
+
+                            w.form().bits(0); // 0 is no parity bits
+                            w.runstdby().set_bit(); // Run in standby
+                            w.cpol().bit(polarity == XckPolarity::FallingEdgeTx);
+
+                            match mode {
+                                XckMode::Master => w.mode().usart_int_clk(), // XCK is an output
+                                XckMode::Slave => w.mode().usart_ext_clk(), // XCK is an input
+                            };
+                            w.cmode().set_bit() // Synchronous mode
+                        });
+
+                        // The BAUD register is only used to generate XCK in
+                        // master mode
+                        sercom.usart_int().baud().modify(|_, w| {
+                            w.baud().bits(baud)
+                        });
+
+                        sercom.usart_int().ctrlb.modify(|_, w| {
+                            w.sbmode().clear_bit(); // 0 is one stop bit
+                            w.chsize().bits(0x0);
+                            w.txen().set_bit();
+                            w.rxen().set_bit()
+                        });
+
+                        while sercom.usart_int().syncbusy.read().ctrlb().bit_is_set() {}
+
+                        sercom.usart_int().ctrla.modify(|_, w| w.enable().set_bit());
+                        // wait for sync of ENABLE
+                        while sercom.usart_int().syncbusy.read().enable().bit_is_set() {}
+                    }
+
+                    Self {
+                        padout,
+                        sercom,
+                        apb_clk,
+                    }
+                }
+
+                pub fn free(self) -> ([<$Type SyncPadout>]<RX, TX, XCK>, $SERCOM) {
+                    self.apb_clk.disable();
+                    (self.padout, self.sercom)
+                }
+
+                fn usart(&self) -> &USART_INT {
+                    return &self.sercom.usart_int();
+                }
+
+                pub fn intenset<F>(&mut self, f: F)
+                where F: FnOnce(&mut crate::target_device::sercom0::usart_int::intenset::W)
+                {
+                    self.usart().intenset.write(|w| {
+                        f(w);
+                        w
+                    });
+                }
+
+                pub fn intenclr<F>(&mut self, f: F)
+                where F: FnOnce(&mut crate::target_device::sercom0::usart_int::intenclr::W)
+                {
+                    self.usart().intenclr.write(|w| {
+                        f(w);
+                        w
+                    });
+                }
+
+                pub fn flags(&self) -> crate::target_device::sercom0::usart_int::status::R {
+                    self.usart().status.read()
+                }
+
+                /// Run in standby mode
+                ///
+                /// When set, the UART keeps running while the chip is in
+                /// standby, so received data can wake up the CPU. This is
+                /// enabled by default.
+                pub fn run_in_standby(&mut self, set: bool) {
+                    let usart = self.usart();
+                    usart.ctrla.modify(|_, w| w.enable().clear_bit());
+                    while usart.syncbusy.read().enable().bit_is_set() {}
+                    usart.ctrla.modify(|_, w| w.runstdby().bit(set));
+                    usart.ctrla.modify(|_, w| w.enable().set_bit());
+                    while usart.syncbusy.read().enable().bit_is_set() {}
+                }
+            }
+
+            /// In [`XckMode::Master`] mode, the XCK frequency is preserved. The
+            /// USART is briefly disabled while the BAUD register is rewritten,
+            /// so no transfer should be in progress.
+            impl<RX, TX, XCK> Retime for [<$Type Sync>]<RX, TX, XCK> {
+                fn clock_domain(&self) -> ClockDomain {
+                    ClockDomain::Peripheral(ClockId::[<$SERCOM _CORE>])
+                }
+
+                fn retime(&mut self, old: Hertz, new: Hertz) {
+                    // In slave mode, XCK is driven by the remote device
+                    if self.usart().ctrla.read().mode().is_usart_int_clk() {
+                        let baud = self.usart().baud().read().baud().bits();
+                        write_baud(self.usart(), rescale_sync_baud_value(baud, old.0, new.0));
+                    }
+                }
+            }
+
+            impl<RX, TX, XCK> serial::Write<u8> for [<$Type Sync>]<RX, TX, XCK> {
+                type Error = ();
+
+                fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+                    [<$Type Tx>]::<TX, ()>::do_write(self.usart(), word)
+                }
+
+                fn flush(&mut self) -> nb::Result<(), Self::Error> {
+                    [<$Type Tx>]::<TX, ()>::do_flush(self.usart())
+                }
+            }
+
+            impl<RX, TX, XCK> serial::Read<u8> for [<$Type Sync>]<RX, TX, XCK> {
+                type Error = ();
+
+                fn read(&mut self) -> nb::Result<u8, Self::Error> {
+                    [<$Type Rx>]::<RX, ()>::do_read(self.usart())
+                }
+            }
+
+            impl<RX, TX, XCK> Default<u8> for [<$Type Sync>]<RX, TX, XCK> {}
+
+            impl<RX, TX, XCK> fmt::Write for [<$Type Sync>]<RX, TX, XCK> {
+                fn write_str(&mut self, s: &str) -> fmt::Result {
+                    self.bwrite_all(s.as_bytes()).map_err(|_| fmt::Error)
+                }
+            }
         }
     }
 }
@@ -455,9 +786,7 @@ uart!(
         (
             Sercom0,
             SERCOM0,
-            sercom0_,
             Sercom0CoreClock,
-            apbamask,
             SERCOM0_0,
             SERCOM0_1,
             SERCOM0_2
@@ -469,9 +798,7 @@ uart!(
         (
             Sercom1,
             SERCOM1,
-            sercom1_,
             Sercom1CoreClock,
-            apbamask,
             SERCOM1_0,
             SERCOM1_1,
             SERCOM1_2
@@ -483,9 +810,7 @@ uart!(
         (
             Sercom2,
             SERCOM2,
-            sercom2_,
             Sercom2CoreClock,
-            apbbmask,
             SERCOM2_0,
             SERCOM2_1,
             SERCOM2_2
@@ -497,9 +822,7 @@ uart!(
         (
             Sercom3,
             SERCOM3,
-            sercom3_,
             Sercom3CoreClock,
-            apbbmask,
             SERCOM3_0,
             SERCOM3_1,
             SERCOM3_2
@@ -511,9 +834,7 @@ uart!(
         (
             Sercom4,
             SERCOM4,
-            sercom4_,
             Sercom4CoreClock,
-            apbdmask,
             SERCOM4_0,
             SERCOM4_1,
             SERCOM4_2
@@ -525,9 +846,7 @@ uart!(
         (
             Sercom5,
             SERCOM5,
-            sercom5_,
             Sercom5CoreClock,
-            apbdmask,
             SERCOM5_0,
             SERCOM5_1,
             SERCOM5_2
@@ -540,9 +859,7 @@ uart!(
         (
             Sercom6,
             SERCOM6,
-            sercom6_,
             Sercom6CoreClock,
-            apbdmask,
             SERCOM6_0,
             SERCOM6_1,
             SERCOM6_2
@@ -555,9 +872,7 @@ uart!(
         (
             Sercom7,
             SERCOM7,
-            sercom7_,
             Sercom7CoreClock,
-            apbdmask,
             SERCOM7_0,
             SERCOM7_1,
             SERCOM7_2
@@ -574,3 +889,48 @@ fn calculate_baud_value(baudrate: u32, clk_freq: u32, n_samples: u8) -> u16 {
 
     baud_calculated as u16
 }
+
+/// Calculate the BAUD register value for synchronous mode
+///
+/// In synchronous mode, the XCK frequency is `clk_freq / (2 * (BAUD + 1))`.
+/// Requests outside the achievable range saturate at the extremes.
+fn calculate_sync_baud_value(baudrate: u32, clk_freq: u32) -> u16 {
+    let baud = (clk_freq / 2 / baudrate).saturating_sub(1);
+    if baud <= u16::MAX as u32 {
+        baud as u16
+    } else {
+        u16::MAX
+    }
+}
+
+/// Rescale an asynchronous BAUD register value to keep the same baud rate
+/// after the reference clock changes from `old` to `new`
+///
+/// In asynchronous mode with 16x oversampling, the baud rate is
+/// `clk_freq * (65536 - BAUD) / (16 * 65536)`.
+fn rescale_baud_value(baud: u16, old: u32, new: u32) -> u16 {
+    let scale = (65536 - baud as u64) * old as u64 / new as u64;
+    65536u64.saturating_sub(scale) as u16
+}
+
+/// Rescale a synchronous BAUD register value to keep the same XCK frequency
+/// after the reference clock changes from `old` to `new`
+fn rescale_sync_baud_value(baud: u16, old: u32, new: u32) -> u16 {
+    let div = (baud as u64 + 1) * new as u64 / old as u64;
+    div.saturating_sub(1).min(u16::MAX as u64) as u16
+}
+
+/// Write the enable-protected BAUD register
+///
+/// The USART is disabled while the register is written, then re-enabled if it
+/// was enabled before.
+fn write_baud(usart: &USART_INT, baud: u16) {
+    let enabled = usart.ctrla.read().enable().bit_is_set();
+    usart.ctrla.modify(|_, w| w.enable().clear_bit());
+    while usart.syncbusy.read().enable().bit_is_set() {}
+    usart.baud().write(|w| unsafe { w.baud().bits(baud) });
+    if enabled {
+        usart.ctrla.modify(|_, w| w.enable().set_bit());
+        while usart.syncbusy.read().enable().bit_is_set() {}
+    }
+}
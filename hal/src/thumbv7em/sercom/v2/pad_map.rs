@@ -12,6 +12,8 @@
 //! [`PinId`]: crate::gpio::v2::PinId
 //! [`PinMode`]: crate::gpio::v2::PinMode
 
+use paste::paste;
+
 use crate::gpio::v2::*;
 use crate::sercom::v2::pads::*;
 use crate::sercom::v2::*;
@@ -62,9 +64,18 @@ macro_rules! map {
     };
 }
 
+/// Value-level equivalent of a single [`Map`] implementation
+type DynMap = (DynPadNum, DynPinId, DynAlternate);
+
+/// Value-level equivalent of all [`Map`] implementations for one [`Sercom`]
+type DynMapTable = &'static [(DynIoSet, &'static [DynMap])];
+
 // If all four PadNums of an IoSet have the same configuration attribute,
 // then you can place a the attribute above the IoSet. Otherwise, each
 // PadNum must have its own configuration attribute.
+//
+// Alongside the Map implementations, each table also produces an equivalent
+// DynMapTable, which is used to validate DynPads at run-time.
 macro_rules! pad_table {
     (
         $Sercom:ident { $(
@@ -84,6 +95,22 @@ macro_rules! pad_table {
                 )+
             );
         )+
+        paste! {
+            const [<$Sercom:upper _MAP>]: DynMapTable = &[
+                $(
+                    $( #[$ioset_cfg] )?
+                    (
+                        DynIoSet::$IoSet,
+                        &[
+                            $(
+                                $( #[$padnum_cfg] )?
+                                (DynPadNum::$PadNum, $PinId::DYN, DynAlternate::$Cfg),
+                            )+
+                        ],
+                    ),
+                )+
+            ];
+        }
     };
 }
 
@@ -366,3 +393,60 @@ pad_table!(
         }
     }
 );
+
+//==============================================================================
+//  Run-time lookup
+//==============================================================================
+
+/// Look up the [`DynPinId`] and [`DynAlternate`] mode for a given SERCOM
+/// number, [`DynIoSet`] and [`DynPadNum`]
+///
+/// This is the value-level equivalent of the [`Map`] trait. It returns `None`
+/// if the combination does not exist.
+pub(crate) fn dyn_map(
+    sercom: usize,
+    ioset: DynIoSet,
+    pad: DynPadNum,
+) -> Option<(DynPinId, DynAlternate)> {
+    let table = match sercom {
+        0 => SERCOM0_MAP,
+        1 => SERCOM1_MAP,
+        2 => SERCOM2_MAP,
+        3 => SERCOM3_MAP,
+        4 => SERCOM4_MAP,
+        5 => SERCOM5_MAP,
+        #[cfg(feature = "min-samd51n")]
+        6 => SERCOM6_MAP,
+        #[cfg(feature = "min-samd51n")]
+        7 => SERCOM7_MAP,
+        _ => return None,
+    };
+    table
+        .iter()
+        .filter(|(i, _)| *i == ioset)
+        .flat_map(|(_, maps)| maps.iter())
+        .find(|(p, _, _)| *p == pad)
+        .map(|&(_, id, mode)| (id, mode))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dyn_map_matches_pad_table() {
+        let map = dyn_map(0, DynIoSet::IoSet1, DynPadNum::Pad0);
+        assert!(map == Some((PA08::DYN, DynAlternate::C)));
+        let map = dyn_map(1, DynIoSet::IoSet4, DynPadNum::Pad2);
+        assert!(map == Some((PA30::DYN, DynAlternate::D)));
+    }
+
+    #[test]
+    fn dyn_map_rejects_pads_outside_ioset() {
+        // PA08 is SERCOM0 PAD0 in IOSET1, but PAD0 of IOSET3 is PA04
+        let map = dyn_map(0, DynIoSet::IoSet3, DynPadNum::Pad0);
+        assert!(map == Some((PA04::DYN, DynAlternate::D)));
+        assert!(dyn_map(0, DynIoSet::IoSet5, DynPadNum::Pad0).is_none());
+        assert!(dyn_map(8, DynIoSet::IoSet1, DynPadNum::Pad0).is_none());
+    }
+}
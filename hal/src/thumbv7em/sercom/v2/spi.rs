@@ -123,7 +123,7 @@
 
 use core::convert::{TryFrom, TryInto};
 use core::marker::PhantomData;
-use core::mem::transmute;
+use core::mem::{transmute, ManuallyDrop};
 
 use bitflags::bitflags;
 use embedded_hal::blocking;
@@ -141,6 +141,7 @@ use pac::sercom0::spim::ctrla::{CPHA_A, CPOL_A, DIPO_A, DOPO_A, DORD_A, MODE_A};
 use pac::sercom0::RegisterBlock;
 use pac::MCLK;
 
+use crate::clock::bus::ApbClk;
 use crate::gpio::v2::{AnyPin, SpecificPin};
 use crate::sercom::v2::pads::{IoSet, Map, Pad0, Pad1, Pad2, Pad3, PadNum};
 use crate::sercom::v2::pads::{OptionalPad, Pad, SomePad};
@@ -738,10 +739,10 @@ pub trait Mode: Sealed {
 
     /// Configure the SPI operating mode
     ///
-    /// For maximum flexibility, this module chooses to always operate in 32-bit
-    /// extension mode. The LENGTH counter is used to control the number of byes
-    /// in each SPI transaction. Due to a hardware bug, ICSPACE must be at least
-    /// one. See the silicon errata for more details.
+    /// For maximum flexibility, this module chooses to operate in 32-bit
+    /// extension mode by default. The LENGTH counter is used to control the
+    /// number of byes in each SPI transaction. See [`Config::data_32bit`] for
+    /// the one exception.
     #[inline]
     fn configure(sercom: &RegisterBlock) -> () {
         sercom
@@ -752,10 +753,6 @@ pub trait Mode: Sealed {
             .spim()
             .ctrlb
             .modify(|_, w| w.mssen().bit(Self::MSSEN));
-        sercom.spim().ctrlc.write(|w| unsafe {
-            w.data32b().data_trans_32bit();
-            w.icspace().bits(1)
-        });
         while sercom.spim().syncbusy.read().ctrlb().bit_is_set() {}
     }
 }
@@ -803,9 +800,9 @@ impl MasterMode for MasterHWSS {}
 
 /// Type-level `enum` representing the SPI transaction length, in bytes
 ///
-/// As mentioned in the [`Mode`] documentation, this module chooses to always
-/// operate in 32-bit extension mode. The LENGTH counter is used to control the
-/// number of byes in each SPI transaction.
+/// As mentioned in the [`Mode`] documentation, this module chooses to operate
+/// in 32-bit extension mode by default. The LENGTH counter is used to control
+/// the number of byes in each SPI transaction.
 ///
 /// The SPI transaction length is represented in the type domain using
 /// [`Unsigned`] types from the [`typenum`] crate. The length can be set
@@ -825,8 +822,17 @@ pub trait Length: Sealed + Unsigned + 'static {
     type Word: 'static;
 
     /// Configure the `LENGTH` register and enable the `LENGTH` counter
+    ///
+    /// The `LENGTH` counter only operates in 32-bit extension mode, so this
+    /// also enables 32-bit `DATA` register access. It also raises ICSPACE to
+    /// the minimum required while the `LENGTH` counter is enabled.
     #[inline]
     fn configure(sercom: &RegisterBlock) -> () {
+        let spacing = sercom.spim().ctrlc.read().icspace().bits().max(MIN_ICSPACE);
+        sercom.spim().ctrlc.modify(|_, w| {
+            w.data32b().data_trans_32bit();
+            unsafe { w.icspace().bits(spacing) }
+        });
         sercom.spim().length.write(|w| unsafe {
             w.len().bits(Self::U8);
             w.lenen().set_bit()
@@ -947,6 +953,15 @@ impl TryFrom<Errors> for () {
 // Config
 //=============================================================================
 
+/// Minimum ICSPACE value when using the LENGTH counter
+///
+/// Due to a hardware bug, ICSPACE must be at least one when using the LENGTH
+/// counter. See the silicon errata for more details.
+const MIN_ICSPACE: u8 = 1;
+
+/// Maximum value of the six-bit ICSPACE field
+const MAX_ICSPACE: u8 = 0x3F;
+
 /// A configurable, disabled SPI peripheral
 ///
 /// This `struct` represents a configurable SPI peripheral in its disabled
@@ -969,6 +984,7 @@ where
 {
     sercom: P::Sercom,
     pads: P,
+    apb_clk: ApbClk<<P::Sercom as Sercom>::ApbClkId>,
     mode: PhantomData<M>,
     len: PhantomData<L>,
     freq: Hertz,
@@ -976,7 +992,12 @@ where
 
 impl<P: AnyPads> Config<P> {
     /// Create a new [`Config`] in the default configuration.
-    fn create(sercom: P::Sercom, pads: P, freq: impl Into<Hertz>) -> Self {
+    fn create(
+        sercom: P::Sercom,
+        pads: P,
+        apb_clk: ApbClk<<P::Sercom as Sercom>::ApbClkId>,
+        freq: impl Into<Hertz>,
+    ) -> Self {
         Self::swrst(&sercom);
         Master::configure(&sercom);
         P::configure(&sercom);
@@ -984,6 +1005,7 @@ impl<P: AnyPads> Config<P> {
         Self {
             sercom,
             pads,
+            apb_clk,
             mode: PhantomData,
             len: PhantomData,
             freq: freq.into(),
@@ -995,14 +1017,16 @@ impl<P: AnyPads> Config<P> {
     /// This function will enable the corresponding APB clock, reset the
     /// [`Sercom`] peripheral, and return a [`Config`] in the default
     /// configuration, [`Master`] [`Mode`] with [`Length`] [`U1`]. [`Config`]
-    /// takes ownership of the [`Sercom`] and [`Pads`].
+    /// takes ownership of the [`Sercom`] and [`Pads`]. The APB clock is gated
+    /// off again when the [`Config`] is freed or dropped.
     ///
     /// Users must configure GCLK manually. The `freq` parameter represents the
     /// GCLK frequency for this [`Sercom`] instance.
     #[inline]
-    pub fn new(mclk: &MCLK, mut sercom: P::Sercom, pads: P, freq: impl Into<Hertz>) -> Self {
-        sercom.enable_apb_clock(mclk);
-        Self::create(sercom, pads, freq)
+    pub fn new(mclk: &MCLK, sercom: P::Sercom, pads: P, freq: impl Into<Hertz>) -> Self {
+        // SAFETY: We own the SERCOM, so no other token exists for its clock
+        let apb_clk = unsafe { ApbClk::enable(mclk) };
+        Self::create(sercom, pads, apb_clk, freq)
     }
 }
 
@@ -1039,6 +1063,7 @@ where
         Config {
             sercom: self.sercom,
             pads: self.pads,
+            apb_clk: self.apb_clk,
             mode: PhantomData,
             len: PhantomData,
             freq: self.freq,
@@ -1049,7 +1074,7 @@ where
     /// default configuration.
     #[inline]
     pub fn reset(self) -> Config<P> {
-        Config::create(self.sercom, self.pads, self.freq)
+        Config::create(self.sercom, self.pads, self.apb_clk, self.freq)
     }
 
     /// Obtain a reference to the PAC `SERCOM` struct
@@ -1061,11 +1086,12 @@ where
         &self.sercom
     }
 
-    /// Consume the [`Config`], reset the peripheral, and return the [`Sercom`]
-    /// and [`Pads`]
+    /// Consume the [`Config`], reset the peripheral, gate off its APB clock,
+    /// and return the [`Sercom`] and [`Pads`]
     #[inline]
     pub fn free(self) -> (P::Sercom, P) {
         Self::swrst(&self.sercom);
+        self.apb_clk.disable();
         (self.sercom, self.pads)
     }
 
@@ -1175,6 +1201,79 @@ where
         self
     }
 
+    /// Return the current SPI mode (clock polarity & phase)
+    #[inline]
+    pub fn get_spi_mode(&self) -> spi::Mode {
+        let ctrla = self.sercom.spim().ctrla.read();
+        let polarity = if ctrla.cpol().is_idle_high() {
+            Polarity::IdleHigh
+        } else {
+            Polarity::IdleLow
+        };
+        let phase = if ctrla.cpha().is_trailing_edge() {
+            Phase::CaptureOnSecondTransition
+        } else {
+            Phase::CaptureOnFirstTransition
+        };
+        spi::Mode { polarity, phase }
+    }
+
+    /// Return the current baud rate
+    ///
+    /// The baud rate is calculated from the BAUD register and the stored GCLK
+    /// frequency, so it can be lower than the value passed to
+    /// [`Config::baud`]. Passing it back to [`Config::baud`] restores the same
+    /// BAUD register setting as long as the GCLK frequency is at least
+    /// `2 * (BAUD + 1) * (BAUD + 2)` Hz, which holds for any BAUD value with a
+    /// GCLK of 132 kHz or more. Below that, the baud rate can't be expressed
+    /// precisely enough in whole Hertz.
+    #[inline]
+    pub fn get_baud(&self) -> Hertz {
+        let baud = self.sercom.spim().baud.read().baud().bits() as u32;
+        Hertz(self.freq.0 / 2 / (baud + 1))
+    }
+
+    /// Set the inter-character spacing
+    ///
+    /// Insert `spacing` idle baud periods between each character. This can be
+    /// used to satisfy the minimum inter-word gap required by some ADCs and
+    /// DACs. The ICSPACE field is six bits wide, so values greater than 63
+    /// saturate. Due to a hardware bug, the spacing must be at least one while
+    /// the LENGTH counter is enabled, so a spacing of zero then saturates to
+    /// one. See the silicon errata for more details.
+    #[inline]
+    pub fn icspace(self, spacing: u8) -> Self {
+        let spim = self.sercom.spim();
+        let min = if spim.length.read().lenen().bit_is_set() {
+            MIN_ICSPACE
+        } else {
+            0
+        };
+        let spacing = spacing.clamp(min, MAX_ICSPACE);
+        spim.ctrlc
+            .modify(|_, w| unsafe { w.icspace().bits(spacing) });
+        self
+    }
+
+    /// Return the inter-character spacing, in baud periods
+    #[inline]
+    pub fn get_icspace(&self) -> u8 {
+        self.sercom.spim().ctrlc.read().icspace().bits()
+    }
+
+    /// Return `true` if the DATA register is accessed as a 32-bit word
+    ///
+    /// See [`Config::data_32bit`] for more details.
+    #[inline]
+    pub fn get_data_32bit(&self) -> bool {
+        self.sercom
+            .spim()
+            .ctrlc
+            .read()
+            .data32b()
+            .is_data_trans_32bit()
+    }
+
     /// Control the buffer overflow notification
     ///
     /// If set to true, an [`Error::Overflow`] will be issued as soon as an
@@ -1239,6 +1338,50 @@ where
     }
 }
 
+impl<P, M> Config<P, M, U1>
+where
+    P: AnyPads,
+    M: Mode,
+{
+    /// Enable or disable 32-bit extension mode
+    ///
+    /// By default, this module operates in 32-bit extension mode, where the
+    /// DATA register is accessed as a 32-bit word and the LENGTH counter
+    /// controls the number of bytes in each transaction. For [`Length`]s
+    /// greater than one, each access of the DATA register can carry up to four
+    /// bytes, which gives the highest throughput.
+    ///
+    /// With a [`Length`] of [`U1`], however, every DATA access carries a
+    /// single byte, and the hardware bug described in [`icspace`] forces at
+    /// least one idle baud period between bytes. Disabling 32-bit extension
+    /// mode switches to 8-bit character mode and turns the LENGTH counter off.
+    /// The inter-character spacing is then reset to zero, so consecutive
+    /// bytes are sent back-to-back, which gives the highest throughput for a
+    /// stream of single bytes. Non-zero spacing can still be set afterwards
+    /// with [`icspace`].
+    ///
+    /// Enabling 32-bit extension mode again restores the LENGTH counter and
+    /// raises the spacing to the minimum it requires. Changing the [`Length`]
+    /// also re-enables 32-bit extension mode.
+    ///
+    /// [`icspace`]: Config::icspace
+    #[inline]
+    pub fn data_32bit(self, enable: bool) -> Self {
+        if enable {
+            U1::configure(&self.sercom);
+        } else {
+            let spim = self.sercom.spim();
+            spim.ctrlc.modify(|_, w| unsafe {
+                w.data32b().data_trans_8bit();
+                w.icspace().bits(0)
+            });
+            spim.length.modify(|_, w| w.lenen().clear_bit());
+            while spim.syncbusy.read().length().bit_is_set() {}
+        }
+        self
+    }
+}
+
 impl<P, M> Config<P, M, DynLength>
 where
     P: AnyPads,
@@ -1433,8 +1576,8 @@ where
 /// An enabled SPI peripheral that can perform transactions
 ///
 /// As noted in the [`Mode`] and [`Length`] traits, this module chooses to
-/// always operate in 32-bit extension mode and uses the LENGTH counter to set
-/// the number of bytes in each transaction, from 1 to 255. In 32-bit extension
+/// operate in 32-bit extension mode by default and uses the LENGTH counter to
+/// set the number of bytes in each transaction, from 1 to 255. In 32-bit extension
 /// mode, transaction [`Length`]s of four bytes or fewer can be completed in a
 /// single read or write of the DATA register. Longer transactions require
 /// multiple reads or writes of the DATA register.
@@ -1528,11 +1671,16 @@ impl<C: ValidConfig> Spi<C> {
         self.config.as_mut().enable_peripheral(false);
 
         // Perform a bitwise copy of the old configuration. This will be used as default
-        // in case the call to update(self.config) panics. This should be safe
-        // as either one of self.config or old_config will be used, and Config
-        // does not deallocate when dropped.
-        let old_config = unsafe { core::ptr::read(&mut self.config as *const _) };
-        replace_with::replace_with(&mut self.config, || old_config, |c| update(c.into()).into());
+        // in case the call to update(self.config) panics. The copy is wrapped in
+        // ManuallyDrop, because the Config owns the APB clock token, which gates
+        // off the clock when dropped. It is only unwrapped on the panic path, so
+        // exactly one of self.config or old_config is ever dropped.
+        let old_config = ManuallyDrop::new(unsafe { core::ptr::read(&self.config) });
+        replace_with::replace_with(
+            &mut self.config,
+            || ManuallyDrop::into_inner(old_config),
+            |c| update(c.into()).into(),
+        );
 
         self.config.as_mut().enable_peripheral(true);
     }
@@ -1608,6 +1756,20 @@ impl<C: ValidConfig> Spi<C> {
         Ok(self.read_flags())
     }
 
+    /// Return the current SPI mode (clock polarity & phase)
+    #[inline]
+    pub fn get_spi_mode(&self) -> spi::Mode {
+        self.config.as_ref().get_spi_mode()
+    }
+
+    /// Return the current baud rate
+    ///
+    /// See [`Config::get_baud`] for more details.
+    #[inline]
+    pub fn get_baud(&self) -> Hertz {
+        self.config.as_ref().get_baud()
+    }
+
     /// Read from the DATA register
     ///
     /// Reading from the data register directly is `unsafe`, because it will
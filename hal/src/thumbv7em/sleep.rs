@@ -0,0 +1,196 @@
+//! # Sleep modes
+//!
+//! The PM puts the chip to sleep when the CPU executes WFI. The depth of sleep
+//! is selected by `PM.SLEEPCFG`:
+//!
+//! - [`SleepMode::Idle`] stops the CPU. Everything else keeps running.
+//! - [`SleepMode::Standby`] also stops all clocks, except those requested by
+//!   peripherals configured to run in standby. RAM is retained as configured
+//!   with [`set_standby_ram`].
+//! - [`SleepMode::Hibernate`] powers off the core domain, except for the
+//!   backup domain and RAM retained with [`set_hibernate_ram`].
+//! - [`SleepMode::Backup`] keeps only the backup domain powered, with the
+//!   backup RAM retained as configured with [`set_backup_ram`].
+//! - [`SleepMode::Off`] powers off everything.
+//!
+//! [`sleep`] returns the reason the chip woke up from Idle or Standby. The chip
+//! resets when it wakes from Hibernate or Backup, so call [`backup_exit`]
+//! early in `main` to find out why. The only way out of Off is a reset.
+//!
+//! ```no_run
+//! # use atsamd_hal::sleep::{self, RamRetention, SleepMode, WakeReason};
+//! # use atsamd_hal::target_device::{Interrupt, PM};
+//! # fn f(pm: &mut PM) {
+//! sleep::set_standby_ram(pm, RamRetention::Partial);
+//! match sleep::sleep(pm, SleepMode::Standby) {
+//!     WakeReason::Interrupt(irq) if irq == Interrupt::RTC as u16 => {
+//!         // Woken by the RTC
+//!     }
+//!     _ => {}
+//! }
+//! # }
+//! ```
+
+use cortex_m::peripheral::NVIC;
+
+use crate::target_device::pm::{bkupcfg, hibcfg, sleepcfg::SLEEPMODE_A, stdbycfg};
+use crate::target_device::{PM, RSTC};
+
+/// Number of NVIC pending registers in use
+const NVIC_WORDS: usize = 5;
+
+/// Sleep mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SleepMode {
+    Idle,
+    Standby,
+    Hibernate,
+    Backup,
+    Off,
+}
+
+impl From<SleepMode> for SLEEPMODE_A {
+    fn from(mode: SleepMode) -> Self {
+        match mode {
+            SleepMode::Idle => SLEEPMODE_A::IDLE,
+            SleepMode::Standby => SLEEPMODE_A::STANDBY,
+            SleepMode::Hibernate => SLEEPMODE_A::HIBERNATE,
+            SleepMode::Backup => SLEEPMODE_A::BACKUP,
+            SleepMode::Off => SLEEPMODE_A::OFF,
+        }
+    }
+}
+
+/// RAM retention during sleep
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RamRetention {
+    /// All of the RAM is retained
+    All,
+    /// Only the first 32 KiB of the system RAM, or the first 4 KiB of the
+    /// backup RAM, is retained
+    Partial,
+    /// No RAM is retained
+    Off,
+}
+
+/// Reason the chip woke up from Idle or Standby
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WakeReason {
+    /// An enabled interrupt is pending. Contains its IRQ number, which can be
+    /// compared with the PAC `Interrupt` values.
+    Interrupt(u16),
+    /// No enabled interrupt is pending, e.g. the chip woke up on an event
+    Unknown,
+}
+
+/// Reason the chip woke up from Hibernate or Backup
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BackupExit {
+    /// An RTC interrupt
+    Rtc,
+    /// The battery backup power switch switched back to main power
+    BatteryBackupPowerSwitch,
+    /// A wake-up from Hibernate, by a source other than the RTC
+    Hibernate,
+}
+
+/// Configure RAM retention in Standby
+pub fn set_standby_ram(pm: &mut PM, ram: RamRetention) {
+    use stdbycfg::RAMCFG_A;
+    let ram = match ram {
+        RamRetention::All => RAMCFG_A::RET,
+        RamRetention::Partial => RAMCFG_A::PARTIAL,
+        RamRetention::Off => RAMCFG_A::OFF,
+    };
+    pm.stdbycfg.modify(|_, w| w.ramcfg().variant(ram));
+}
+
+/// Configure retention of the system RAM and backup RAM in Hibernate
+pub fn set_hibernate_ram(pm: &mut PM, ram: RamRetention, backup_ram: RamRetention) {
+    use hibcfg::{BRAMCFG_A, RAMCFG_A};
+    let ram = match ram {
+        RamRetention::All => RAMCFG_A::RET,
+        RamRetention::Partial => RAMCFG_A::PARTIAL,
+        RamRetention::Off => RAMCFG_A::OFF,
+    };
+    let backup_ram = match backup_ram {
+        RamRetention::All => BRAMCFG_A::RET,
+        RamRetention::Partial => BRAMCFG_A::PARTIAL,
+        RamRetention::Off => BRAMCFG_A::OFF,
+    };
+    pm.hibcfg.write(|w| {
+        w.ramcfg().variant(ram);
+        w.bramcfg().variant(backup_ram)
+    });
+}
+
+/// Configure retention of the backup RAM in Backup
+pub fn set_backup_ram(pm: &mut PM, backup_ram: RamRetention) {
+    use bkupcfg::BRAMCFG_A;
+    let backup_ram = match backup_ram {
+        RamRetention::All => BRAMCFG_A::RET,
+        RamRetention::Partial => BRAMCFG_A::PARTIAL,
+        RamRetention::Off => BRAMCFG_A::OFF,
+    };
+    pm.bkupcfg.write(|w| w.bramcfg().variant(backup_ram));
+}
+
+/// Keep the I/O pins in their current state during Hibernate and Backup
+///
+/// After waking up, the pins stay frozen until this is disabled again.
+pub fn set_io_retention(pm: &mut PM, retain: bool) {
+    pm.ctrla.modify(|_, w| w.ioret().bit(retain));
+}
+
+/// Enter a sleep mode and return the reason the chip woke up
+///
+/// Interrupts are masked while the chip sleeps and the wake reason is read,
+/// so the interrupt that woke it up is only handled once this returns.
+///
+/// In Hibernate, Backup and Off, the chip resets when it wakes up, so this
+/// only returns if an enabled interrupt was already pending.
+pub fn sleep(pm: &mut PM, mode: SleepMode) -> WakeReason {
+    let mode = SLEEPMODE_A::from(mode);
+    pm.sleepcfg.write(|w| w.sleepmode().variant(mode));
+    // The new mode takes effect once it can be read back
+    while pm.sleepcfg.read().sleepmode().bits() != mode as u8 {}
+
+    cortex_m::interrupt::free(|_| {
+        cortex_m::asm::dsb();
+        cortex_m::asm::wfi();
+        pending_interrupt()
+    })
+}
+
+/// Find the lowest-numbered interrupt that is both enabled and pending
+fn pending_interrupt() -> WakeReason {
+    // SAFETY: Read-only access to the NVIC
+    let nvic = unsafe { &*NVIC::ptr() };
+    for i in 0..NVIC_WORDS {
+        let pending = nvic.ispr[i].read() & nvic.iser[i].read();
+        if pending != 0 {
+            return WakeReason::Interrupt((i * 32) as u16 + pending.trailing_zeros() as u16);
+        }
+    }
+    WakeReason::Unknown
+}
+
+/// Returns the reason the chip woke up from Hibernate or Backup
+///
+/// Returns `None` if the last reset was not a wake-up from Hibernate or
+/// Backup.
+pub fn backup_exit(rstc: &RSTC) -> Option<BackupExit> {
+    if rstc.rcause.read().backup().bit_is_clear() {
+        return None;
+    }
+    let exit = rstc.bkupexit.read();
+    if exit.rtc().bit_is_set() {
+        Some(BackupExit::Rtc)
+    } else if exit.bbps().bit_is_set() {
+        Some(BackupExit::BatteryBackupPowerSwitch)
+    } else if exit.hib().bit_is_set() {
+        Some(BackupExit::Hibernate)
+    } else {
+        None
+    }
+}
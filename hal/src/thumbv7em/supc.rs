@@ -0,0 +1,341 @@
+//! # Supply controller
+//!
+//! The SUPC manages the power supplies of the chip:
+//!
+//! - The 3.3V brown-out detector, BOD33, monitors VDD. When VDD drops below
+//!   the configured threshold, it can reset the chip, raise an interrupt or
+//!   put the chip into Backup. See [`Bod33Config`].
+//! - The main voltage regulator can be an LDO or a more efficient buck
+//!   converter, which needs an external inductor. See [`set_regulator`].
+//! - The internal voltage reference, VREF, can be routed to the ADC and DAC
+//!   at one of several levels. See [`VrefConfig`].
+//! - The backup domain can switch to VBAT, either when the BOD33 detects a
+//!   brown-out or unconditionally. See [`set_battery_backup`].
+//!
+//! ```no_run
+//! # use atsamd_hal::supc::{self, Bod33Action, Bod33Config, Regulator};
+//! # fn f(supc: &mut atsamd_hal::target_device::SUPC) {
+//! // Interrupt rather than reset on a brown-out
+//! let bod33 = Bod33Config::new(0x1C)
+//!     .hysteresis(2)
+//!     .action(Bod33Action::Interrupt);
+//! supc::configure_bod33(supc, bod33);
+//! supc::enable_bod33_interrupt(supc);
+//! supc::set_regulator(supc, Regulator::Buck);
+//! # }
+//! ```
+
+use crate::target_device::supc::{bbps, bod33, vref, vreg};
+use crate::target_device::SUPC;
+
+//==============================================================================
+// BOD33
+//==============================================================================
+
+/// Action taken when the BOD33 detects a brown-out
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bod33Action {
+    /// No action
+    None,
+    /// Reset the chip
+    Reset,
+    /// Raise the BOD33DET interrupt
+    Interrupt,
+    /// Put the chip into Backup
+    Backup,
+}
+
+/// BOD33 configuration
+///
+/// The threshold levels are raw register values. See the electrical
+/// characteristics in the datasheet for the matching voltages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bod33Config {
+    level: u8,
+    battery_level: u8,
+    hysteresis: u8,
+    action: Bod33Action,
+    run_standby: bool,
+    run_hibernate: bool,
+    run_backup: bool,
+}
+
+impl Bod33Config {
+    /// Create a new configuration with the threshold level for VDD
+    ///
+    /// By default, the BOD33 resets the chip, has no hysteresis and only runs
+    /// in active mode.
+    #[inline]
+    pub const fn new(level: u8) -> Self {
+        Self {
+            level,
+            battery_level: 0,
+            hysteresis: 0,
+            action: Bod33Action::Reset,
+            run_standby: false,
+            run_hibernate: false,
+            run_backup: false,
+        }
+    }
+
+    /// Set the threshold level for VBAT, used in Backup
+    #[inline]
+    pub const fn battery_level(mut self, level: u8) -> Self {
+        self.battery_level = level;
+        self
+    }
+
+    /// Set the hysteresis, from 0 to 15
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hysteresis` is greater than 15.
+    #[inline]
+    pub const fn hysteresis(mut self, hysteresis: u8) -> Self {
+        assert!(hysteresis <= 0xF, "BOD33 hysteresis out of range");
+        self.hysteresis = hysteresis;
+        self
+    }
+
+    /// Set the action taken on a brown-out
+    #[inline]
+    pub const fn action(mut self, action: Bod33Action) -> Self {
+        self.action = action;
+        self
+    }
+
+    /// Keep the BOD33 running in Standby
+    #[inline]
+    pub const fn run_standby(mut self, run_standby: bool) -> Self {
+        self.run_standby = run_standby;
+        self
+    }
+
+    /// Keep the BOD33 running in Hibernate
+    #[inline]
+    pub const fn run_hibernate(mut self, run_hibernate: bool) -> Self {
+        self.run_hibernate = run_hibernate;
+        self
+    }
+
+    /// Keep the BOD33 running in Backup, monitoring VBAT
+    #[inline]
+    pub const fn run_backup(mut self, run_backup: bool) -> Self {
+        self.run_backup = run_backup;
+        self
+    }
+}
+
+/// Disable the BOD33 and wait for the change to take effect
+fn bod33_disable(supc: &mut SUPC) {
+    supc.bod33.modify(|_, w| w.enable().clear_bit());
+    while supc.status.read().b33srdy().bit_is_clear() {}
+}
+
+/// Configure and enable the BOD33
+///
+/// The BOD33 is disabled while it is reconfigured, so this waits until it is
+/// ready again before returning.
+pub fn configure_bod33(supc: &mut SUPC, config: Bod33Config) {
+    use bod33::ACTION_A;
+    let action = match config.action {
+        Bod33Action::None => ACTION_A::NONE,
+        Bod33Action::Reset => ACTION_A::RESET,
+        Bod33Action::Interrupt => ACTION_A::INT,
+        Bod33Action::Backup => ACTION_A::BKUP,
+    };
+    bod33_disable(supc);
+    supc.bod33.modify(|_, w| {
+        // SAFETY: Any level is valid, and the hysteresis was checked by the
+        // config
+        unsafe {
+            w.level().bits(config.level);
+            w.vbatlevel().bits(config.battery_level);
+            w.hyst().bits(config.hysteresis);
+        }
+        w.action().variant(action);
+        w.runstdby().bit(config.run_standby);
+        w.runhib().bit(config.run_hibernate);
+        w.runbkup().bit(config.run_backup)
+    });
+    supc.bod33.modify(|_, w| w.enable().set_bit());
+    while supc.status.read().b33srdy().bit_is_clear() {}
+    while supc.status.read().bod33rdy().bit_is_clear() {}
+}
+
+/// Disable the BOD33
+pub fn disable_bod33(supc: &mut SUPC) {
+    bod33_disable(supc);
+}
+
+/// Returns `true` if VDD is currently below the BOD33 threshold
+#[inline]
+pub fn bod33_detected(supc: &SUPC) -> bool {
+    supc.status.read().bod33det().bit_is_set()
+}
+
+/// Enable the BOD33DET interrupt
+#[inline]
+pub fn enable_bod33_interrupt(supc: &mut SUPC) {
+    supc.intenset.write(|w| w.bod33det().set_bit());
+}
+
+/// Disable the BOD33DET interrupt
+#[inline]
+pub fn disable_bod33_interrupt(supc: &mut SUPC) {
+    supc.intenclr.write(|w| w.bod33det().set_bit());
+}
+
+/// Clear the BOD33DET interrupt flag
+#[inline]
+pub fn clear_bod33_interrupt(supc: &mut SUPC) {
+    supc.intflag.write(|w| w.bod33det().set_bit());
+}
+
+//==============================================================================
+// Voltage regulator
+//==============================================================================
+
+/// Main voltage regulator
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Regulator {
+    /// Linear regulator
+    Ldo,
+    /// Switching buck converter, which requires an external inductor
+    Buck,
+}
+
+/// Select the main voltage regulator and wait until it is ready
+pub fn set_regulator(supc: &mut SUPC, regulator: Regulator) {
+    let sel = match regulator {
+        Regulator::Ldo => vreg::SEL_A::LDO,
+        Regulator::Buck => vreg::SEL_A::BUCK,
+    };
+    supc.vreg.modify(|_, w| w.sel().variant(sel));
+    while supc.status.read().vregrdy().bit_is_clear() {}
+}
+
+/// Returns the selected main voltage regulator
+#[inline]
+pub fn regulator(supc: &SUPC) -> Regulator {
+    if supc.vreg.read().sel().is_buck() {
+        Regulator::Buck
+    } else {
+        Regulator::Ldo
+    }
+}
+
+//==============================================================================
+// VREF
+//==============================================================================
+
+/// Level of the internal voltage reference
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VrefLevel {
+    V1_0,
+    V1_1,
+    V1_2,
+    V1_25,
+    V2_0,
+    V2_2,
+    V2_4,
+    V2_5,
+}
+
+impl From<VrefLevel> for vref::SEL_A {
+    fn from(level: VrefLevel) -> Self {
+        match level {
+            VrefLevel::V1_0 => vref::SEL_A::_1V0,
+            VrefLevel::V1_1 => vref::SEL_A::_1V1,
+            VrefLevel::V1_2 => vref::SEL_A::_1V2,
+            VrefLevel::V1_25 => vref::SEL_A::_1V25,
+            VrefLevel::V2_0 => vref::SEL_A::_2V0,
+            VrefLevel::V2_2 => vref::SEL_A::_2V2,
+            VrefLevel::V2_4 => vref::SEL_A::_2V4,
+            VrefLevel::V2_5 => vref::SEL_A::_2V5,
+        }
+    }
+}
+
+/// VREF configuration
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VrefConfig {
+    level: VrefLevel,
+    output_enable: bool,
+    run_standby: bool,
+    on_demand: bool,
+}
+
+impl VrefConfig {
+    /// Create a new configuration with the given level
+    ///
+    /// By default, the reference is not routed to the ADC and DAC.
+    #[inline]
+    pub const fn new(level: VrefLevel) -> Self {
+        Self {
+            level,
+            output_enable: false,
+            run_standby: false,
+            on_demand: false,
+        }
+    }
+
+    /// Route the reference to the ADC and DAC
+    #[inline]
+    pub const fn output_enable(mut self, output_enable: bool) -> Self {
+        self.output_enable = output_enable;
+        self
+    }
+
+    /// Keep the reference running in Standby
+    #[inline]
+    pub const fn run_standby(mut self, run_standby: bool) -> Self {
+        self.run_standby = run_standby;
+        self
+    }
+
+    /// Only run the reference while a peripheral requests it
+    #[inline]
+    pub const fn on_demand(mut self, on_demand: bool) -> Self {
+        self.on_demand = on_demand;
+        self
+    }
+}
+
+/// Configure the internal voltage reference
+pub fn configure_vref(supc: &mut SUPC, config: VrefConfig) {
+    supc.vref.modify(|_, w| {
+        w.sel().variant(config.level.into());
+        w.vrefoe().bit(config.output_enable);
+        w.runstdby().bit(config.run_standby);
+        w.ondemand().bit(config.on_demand)
+    });
+}
+
+//==============================================================================
+// Battery backup power switch
+//==============================================================================
+
+/// When the backup domain switches to VBAT
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BatteryBackup {
+    /// Switch to VBAT when the BOD33 detects a brown-out on VDD
+    Bod33,
+    /// Always run the backup domain from VBAT
+    Forced,
+}
+
+/// Configure the battery backup power switch
+///
+/// When `wake` is set, the chip wakes up from Backup when the switch goes
+/// back to VDD.
+pub fn set_battery_backup(supc: &mut SUPC, mode: BatteryBackup, wake: bool) {
+    let conf = match mode {
+        BatteryBackup::Bod33 => bbps::CONF_A::BOD33,
+        BatteryBackup::Forced => bbps::CONF_A::FORCED,
+    };
+    supc.bbps.write(|w| {
+        w.conf().variant(conf);
+        w.wakeen().bit(wake)
+    });
+}
@@ -1,6 +1,6 @@
 //! Working with timer counter hardware
 use crate::hal::timer::{CountDown, Periodic};
-use crate::target_device::tc0::COUNT16;
+use crate::target_device::tc0::{COUNT16, COUNT32};
 #[allow(unused)]
 use crate::target_device::{MCLK, TC2, TC3};
 use crate::timer_params::TimerParams;
@@ -10,38 +10,74 @@ use crate::target_device::{TC4, TC5};
 use crate::timer_traits::InterruptDrivenTimer;
 
 use crate::clock;
-use crate::time::{Hertz, Nanoseconds};
+use crate::clock::bus::{self, ApbClk, ApbId};
+use crate::clock::scaling::{ClockDomain, Retime};
+use crate::clock::ClockId;
+use crate::time::{Hertz, Microseconds, Nanoseconds};
 use void::Void;
 
 use cortex_m::asm::delay as cycle_delay;
 
+pub mod capture;
+pub mod monotonic;
+
 // Note:
-// TC3 + TC4 can be paired to make a 32-bit counter
-// TC5 + TC6 can be paired to make a 32-bit counter
+// TC0 + TC1 can be paired to make a 32-bit counter
+// TC2 + TC3 can be paired to make a 32-bit counter
+// TC4 + TC5 can be paired to make a 32-bit counter
+// TC6 + TC7 can be paired to make a 32-bit counter
 
 /// A generic hardware timer counter.
-/// The counters are exposed in 16-bit mode only.
-/// The hardware allows configuring the 8-bit mode
-/// and pairing up some instances to run in 32-bit
-/// mode, but that functionality is not currently
-/// exposed by this hal implementation.
+/// The counters are exposed in 16-bit mode.
+/// The hardware allows configuring the 8-bit mode,
+/// but that functionality is not currently
+/// exposed by this hal implementation. Pairs of
+/// instances are exposed in 32-bit mode by
+/// [`TimerCounter32`].
 /// TimerCounter implements both the `Periodic` and
 /// the `CountDown` embedded_hal timer traits.
 /// Before a hardware timer can be used, it must first
 /// have a clock configured.
-pub struct TimerCounter<TC> {
+pub struct TimerCounter<TC: Count16> {
     freq: Hertz,
     tc: TC,
+    apb_clk: ApbClk<TC::ApbId>,
+    timeout: Option<Nanoseconds>,
+    run_standby: bool,
+    on_demand: bool,
+}
+
+impl<TC: Count16> TimerCounter<TC> {
+    /// Run in standby mode
+    ///
+    /// When set, the timer keeps counting while the chip is in standby. This
+    /// is enabled by default and takes effect the next time the timer is
+    /// started.
+    pub fn run_in_standby(&mut self, set: bool) {
+        self.run_standby = set;
+    }
+
+    /// Request the generic clock on demand
+    ///
+    /// When set, the timer only requests its generic clock while it needs it,
+    /// so the clock source can stop in standby. This is disabled by default
+    /// and takes effect the next time the timer is started.
+    pub fn on_demand(&mut self, set: bool) {
+        self.on_demand = set;
+    }
 }
 
 /// This is a helper trait to make it easier to make most of the
 /// TimerCounter impl generic.  It doesn't make too much sense to
 /// to try to implement this trait outside of this module.
 pub trait Count16 {
+    /// Bus clock ID of the TC
+    type ApbId: ApbId;
+
     fn count_16(&self) -> &COUNT16;
 }
 
-impl<TC> Periodic for TimerCounter<TC> {}
+impl<TC: Count16> Periodic for TimerCounter<TC> {}
 impl<TC> CountDown for TimerCounter<TC>
 where
     TC: Count16,
@@ -52,9 +88,8 @@ where
     where
         T: Into<Self::Time>,
     {
-        let params = TimerParams::new_us(timeout, self.freq.0);
-        let divider = params.divider;
-        let cycles = params.cycles;
+        let timeout = timeout.into();
+        self.timeout = Some(timeout);
         let count = self.tc.count_16();
 
         // Disable the timer while we reconfigure it
@@ -77,26 +112,15 @@ where
             w.oneshot().clear_bit()
         });
 
-        // Set TOP value for mfrq mode
-        count.cc[0].write(|w| unsafe { w.cc().bits(cycles as u16) });
+        self.set_period(timeout);
 
         // Enable Match Frequency Waveform generation
         count.wave.modify(|_, w| w.wavegen().mfrq());
 
         count.ctrla.modify(|_, w| {
-            match divider {
-                1 => w.prescaler().div1(),
-                2 => w.prescaler().div2(),
-                4 => w.prescaler().div4(),
-                8 => w.prescaler().div8(),
-                16 => w.prescaler().div16(),
-                64 => w.prescaler().div64(),
-                256 => w.prescaler().div256(),
-                1024 => w.prescaler().div1024(),
-                _ => unreachable!(),
-            };
-            w.enable().set_bit();
-            w.runstdby().set_bit()
+            w.runstdby().bit(self.run_standby);
+            w.ondemand().bit(self.on_demand);
+            w.enable().set_bit()
         });
     }
 
@@ -112,6 +136,53 @@ where
     }
 }
 
+impl<TC> TimerCounter<TC>
+where
+    TC: Count16,
+{
+    /// Write the TOP value and prescaler for `timeout` at the current clock
+    /// frequency
+    ///
+    /// The timer must be disabled.
+    fn set_period(&self, timeout: Nanoseconds) {
+        let params = TimerParams::new_us(timeout, self.freq.0);
+        let count = self.tc.count_16();
+
+        // Set TOP value for mfrq mode
+        count.cc[0].write(|w| unsafe { w.cc().bits(params.cycles as u16) });
+
+        count.ctrla.modify(|_, w| match params.divider {
+            1 => w.prescaler().div1(),
+            2 => w.prescaler().div2(),
+            4 => w.prescaler().div4(),
+            8 => w.prescaler().div8(),
+            16 => w.prescaler().div16(),
+            64 => w.prescaler().div64(),
+            256 => w.prescaler().div256(),
+            1024 => w.prescaler().div1024(),
+            _ => unreachable!(),
+        });
+    }
+
+    /// Recalculate the period of a running timer for a new clock frequency
+    ///
+    /// The counter restarts from zero. Interrupt settings are kept.
+    fn retime_period(&mut self, freq: Hertz) {
+        self.freq = freq;
+        let count = self.tc.count_16();
+        let timeout = match self.timeout {
+            Some(timeout) if count.ctrla.read().enable().bit_is_set() => timeout,
+            _ => return,
+        };
+        count.ctrla.modify(|_, w| w.enable().clear_bit());
+        while count.syncbusy.read().enable().bit_is_set() {}
+        self.set_period(timeout);
+        count.count.write(|w| unsafe { w.count().bits(0) });
+        while count.syncbusy.read().count().bit_is_set() {}
+        count.ctrla.modify(|_, w| w.enable().set_bit());
+    }
+}
+
 impl<TC> InterruptDrivenTimer for TimerCounter<TC>
 where
     TC: Count16,
@@ -133,12 +204,186 @@ where
     }
 }
 
+/// A 32-bit hardware timer counter, built from a pair of TCs.
+/// The even-numbered master TC counts the lower 16 bits and
+/// the odd-numbered slave TC the upper 16 bits. The slave
+/// only provides its registers to the master, and is owned by
+/// the pair until it is freed.
+/// Periods that fit in 32 bits run without a prescaler, at
+/// the full resolution of the clock, and timeouts of up to
+/// 2^32 microseconds are accepted.
+/// TimerCounter32 implements both the `Periodic` and
+/// the `CountDown` embedded_hal timer traits. It doesn't
+/// implement `InterruptDrivenTimer`, which takes its timeouts
+/// in nanoseconds, so use a [`TimerCounter`] for a
+/// `SleepingDelay`.
+pub struct TimerCounter32<TC: Count16, SLAVE: Count16> {
+    freq: Hertz,
+    tc: TC,
+    slave: SLAVE,
+    apb_clk: ApbClk<TC::ApbId>,
+    slave_apb_clk: ApbClk<SLAVE::ApbId>,
+    timeout: Option<Microseconds>,
+    run_standby: bool,
+    on_demand: bool,
+}
+
+impl<TC: Count16, SLAVE: Count16> TimerCounter32<TC, SLAVE> {
+    /// Run in standby mode
+    ///
+    /// When set, the timer keeps counting while the chip is in standby. This
+    /// is enabled by default and takes effect the next time the timer is
+    /// started.
+    pub fn run_in_standby(&mut self, set: bool) {
+        self.run_standby = set;
+    }
+
+    /// Request the generic clock on demand
+    ///
+    /// When set, the timer only requests its generic clock while it needs it,
+    /// so the clock source can stop in standby. This is disabled by default
+    /// and takes effect the next time the timer is started.
+    pub fn on_demand(&mut self, set: bool) {
+        self.on_demand = set;
+    }
+}
+
+/// The 32-bit counterpart of [`Count16`], implemented by the
+/// master TC of each pair.
+pub trait Count32: Count16 {
+    fn count_32(&self) -> &COUNT32;
+}
+
+impl<TC: Count16, SLAVE: Count16> Periodic for TimerCounter32<TC, SLAVE> {}
+impl<TC, SLAVE> CountDown for TimerCounter32<TC, SLAVE>
+where
+    TC: Count32,
+    SLAVE: Count16,
+{
+    type Time = Microseconds;
+
+    fn start<T>(&mut self, timeout: T)
+    where
+        T: Into<Self::Time>,
+    {
+        let timeout = timeout.into();
+        self.timeout = Some(timeout);
+        let count = self.tc.count_32();
+
+        // Disable the timer while we reconfigure it
+        count.ctrla.modify(|_, w| w.enable().clear_bit());
+        while count.syncbusy.read().enable().bit_is_set() {}
+
+        count.ctrla.write(|w| w.swrst().set_bit());
+        while count.syncbusy.read().swrst().bit_is_set() {}
+
+        // The CC registers are only 32 bits wide in 32-bit mode
+        count.ctrla.write(|w| w.mode().count32());
+
+        count.ctrlbset.write(|w| {
+            // Count up when the direction bit is zero
+            w.dir().clear_bit();
+            // Periodic
+            w.oneshot().clear_bit()
+        });
+
+        self.set_period(timeout);
+
+        // Enable Match Frequency Waveform generation
+        count.wave.modify(|_, w| w.wavegen().mfrq());
+
+        count.ctrla.modify(|_, w| {
+            w.runstdby().bit(self.run_standby);
+            w.ondemand().bit(self.on_demand);
+            w.enable().set_bit()
+        });
+    }
+
+    fn wait(&mut self) -> nb::Result<(), Void> {
+        let count = self.tc.count_32();
+        if count.intflag.read().ovf().bit_is_set() {
+            // Writing a 1 clears the flag
+            count.intflag.modify(|_, w| w.ovf().set_bit());
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
+impl<TC, SLAVE> TimerCounter32<TC, SLAVE>
+where
+    TC: Count32,
+    SLAVE: Count16,
+{
+    /// Write the TOP value and prescaler for `timeout` at the current clock
+    /// frequency
+    ///
+    /// The timer must be disabled.
+    fn set_period(&self, timeout: Microseconds) {
+        let params = TimerParams::new_us_32(timeout, self.freq.0);
+        let count = self.tc.count_32();
+
+        // Set TOP value for mfrq mode
+        count.cc[0].write(|w| unsafe { w.cc().bits(params.cycles) });
+
+        count.ctrla.modify(|_, w| match params.divider {
+            1 => w.prescaler().div1(),
+            2 => w.prescaler().div2(),
+            4 => w.prescaler().div4(),
+            8 => w.prescaler().div8(),
+            16 => w.prescaler().div16(),
+            64 => w.prescaler().div64(),
+            256 => w.prescaler().div256(),
+            1024 => w.prescaler().div1024(),
+            _ => unreachable!(),
+        });
+    }
+
+    /// Recalculate the period of a running timer for a new clock frequency
+    ///
+    /// The counter restarts from zero. Interrupt settings are kept.
+    fn retime_period(&mut self, freq: Hertz) {
+        self.freq = freq;
+        let count = self.tc.count_32();
+        let timeout = match self.timeout {
+            Some(timeout) if count.ctrla.read().enable().bit_is_set() => timeout,
+            _ => return,
+        };
+        count.ctrla.modify(|_, w| w.enable().clear_bit());
+        while count.syncbusy.read().enable().bit_is_set() {}
+        self.set_period(timeout);
+        count.count.write(|w| unsafe { w.count().bits(0) });
+        while count.syncbusy.read().count().bit_is_set() {}
+        count.ctrla.modify(|_, w| w.enable().set_bit());
+    }
+}
+
+impl<TC, SLAVE> TimerCounter32<TC, SLAVE>
+where
+    TC: Count32,
+    SLAVE: Count16,
+{
+    /// Enable the interrupt generation for this hardware timer.
+    /// The interrupt is raised by the master TC.
+    pub fn enable_interrupt(&mut self) {
+        self.tc.count_32().intenset.write(|w| w.ovf().set_bit());
+    }
+
+    /// Disables interrupt generation for this hardware timer.
+    pub fn disable_interrupt(&mut self) {
+        self.tc.count_32().intenclr.write(|w| w.ovf().set_bit());
+    }
+}
+
 macro_rules! tc {
-    ($($TYPE:ident: ($TC:ident, $mclk:ident, $clock:ident, $apmask:ident),)+) => {
+    ($($TYPE:ident: ($TC:ident, $mclk:ident, $clock:ident, $id:ident, $Tc:ident),)+) => {
         $(
 pub type $TYPE = TimerCounter<$TC>;
 
 impl Count16 for $TC {
+    type ApbId = bus::$Tc;
+
     fn count_16(&self) -> &COUNT16 {
         self.count16()
     }
@@ -153,8 +398,8 @@ impl TimerCounter<$TC>
     /// Note that some hardware timer instances share the same clock
     /// generator instance and thus will be clocked at the same rate.
     pub fn $mclk(clock: &clock::$clock, tc: $TC, mclk: &mut MCLK) -> Self {
-        // this is safe because we're constrained to just the tc3 bit
-        mclk.$apmask.modify(|_, w| w.$mclk().set_bit());
+        // SAFETY: We own the TC, so no other token exists for its clock
+        let apb_clk = unsafe { ApbClk::enable(mclk) };
         {
             let count = tc.count16();
 
@@ -165,23 +410,131 @@ impl TimerCounter<$TC>
         Self {
             freq: clock.freq(),
             tc,
+            apb_clk,
+            timeout: None,
+            run_standby: true,
+            on_demand: false,
         }
     }
+
+    /// Disable the timer counter, gate off its APB clock and return the TC
+    pub fn free(self) -> $TC {
+        let count = self.tc.count_16();
+        count.ctrla.modify(|_, w| w.enable().clear_bit());
+        while count.syncbusy.read().enable().bit_is_set() {}
+        self.apb_clk.disable();
+        self.tc
+    }
+}
+
+/// A running timer is reconfigured to keep its period at the new frequency
+impl Retime for TimerCounter<$TC> {
+    #[inline]
+    fn clock_domain(&self) -> ClockDomain {
+        ClockDomain::Peripheral(ClockId::$id)
+    }
+
+    #[inline]
+    fn retime(&mut self, _old: Hertz, new: Hertz) {
+        self.retime_period(new);
+    }
 }
         )+
     }
 }
 
 tc! {
-    TimerCounter2: (TC2, tc2_, Tc2Tc3Clock, apbbmask),
-    TimerCounter3: (TC3, tc3_, Tc2Tc3Clock, apbbmask),
+    TimerCounter2: (TC2, tc2_, Tc2Tc3Clock, TC2_TC3, Tc2),
+    TimerCounter3: (TC3, tc3_, Tc2Tc3Clock, TC2_TC3, Tc3),
 }
 
 // Only the G variants are missing these timers
 #[cfg(feature = "min-samd51j")]
 tc! {
-    TimerCounter4: (TC4, tc4_, Tc4Tc5Clock, apbcmask),
-    TimerCounter5: (TC5, tc5_, Tc4Tc5Clock, apbcmask),
+    TimerCounter4: (TC4, tc4_, Tc4Tc5Clock, TC4_TC5, Tc4),
+    TimerCounter5: (TC5, tc5_, Tc4Tc5Clock, TC4_TC5, Tc5),
+}
+
+macro_rules! tc32 {
+    ($($TYPE:ident: ($TC:ident, $SLAVE:ident, $mclk:ident, $clock:ident, $id:ident),)+) => {
+        $(
+pub type $TYPE = TimerCounter32<$TC, $SLAVE>;
+
+impl Count32 for $TC {
+    fn count_32(&self) -> &COUNT32 {
+        self.count32()
+    }
+}
+
+impl TimerCounter32<$TC, $SLAVE>
+{
+    /// Configure this pair of timer counter instances.
+    /// The clock is obtained from the `GenericClockController` instance
+    /// and its frequency impacts the resolution and maximum range of
+    /// the timeout values that can be passed to the `start` method.
+    /// Both instances of the pair share this clock.
+    pub fn $mclk(clock: &clock::$clock, tc: $TC, slave: $SLAVE, mclk: &mut MCLK) -> Self {
+        // SAFETY: We own both TCs, so no other tokens exist for their clocks
+        let (apb_clk, slave_apb_clk) = unsafe { (ApbClk::enable(mclk), ApbClk::enable(mclk)) };
+        {
+            let count = tc.count_32();
+
+            // Disable the timer while we reconfigure it
+            count.ctrla.modify(|_, w| w.enable().clear_bit());
+            while count.syncbusy.read().enable().bit_is_set() {}
+        }
+        Self {
+            freq: clock.freq(),
+            tc,
+            slave,
+            apb_clk,
+            slave_apb_clk,
+            timeout: None,
+            run_standby: true,
+            on_demand: false,
+        }
+    }
+
+    /// Disable the timer counter, gate off the APB clocks and return the
+    /// TCs
+    pub fn free(self) -> ($TC, $SLAVE) {
+        let count = self.tc.count_32();
+        count.ctrla.modify(|_, w| w.enable().clear_bit());
+        while count.syncbusy.read().enable().bit_is_set() {}
+        // Return the master to 16-bit mode, so the slave can be used on its
+        // own again
+        count.ctrla.write(|w| w.swrst().set_bit());
+        while count.syncbusy.read().swrst().bit_is_set() {}
+        self.apb_clk.disable();
+        self.slave_apb_clk.disable();
+        (self.tc, self.slave)
+    }
+}
+
+/// A running timer is reconfigured to keep its period at the new frequency
+impl Retime for TimerCounter32<$TC, $SLAVE> {
+    #[inline]
+    fn clock_domain(&self) -> ClockDomain {
+        ClockDomain::Peripheral(ClockId::$id)
+    }
+
+    #[inline]
+    fn retime(&mut self, _old: Hertz, new: Hertz) {
+        self.retime_period(new);
+    }
+}
+        )+
+    }
+}
+
+tc32! {
+    TimerCounter2_3: (TC2, TC3, tc2_tc3, Tc2Tc3Clock, TC2_TC3),
+}
+
+// Only the G variants are missing these timers
+#[cfg(feature = "min-samd51j")]
+tc32! {
+    TimerCounter4_5: (TC4, TC5, tc4_tc5, Tc4Tc5Clock, TC4_TC5),
 }
 
 #[derive(Clone, Copy)]
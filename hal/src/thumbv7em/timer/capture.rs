@@ -0,0 +1,491 @@
+//! # Input capture
+//!
+//! [`TcCapture`] measures the period and pulse width of an external signal
+//! with a TC in 16-bit mode. The signal is routed from an EIC pin to the TC
+//! event input through the EVSYS, and the TC captures on each edge:
+//!
+//! - In [`CaptureMode::PeriodPulseWidth`], the counter restarts on each rising
+//!   edge of the event, the period is captured into CC0 and the pulse width
+//!   into CC1.
+//! - In [`CaptureMode::PulseWidthPeriod`], the roles of CC0 and CC1 are
+//!   swapped.
+//!
+//! Configure the EIC pin with `Sense::HIGH` and enable its event output, so
+//! the event follows the pin level. `Sense::LOW` inverts the signal, which
+//! measures the low pulses instead.
+//!
+//! The period must fit in 16 bits, so choose the prescaler divider for the
+//! slowest signal to measure. For example, with a 48 MHz clock divided by 16,
+//! periods of up to 21.8 ms, or 45.8 Hz, can be measured with a resolution of
+//! 1/3 µs, which suits RC receivers running at 50 Hz.
+//!
+//! [`TcCapture32`] does the same with a pair of TCs in 32-bit mode, created
+//! from a [`TimerCounter32`]. The period only has to fit in 32 bits, so slow
+//! signals can be measured without a prescaler, at the full resolution of the
+//! clock.
+//!
+//! ```no_run
+//! # use atsamd_hal::eic::pin::{ExternalInterrupt, Sense};
+//! # use atsamd_hal::evsys::Evsys;
+//! # use atsamd_hal::timer::TimerCounter2;
+//! # use atsamd_hal::timer::capture::CaptureMode;
+//! # fn f(
+//! #     timer: TimerCounter2,
+//! #     evsys: &mut Evsys,
+//! #     pin: &atsamd_hal::gpio::Pa4<atsamd_hal::gpio::PfA>,
+//! # ) {
+//! let mut capture = timer.into_capture(CaptureMode::PeriodPulseWidth, 16);
+//! capture.connect_eic(evsys, 0, pin.id());
+//! let measurement = nb::block!(capture.read()).unwrap();
+//! let freq = measurement.frequency();
+//! let duty = measurement.duty_cycle();
+//! # }
+//! ```
+
+use super::{Count16, Count32, TimerCounter, TimerCounter32};
+use crate::eic::pin::ExternalInterruptID;
+use crate::evsys::{generator, user, Evsys, UserId};
+use crate::target_device::tc0::count16::evctrl::EVACT_A;
+use crate::target_device::tc0::count32::evctrl::EVACT_A as EVACT32_A;
+#[allow(unused)]
+use crate::target_device::{TC2, TC3};
+// Only the G variants are missing these timers
+#[cfg(feature = "min-samd51j")]
+use crate::target_device::{TC4, TC5};
+use crate::time::{Hertz, Microseconds};
+
+/// Assignment of the period and pulse width to the capture channels
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaptureMode {
+    /// Capture the period into CC0 and the pulse width into CC1
+    PeriodPulseWidth,
+    /// Capture the pulse width into CC0 and the period into CC1
+    PulseWidthPeriod,
+}
+
+impl From<CaptureMode> for EVACT_A {
+    fn from(mode: CaptureMode) -> Self {
+        match mode {
+            CaptureMode::PeriodPulseWidth => EVACT_A::PPW,
+            CaptureMode::PulseWidthPeriod => EVACT_A::PWP,
+        }
+    }
+}
+
+impl From<CaptureMode> for EVACT32_A {
+    fn from(mode: CaptureMode) -> Self {
+        match mode {
+            CaptureMode::PeriodPulseWidth => EVACT32_A::PPW,
+            CaptureMode::PulseWidthPeriod => EVACT32_A::PWP,
+        }
+    }
+}
+
+/// Errors that can occur during a measurement
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The counter overflowed
+    ///
+    /// The signal stopped, or its period is too long for the prescaler
+    /// divider.
+    Overflow,
+    /// A new capture happened before the previous one was read
+    Overrun,
+}
+
+/// A period and pulse width measurement
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Measurement {
+    period: u32,
+    pulse_width: u32,
+    tick_freq: Hertz,
+}
+
+impl Measurement {
+    /// Returns the period, in timer ticks
+    #[inline]
+    pub fn period_ticks(&self) -> u32 {
+        self.period
+    }
+
+    /// Returns the pulse width, in timer ticks
+    #[inline]
+    pub fn pulse_width_ticks(&self) -> u32 {
+        self.pulse_width
+    }
+
+    /// Returns the period
+    #[inline]
+    pub fn period(&self) -> Microseconds {
+        self.ticks_to_us(self.period)
+    }
+
+    /// Returns the pulse width
+    #[inline]
+    pub fn pulse_width(&self) -> Microseconds {
+        self.ticks_to_us(self.pulse_width)
+    }
+
+    /// Returns the frequency of the signal, rounded down
+    ///
+    /// Returns zero if the period is zero.
+    #[inline]
+    pub fn frequency(&self) -> Hertz {
+        Hertz(self.tick_freq.0.checked_div(self.period).unwrap_or(0))
+    }
+
+    /// Returns the duty cycle of the signal, from 0 to 1
+    ///
+    /// Returns zero if the period is zero.
+    #[inline]
+    pub fn duty_cycle(&self) -> f32 {
+        if self.period == 0 {
+            0.0
+        } else {
+            self.pulse_width as f32 / self.period as f32
+        }
+    }
+
+    #[inline]
+    fn ticks_to_us(&self, ticks: u32) -> Microseconds {
+        Microseconds((ticks as u64 * 1_000_000 / self.tick_freq.0 as u64) as u32)
+    }
+}
+
+/// A TC which can capture events from the EVSYS
+pub trait CaptureTc: Count16 {
+    /// EVSYS user ID of the TC event input
+    const EVSYS_USER: UserId;
+}
+
+/// A TC configured for input capture
+///
+/// See the [module-level](self) documentation for more details.
+pub struct TcCapture<TC: Count16> {
+    timer: TimerCounter<TC>,
+    mode: CaptureMode,
+    tick_freq: Hertz,
+}
+
+impl<TC: CaptureTc> TimerCounter<TC> {
+    /// Reconfigure the timer for input capture
+    ///
+    /// The counter runs at the TC clock frequency divided by `divider`. The
+    /// run in standby and on demand settings of the timer are kept.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `divider` is not one of 1, 2, 4, 8, 16, 64, 256 or 1024.
+    pub fn into_capture(self, mode: CaptureMode, divider: u16) -> TcCapture<TC> {
+        let count = self.tc.count_16();
+
+        // Disable the timer while we reconfigure it
+        count.ctrla.modify(|_, w| w.enable().clear_bit());
+        while count.syncbusy.read().enable().bit_is_set() {}
+
+        count.ctrla.write(|w| w.swrst().set_bit());
+        while count.syncbusy.read().swrst().bit_is_set() {}
+
+        count.evctrl.write(|w| {
+            w.tcei().set_bit();
+            w.evact().variant(mode.into())
+        });
+
+        count.ctrla.modify(|_, w| {
+            match divider {
+                1 => w.prescaler().div1(),
+                2 => w.prescaler().div2(),
+                4 => w.prescaler().div4(),
+                8 => w.prescaler().div8(),
+                16 => w.prescaler().div16(),
+                64 => w.prescaler().div64(),
+                256 => w.prescaler().div256(),
+                1024 => w.prescaler().div1024(),
+                _ => panic!("invalid TC prescaler divider"),
+            };
+            w.capten0().set_bit();
+            w.capten1().set_bit();
+            w.runstdby().bit(self.run_standby);
+            w.ondemand().bit(self.on_demand);
+            w.enable().set_bit()
+        });
+        while count.syncbusy.read().enable().bit_is_set() {}
+
+        let tick_freq = Hertz(self.freq.0 / divider as u32);
+        TcCapture {
+            timer: self,
+            mode,
+            tick_freq,
+        }
+    }
+}
+
+impl<TC: CaptureTc> TcCapture<TC> {
+    /// Route the event of an EIC external interrupt to the TC through an
+    /// EVSYS channel
+    ///
+    /// The EIC pin must have its event output enabled.
+    #[inline]
+    pub fn connect_eic(&mut self, evsys: &mut Evsys, channel: u8, extint: ExternalInterruptID) {
+        evsys.connect(channel, generator::eic_extint(extint), TC::EVSYS_USER);
+    }
+
+    /// Returns the frequency of the counter
+    #[inline]
+    pub fn tick_freq(&self) -> Hertz {
+        self.tick_freq
+    }
+
+    /// Read the latest measurement
+    ///
+    /// Returns `WouldBlock` until both capture channels hold a new value.
+    pub fn read(&mut self) -> nb::Result<Measurement, Error> {
+        let count = self.timer.tc.count_16();
+        let flags = count.intflag.read();
+        if flags.ovf().bit_is_set() {
+            count.intflag.write(|w| w.ovf().set_bit());
+            return Err(nb::Error::Other(Error::Overflow));
+        }
+        if flags.err().bit_is_set() {
+            count.intflag.write(|w| w.err().set_bit());
+            return Err(nb::Error::Other(Error::Overrun));
+        }
+        // The pulse width is captured last, on the falling edge, into CC1 in
+        // PPW mode and into CC0 in PWP mode
+        let ready = match self.mode {
+            CaptureMode::PeriodPulseWidth => flags.mc1().bit_is_set(),
+            CaptureMode::PulseWidthPeriod => flags.mc0().bit_is_set(),
+        };
+        if !ready {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        // Reading the capture registers clears the MC flags
+        let cc0 = count.cc[0].read().cc().bits() as u32;
+        let cc1 = count.cc[1].read().cc().bits() as u32;
+        let (period, pulse_width) = match self.mode {
+            CaptureMode::PeriodPulseWidth => (cc0, cc1),
+            CaptureMode::PulseWidthPeriod => (cc1, cc0),
+        };
+        Ok(Measurement {
+            period,
+            pulse_width,
+            tick_freq: self.tick_freq,
+        })
+    }
+
+    /// Enable the interrupt raised when a new measurement is ready
+    ///
+    /// Overflows and overruns raise the interrupt too.
+    pub fn enable_interrupt(&mut self) {
+        self.timer.tc.count_16().intenset.write(|w| {
+            match self.mode {
+                CaptureMode::PeriodPulseWidth => w.mc1().set_bit(),
+                CaptureMode::PulseWidthPeriod => w.mc0().set_bit(),
+            };
+            w.ovf().set_bit().err().set_bit()
+        });
+    }
+
+    /// Disable the measurement interrupt
+    pub fn disable_interrupt(&mut self) {
+        self.timer.tc.count_16().intenclr.write(|w| {
+            w.mc0().set_bit();
+            w.mc1().set_bit();
+            w.ovf().set_bit().err().set_bit()
+        });
+    }
+
+    /// Stop capturing and return the timer
+    ///
+    /// The timer is disabled until it is started again.
+    pub fn into_timer(self) -> TimerCounter<TC> {
+        let count = self.timer.tc.count_16();
+        count.intenclr.write(|w| {
+            w.mc0().set_bit();
+            w.mc1().set_bit();
+            w.ovf().set_bit().err().set_bit()
+        });
+        count.ctrla.modify(|_, w| w.enable().clear_bit());
+        while count.syncbusy.read().enable().bit_is_set() {}
+        self.timer
+    }
+}
+
+/// A pair of TCs configured for 32-bit input capture
+///
+/// See the [module-level](self) documentation for more details.
+pub struct TcCapture32<TC: Count16, SLAVE: Count16> {
+    timer: TimerCounter32<TC, SLAVE>,
+    mode: CaptureMode,
+    tick_freq: Hertz,
+}
+
+impl<TC: CaptureTc + Count32, SLAVE: Count16> TimerCounter32<TC, SLAVE> {
+    /// Reconfigure the timer for input capture
+    ///
+    /// The counter runs at the TC clock frequency divided by `divider`. The
+    /// run in standby and on demand settings of the timer are kept.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `divider` is not one of 1, 2, 4, 8, 16, 64, 256 or 1024.
+    pub fn into_capture(self, mode: CaptureMode, divider: u16) -> TcCapture32<TC, SLAVE> {
+        let count = self.tc.count_32();
+
+        // Disable the timer while we reconfigure it
+        count.ctrla.modify(|_, w| w.enable().clear_bit());
+        while count.syncbusy.read().enable().bit_is_set() {}
+
+        count.ctrla.write(|w| w.swrst().set_bit());
+        while count.syncbusy.read().swrst().bit_is_set() {}
+
+        count.evctrl.write(|w| {
+            w.tcei().set_bit();
+            w.evact().variant(mode.into())
+        });
+
+        count.ctrla.write(|w| {
+            w.mode().count32();
+            match divider {
+                1 => w.prescaler().div1(),
+                2 => w.prescaler().div2(),
+                4 => w.prescaler().div4(),
+                8 => w.prescaler().div8(),
+                16 => w.prescaler().div16(),
+                64 => w.prescaler().div64(),
+                256 => w.prescaler().div256(),
+                1024 => w.prescaler().div1024(),
+                _ => panic!("invalid TC prescaler divider"),
+            };
+            w.capten0().set_bit();
+            w.capten1().set_bit();
+            w.runstdby().bit(self.run_standby);
+            w.ondemand().bit(self.on_demand);
+            w.enable().set_bit()
+        });
+        while count.syncbusy.read().enable().bit_is_set() {}
+
+        let tick_freq = Hertz(self.freq.0 / divider as u32);
+        TcCapture32 {
+            timer: self,
+            mode,
+            tick_freq,
+        }
+    }
+}
+
+impl<TC: CaptureTc + Count32, SLAVE: Count16> TcCapture32<TC, SLAVE> {
+    /// Route the event of an EIC external interrupt to the master TC through
+    /// an EVSYS channel
+    ///
+    /// The EIC pin must have its event output enabled.
+    #[inline]
+    pub fn connect_eic(&mut self, evsys: &mut Evsys, channel: u8, extint: ExternalInterruptID) {
+        evsys.connect(channel, generator::eic_extint(extint), TC::EVSYS_USER);
+    }
+
+    /// Returns the frequency of the counter
+    #[inline]
+    pub fn tick_freq(&self) -> Hertz {
+        self.tick_freq
+    }
+
+    /// Read the latest measurement
+    ///
+    /// Returns `WouldBlock` until both capture channels hold a new value.
+    pub fn read(&mut self) -> nb::Result<Measurement, Error> {
+        let count = self.timer.tc.count_32();
+        let flags = count.intflag.read();
+        if flags.ovf().bit_is_set() {
+            count.intflag.write(|w| w.ovf().set_bit());
+            return Err(nb::Error::Other(Error::Overflow));
+        }
+        if flags.err().bit_is_set() {
+            count.intflag.write(|w| w.err().set_bit());
+            return Err(nb::Error::Other(Error::Overrun));
+        }
+        // The pulse width is captured last, on the falling edge, into CC1 in
+        // PPW mode and into CC0 in PWP mode
+        let ready = match self.mode {
+            CaptureMode::PeriodPulseWidth => flags.mc1().bit_is_set(),
+            CaptureMode::PulseWidthPeriod => flags.mc0().bit_is_set(),
+        };
+        if !ready {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        // Reading the capture registers clears the MC flags
+        let cc0 = count.cc[0].read().cc().bits();
+        let cc1 = count.cc[1].read().cc().bits();
+        let (period, pulse_width) = match self.mode {
+            CaptureMode::PeriodPulseWidth => (cc0, cc1),
+            CaptureMode::PulseWidthPeriod => (cc1, cc0),
+        };
+        Ok(Measurement {
+            period,
+            pulse_width,
+            tick_freq: self.tick_freq,
+        })
+    }
+
+    /// Enable the interrupt raised when a new measurement is ready
+    ///
+    /// Overflows and overruns raise the interrupt too. The interrupt is
+    /// raised by the master TC.
+    pub fn enable_interrupt(&mut self) {
+        self.timer.tc.count_32().intenset.write(|w| {
+            match self.mode {
+                CaptureMode::PeriodPulseWidth => w.mc1().set_bit(),
+                CaptureMode::PulseWidthPeriod => w.mc0().set_bit(),
+            };
+            w.ovf().set_bit().err().set_bit()
+        });
+    }
+
+    /// Disable the measurement interrupt
+    pub fn disable_interrupt(&mut self) {
+        self.timer.tc.count_32().intenclr.write(|w| {
+            w.mc0().set_bit();
+            w.mc1().set_bit();
+            w.ovf().set_bit().err().set_bit()
+        });
+    }
+
+    /// Stop capturing and return the timer
+    ///
+    /// The timer is disabled until it is started again.
+    pub fn into_timer(self) -> TimerCounter32<TC, SLAVE> {
+        let count = self.timer.tc.count_32();
+        count.intenclr.write(|w| {
+            w.mc0().set_bit();
+            w.mc1().set_bit();
+            w.ovf().set_bit().err().set_bit()
+        });
+        count.ctrla.modify(|_, w| w.enable().clear_bit());
+        while count.syncbusy.read().enable().bit_is_set() {}
+        self.timer
+    }
+}
+
+macro_rules! capture_tc {
+    ($($TC:ident,)+) => {
+        $(
+impl CaptureTc for $TC {
+    const EVSYS_USER: UserId = user::$TC;
+}
+        )+
+    }
+}
+
+capture_tc! {
+    TC2,
+    TC3,
+}
+
+// Only the G variants are missing these timers
+#[cfg(feature = "min-samd51j")]
+capture_tc! {
+    TC4,
+    TC5,
+}
@@ -0,0 +1,476 @@
+//! # Monotonic timers
+//!
+//! Two 64-bit monotonic timers are provided, which count ticks since they were
+//! started and raise an interrupt at a scheduled instant:
+//!
+//! - [`TcMonotonic`] runs a [`TimerCounter32`] freely. The upper 32 bits are
+//!   counted in software on the overflow interrupt, and compare register 0
+//!   raises the interrupt at the scheduled instant. Like the TC, it can keep
+//!   counting in Standby with [`TimerCounter32::run_in_standby`].
+//! - [`DwtMonotonic`] counts CPU cycles with the DWT cycle counter, CYCCNT,
+//!   and schedules the interrupt with SysTick. It needs no TC, but both stop
+//!   with the CPU clock, so the CPU must not sleep while it is used. RTIC's
+//!   default idle task sleeps, so an RTIC app must provide its own, see
+//!   [`DwtMonotonic`]. The CPU frequency must not be scaled while it is used
+//!   either.
+//!
+//! With the `rtic` feature, both implement the RTIC `Monotonic` trait. Bind
+//! the interrupt of the master TC, or SysTick, to the monotonic, e.g.
+//! `#[monotonic(binds = TC2, default = true)]`.
+//!
+//! Without RTIC, call `on_interrupt` from the interrupt handler, and schedule
+//! wake-ups with `set_alarm`. Instants are plain tick counts, so the time
+//! between two instants is their difference divided by the frequency.
+//!
+//! ```no_run
+//! # use atsamd_hal::timer::TimerCounter2_3;
+//! # use atsamd_hal::timer::monotonic::TcMonotonic;
+//! # fn f(timer: TimerCounter2_3) {
+//! let mut mono = TcMonotonic::<_, _, 48_000_000>::new(timer);
+//! let start = mono.now();
+//! mono.set_alarm(start + 48_000);
+//! mono.enable_alarm();
+//! # }
+//! ```
+
+use super::{Count16, Count32, TimerCounter32};
+use crate::clock::scaling::{ClockDomain, Retime};
+use crate::time::Hertz;
+use cortex_m::peripheral::syst::SystClkSource;
+use cortex_m::peripheral::{DCB, DWT, SCB, SYST};
+
+/// A 64-bit monotonic timer running at `FREQ` Hz, built on a pair of TCs
+///
+/// See the [module-level](self) documentation for more details.
+pub struct TcMonotonic<TC: Count16, SLAVE: Count16, const FREQ: u32> {
+    timer: TimerCounter32<TC, SLAVE>,
+    overflows: u32,
+}
+
+impl<TC: Count32, SLAVE: Count16, const FREQ: u32> TcMonotonic<TC, SLAVE, FREQ> {
+    /// Start the monotonic timer from zero
+    ///
+    /// The counter runs without a prescaler up to its maximum value, and the
+    /// overflow interrupt is enabled. The interrupt of the master TC must also
+    /// be unmasked in the NVIC. The run in standby and on demand settings of
+    /// the timer are kept.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the TC clock frequency is not `FREQ`.
+    pub fn new(timer: TimerCounter32<TC, SLAVE>) -> Self {
+        assert_eq!(timer.freq.0, FREQ, "TC clock frequency mismatch");
+
+        let count = timer.tc.count_32();
+        count.ctrla.modify(|_, w| w.enable().clear_bit());
+        while count.syncbusy.read().enable().bit_is_set() {}
+        count.ctrla.write(|w| w.swrst().set_bit());
+        while count.syncbusy.read().swrst().bit_is_set() {}
+
+        // The waveform generation is reset to normal frequency, so the counter
+        // wraps around at its maximum value
+        count.ctrla.write(|w| {
+            w.mode().count32();
+            w.prescaler().div1();
+            w.runstdby().bit(timer.run_standby);
+            w.ondemand().bit(timer.on_demand);
+            w.enable().set_bit()
+        });
+        while count.syncbusy.read().enable().bit_is_set() {}
+
+        let mut mono = Self {
+            timer,
+            overflows: 0,
+        };
+        // SAFETY: The timer isn't shared with anything else
+        unsafe { mono.restart() };
+        mono
+    }
+
+    /// Reset the counter to zero and enable the overflow interrupt
+    ///
+    /// # Safety
+    ///
+    /// Instants taken before the reset are no longer comparable with new ones.
+    unsafe fn restart(&mut self) {
+        let count = self.timer.tc.count_32();
+        count.count.write(|w| w.count().bits(0));
+        while count.syncbusy.read().count().bit_is_set() {}
+        self.overflows = 0;
+        count.intflag.write(|w| w.ovf().set_bit().mc0().set_bit());
+        count.intenset.write(|w| w.ovf().set_bit());
+    }
+
+    /// Synchronize and read the counter
+    fn count(&self) -> u32 {
+        let count = self.timer.tc.count_32();
+        count.ctrlbset.write(|w| w.cmd().readsync());
+        while count.syncbusy.read().ctrlb().bit_is_set() {}
+        // The command is cleared once the counter has been synchronized
+        while count.ctrlbset.read().cmd().bits() != 0 {}
+        count.count.read().count().bits()
+    }
+
+    /// Returns the counting frequency
+    #[inline]
+    pub fn freq(&self) -> Hertz {
+        Hertz(FREQ)
+    }
+
+    /// Returns the current number of ticks
+    pub fn now(&mut self) -> u64 {
+        cortex_m::interrupt::free(|_| {
+            let mut count = self.count();
+            let mut overflows = self.overflows;
+            // The counter overflowed, but the interrupt hasn't been handled yet
+            if self.timer.tc.count_32().intflag.read().ovf().bit_is_set() {
+                overflows = overflows.wrapping_add(1);
+                // The overflow may have happened after reading the counter
+                count = self.count();
+            }
+            ((overflows as u64) << 32) | count as u64
+        })
+    }
+
+    /// Schedule the compare interrupt at `instant`, in ticks
+    ///
+    /// Only the lower 32 bits are compared, so an alarm more than 2^32 ticks
+    /// ahead fires early, and an alarm in the past only fires once the counter
+    /// wraps around. Check [`now`](Self::now) after setting the alarm and when
+    /// it fires.
+    pub fn set_alarm(&mut self, instant: u64) {
+        let count = self.timer.tc.count_32();
+        count.cc[0].write(|w| unsafe { w.cc().bits(instant as u32) });
+        while count.syncbusy.read().cc0().bit_is_set() {}
+    }
+
+    /// Clear the compare interrupt flag
+    #[inline]
+    pub fn clear_alarm(&mut self) {
+        self.timer
+            .tc
+            .count_32()
+            .intflag
+            .write(|w| w.mc0().set_bit());
+    }
+
+    /// Enable the compare interrupt
+    #[inline]
+    pub fn enable_alarm(&mut self) {
+        self.timer
+            .tc
+            .count_32()
+            .intenset
+            .write(|w| w.mc0().set_bit());
+    }
+
+    /// Disable the compare interrupt
+    #[inline]
+    pub fn disable_alarm(&mut self) {
+        self.timer
+            .tc
+            .count_32()
+            .intenclr
+            .write(|w| w.mc0().set_bit());
+    }
+
+    /// Handle the overflow interrupt
+    ///
+    /// Call this from the interrupt handler of the master TC. The compare
+    /// interrupt flag is left for the caller to clear.
+    pub fn on_interrupt(&mut self) {
+        let intflag = &self.timer.tc.count_32().intflag;
+        if intflag.read().ovf().bit_is_set() {
+            intflag.write(|w| w.ovf().set_bit());
+            self.overflows = self.overflows.wrapping_add(1);
+        }
+    }
+
+    /// Disable the interrupts and the timer, and return it
+    pub fn free(self) -> TimerCounter32<TC, SLAVE> {
+        let count = self.timer.tc.count_32();
+        count.intenclr.write(|w| w.ovf().set_bit().mc0().set_bit());
+        count.ctrla.modify(|_, w| w.enable().clear_bit());
+        while count.syncbusy.read().enable().bit_is_set() {}
+        self.timer
+    }
+}
+
+#[cfg(feature = "rtic")]
+impl<TC: Count32, SLAVE: Count16, const FREQ: u32> rtic_monotonic::Monotonic
+    for TcMonotonic<TC, SLAVE, FREQ>
+{
+    type Instant = fugit::TimerInstantU64<FREQ>;
+    type Duration = fugit::TimerDurationU64<FREQ>;
+
+    #[inline]
+    fn now(&mut self) -> Self::Instant {
+        Self::Instant::from_ticks(TcMonotonic::now(self))
+    }
+
+    #[inline]
+    fn zero() -> Self::Instant {
+        Self::Instant::from_ticks(0)
+    }
+
+    unsafe fn reset(&mut self) {
+        self.restart();
+    }
+
+    #[inline]
+    fn set_compare(&mut self, instant: Self::Instant) {
+        self.set_alarm(instant.ticks());
+    }
+
+    #[inline]
+    fn clear_compare_flag(&mut self) {
+        self.clear_alarm();
+    }
+
+    #[inline]
+    fn on_interrupt(&mut self) {
+        TcMonotonic::on_interrupt(self);
+    }
+
+    #[inline]
+    fn enable_timer(&mut self) {
+        self.enable_alarm();
+    }
+
+    #[inline]
+    fn disable_timer(&mut self) {
+        self.disable_alarm();
+    }
+}
+
+/// Largest SysTick reload value
+const SYST_MAX_RELOAD: u32 = 0x00FF_FFFF;
+
+/// A 64-bit monotonic timer counting CPU cycles at `FREQ` Hz
+///
+/// CYCCNT is extended to 64 bits in software, by counting its wrap-arounds
+/// each time the timer is read. SysTick keeps interrupting at least every 2^24
+/// cycles, also while the alarm is disabled, so that no wrap-around is missed.
+///
+/// # Sleep
+///
+/// CYCCNT and SysTick stop while the CPU sleeps, so time would stand still
+/// and alarms would be missed. [`new`](Self::new) disables sleep-on-exit, and
+/// the application must not execute `WFI` or `WFE` while the timer is used.
+/// With RTIC, the default idle task sleeps, so provide an idle task that
+/// doesn't:
+///
+/// ```ignore
+/// #[idle]
+/// fn idle(_: idle::Context) -> ! {
+///     loop {
+///         cortex_m::asm::nop();
+///     }
+/// }
+/// ```
+///
+/// # Frequency scaling
+///
+/// CYCCNT counts CPU cycles, at the frequency of GCLK0 divided by the MCLK
+/// CPUDIV prescaler. Instants are tick counts at the fixed frequency `FREQ`,
+/// so the CPU frequency must not change while the timer is used, neither with
+/// [`set_cpu_divider`] nor with [`reconfigure_gclk0`]. The timer implements
+/// [`Retime`], so pass it to them to catch such a change.
+///
+/// See the [module-level](self) documentation for more details.
+///
+/// [`set_cpu_divider`]: crate::clock::GenericClockController::set_cpu_divider
+/// [`reconfigure_gclk0`]: crate::clock::GenericClockController::reconfigure_gclk0
+pub struct DwtMonotonic<const FREQ: u32> {
+    dwt: DWT,
+    systick: SYST,
+    last: u32,
+    overflows: u32,
+}
+
+impl<const FREQ: u32> DwtMonotonic<FREQ> {
+    /// Start the monotonic timer from zero
+    ///
+    /// `sysclk` is the CPU clock frequency, i.e. the frequency of GCLK0
+    /// divided by the MCLK CPUDIV prescaler, as returned by
+    /// [`cpu_freq`](crate::clock::GenericClockController::cpu_freq). The
+    /// SysTick interrupt is enabled, and sleep-on-exit is disabled.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sysclk` is not `FREQ`.
+    pub fn new(
+        dcb: &mut DCB,
+        scb: &mut SCB,
+        mut dwt: DWT,
+        systick: SYST,
+        sysclk: impl Into<Hertz>,
+    ) -> Self {
+        assert_eq!(sysclk.into().0, FREQ, "CPU clock frequency mismatch");
+
+        // The cycle counter stops while the CPU sleeps
+        scb.clear_sleeponexit();
+        dcb.enable_trace();
+        dwt.enable_cycle_counter();
+
+        let mut mono = Self {
+            dwt,
+            systick,
+            last: 0,
+            overflows: 0,
+        };
+        // SAFETY: The DWT and SysTick aren't shared with anything else
+        unsafe { mono.restart() };
+        mono
+    }
+
+    /// Reset the counter to zero and start SysTick
+    ///
+    /// # Safety
+    ///
+    /// Instants taken before the reset are no longer comparable with new ones.
+    unsafe fn restart(&mut self) {
+        self.dwt.cyccnt.write(0);
+        self.last = 0;
+        self.overflows = 0;
+
+        self.systick.disable_counter();
+        self.systick.set_clock_source(SystClkSource::Core);
+        self.systick.set_reload(SYST_MAX_RELOAD);
+        self.systick.clear_current();
+        self.systick.enable_interrupt();
+        self.systick.enable_counter();
+    }
+
+    /// Returns the counting frequency
+    #[inline]
+    pub fn freq(&self) -> Hertz {
+        Hertz(FREQ)
+    }
+
+    /// Returns the current number of ticks
+    pub fn now(&mut self) -> u64 {
+        cortex_m::interrupt::free(|_| {
+            let count = self.dwt.cyccnt.read();
+            if count < self.last {
+                self.overflows = self.overflows.wrapping_add(1);
+            }
+            self.last = count;
+            ((self.overflows as u64) << 32) | count as u64
+        })
+    }
+
+    /// Schedule the SysTick interrupt at `instant`, in ticks
+    ///
+    /// SysTick counts at most 2^24 cycles, so an alarm further ahead fires
+    /// early, and an alarm in the past fires within two cycles. Check
+    /// [`now`](Self::now) when it fires.
+    pub fn set_alarm(&mut self, instant: u64) {
+        let ticks = instant.saturating_sub(self.now());
+        // SysTick wraps around every `reload + 1` cycles, and a reload value
+        // of 0 never raises the interrupt
+        let reload = ticks.saturating_sub(1).clamp(1, SYST_MAX_RELOAD as u64) as u32;
+        self.systick.set_reload(reload);
+        // Load the new value now, instead of on the next wrap-around
+        self.systick.clear_current();
+    }
+
+    /// Clear the SysTick interrupt flag
+    #[inline]
+    pub fn clear_alarm(&mut self) {
+        // Reading the flag clears it
+        self.systick.has_wrapped();
+    }
+
+    /// Enable the SysTick interrupt
+    #[inline]
+    pub fn enable_alarm(&mut self) {
+        self.systick.enable_interrupt();
+    }
+
+    /// Stop scheduling the SysTick interrupt
+    ///
+    /// The interrupt still fires every 2^24 cycles to track the CYCCNT
+    /// wrap-arounds.
+    #[inline]
+    pub fn disable_alarm(&mut self) {
+        self.systick.set_reload(SYST_MAX_RELOAD);
+    }
+
+    /// Handle the SysTick interrupt
+    ///
+    /// Call this from the SysTick exception handler. The interrupt flag is
+    /// left for the caller to clear.
+    #[inline]
+    pub fn on_interrupt(&mut self) {
+        self.now();
+    }
+
+    /// Stop SysTick and return the peripherals
+    pub fn free(mut self) -> (DWT, SYST) {
+        self.systick.disable_interrupt();
+        self.systick.disable_counter();
+        (self.dwt, self.systick)
+    }
+}
+
+impl<const FREQ: u32> Retime for DwtMonotonic<FREQ> {
+    #[inline]
+    fn clock_domain(&self) -> ClockDomain {
+        ClockDomain::Cpu
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the new CPU frequency is not `FREQ`, as the timer can't
+    /// follow the change.
+    #[inline]
+    fn retime(&mut self, _old: Hertz, new: Hertz) {
+        assert_eq!(new.0, FREQ, "CPU frequency changed under a DwtMonotonic");
+    }
+}
+
+#[cfg(feature = "rtic")]
+impl<const FREQ: u32> rtic_monotonic::Monotonic for DwtMonotonic<FREQ> {
+    type Instant = fugit::TimerInstantU64<FREQ>;
+    type Duration = fugit::TimerDurationU64<FREQ>;
+
+    #[inline]
+    fn now(&mut self) -> Self::Instant {
+        Self::Instant::from_ticks(DwtMonotonic::now(self))
+    }
+
+    #[inline]
+    fn zero() -> Self::Instant {
+        Self::Instant::from_ticks(0)
+    }
+
+    unsafe fn reset(&mut self) {
+        self.restart();
+    }
+
+    #[inline]
+    fn set_compare(&mut self, instant: Self::Instant) {
+        self.set_alarm(instant.ticks());
+    }
+
+    #[inline]
+    fn clear_compare_flag(&mut self) {
+        self.clear_alarm();
+    }
+
+    #[inline]
+    fn on_interrupt(&mut self) {
+        DwtMonotonic::on_interrupt(self);
+    }
+
+    #[inline]
+    fn enable_timer(&mut self) {
+        self.enable_alarm();
+    }
+
+    #[inline]
+    fn disable_timer(&mut self) {
+        self.disable_alarm();
+    }
+}
@@ -1,3 +1,4 @@
+use crate::clock::bus::{self, ApbClk};
 use crate::target_device::{MCLK, TRNG};
 
 use rand_core::{CryptoRng, RngCore};
@@ -5,13 +6,21 @@ use rand_core::{CryptoRng, RngCore};
 #[cfg(feature = "unproven")]
 use embedded_hal::blocking::rng::Read;
 
-pub struct Trng(TRNG);
+pub struct Trng(TRNG, ApbClk<bus::Trng>);
 
 impl Trng {
     pub fn new(mclk: &mut MCLK, trng: TRNG) -> Trng {
-        mclk.apbcmask.modify(|_, w| w.trng_().set_bit());
+        // SAFETY: We own the TRNG, so no other token exists for its clock
+        let apb_clk = unsafe { ApbClk::enable(mclk) };
         trng.ctrla.modify(|_, w| w.enable().set_bit());
-        Self(trng)
+        Self(trng, apb_clk)
+    }
+
+    /// Disable the TRNG, gate off its APB clock and return it
+    pub fn free(self) -> TRNG {
+        self.0.ctrla.modify(|_, w| w.enable().clear_bit());
+        self.1.disable();
+        self.0
     }
 
     pub fn random(&self, buf: &mut [u8]) {
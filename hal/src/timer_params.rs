@@ -1,5 +1,5 @@
 //! helper struct to calculate divider & cycles settings for timers.
-use crate::time::{Hertz, Nanoseconds};
+use crate::time::{Hertz, Microseconds, Nanoseconds};
 
 /// Helper type for computing cycles and divider given frequency
 #[derive(Debug, Clone, Copy)]
@@ -29,8 +29,41 @@ impl TimerParams {
         Self::new_from_ticks(ticks)
     }
 
+    /// calculates TimerParams for a 32-bit counter from a given period based
+    /// timeout.
+    ///
+    /// The number of ticks is computed in 64 bits. Timeouts that fit in 32
+    /// bits use a divider of 1, so the counter runs at the full resolution of
+    /// the clock. Longer timeouts use the smallest divider that fits, and
+    /// saturate beyond the largest one.
+    pub fn new_us_32<T>(timeout: T, src_freq: u32) -> Self
+    where
+        T: Into<Microseconds>,
+    {
+        let timeout = timeout.into();
+        let ticks = timeout.0 as u64 * src_freq as u64 / 1_000_000_u64;
+        let divider = Self::divider(ticks >> 32);
+        TimerParams {
+            divider,
+            cycles: (ticks / divider as u64).min(u32::MAX as u64) as u32,
+        }
+    }
+
     fn new_from_ticks(ticks: u32) -> Self {
-        let divider = ((ticks >> 16) + 1).next_power_of_two();
+        let divider = Self::divider(ticks as u64 >> 16);
+        let cycles: u32 = ticks / divider as u32;
+
+        if cycles > u16::max_value() as u32 {
+            panic!("cycles {} is out of range for a 16 bit counter", cycles);
+        }
+
+        TimerParams { divider, cycles }
+    }
+
+    /// Returns the smallest TC prescaler divider that brings `overflows`, the
+    /// number of times the ticks overflow the counter, down to zero
+    fn divider(overflows: u64) -> u16 {
+        let divider = (overflows + 1).next_power_of_two();
         let divider = match divider {
             1 | 2 | 4 | 8 | 16 | 64 | 256 | 1024 => divider,
             // There are a couple of gaps, so we round up to the next largest
@@ -42,17 +75,7 @@ impl TimerParams {
             // and fail at compile time.
             _ => 1024,
         };
-
-        let cycles: u32 = ticks / divider as u32;
-
-        if cycles > u16::max_value() as u32 {
-            panic!("cycles {} is out of range for a 16 bit counter", cycles);
-        }
-
-        TimerParams {
-            divider: divider as u16,
-            cycles,
-        }
+        divider as u16
     }
 }
 
@@ -80,4 +103,23 @@ mod tests {
         assert_eq!(tp_from_hz.divider, tp_from_us.divider);
         assert!((tp_from_hz.cycles as i32 - tp_from_us.cycles as i32).abs() <= 1);
     }
+
+    #[test]
+    fn timer_params_32_bit_full_resolution() {
+        // Out of range for a 16 bit counter, even with the largest divider
+        let tp = TimerParams::new_us_32(1_000_000_u32.us(), 120_000_000_u32);
+
+        assert_eq!(tp.divider, 1);
+        assert_eq!(tp.cycles, 120_000_000);
+    }
+
+    #[test]
+    fn timer_params_32_bit_long_timeout() {
+        // 60 s at 120 MHz is out of range for a 32 bit counter without a
+        // divider
+        let tp = TimerParams::new_us_32(60_u32.s(), 120_000_000_u32);
+
+        assert_eq!(tp.divider, 2);
+        assert_eq!(tp.cycles, 3_600_000_000);
+    }
 }